@@ -0,0 +1,18 @@
+#!/bin/sh
+# Build each feature profile and check it fits in the STM32F303x8's flash.
+# Requires cargo-binutils: `cargo install cargo-binutils && rustup component add llvm-tools-preview`
+set -e
+
+FLASH_SIZE=65536 # Bytes
+
+for profile in minimal standard deluxe; do
+    cargo build --release --no-default-features --features "$profile"
+    # text and data both live in flash
+    size=$(cargo size --release --no-default-features --features "$profile" -- -B \
+        | awk 'NR == 2 { print $1 + $2 }')
+    echo "$profile: $size of $FLASH_SIZE bytes"
+    if [ "$size" -gt "$FLASH_SIZE" ]; then
+        echo "$profile does not fit in flash" >&2
+        exit 1
+    fi
+done
@@ -0,0 +1,142 @@
+//! Times the render path on the device, so changes to rendering can't
+//! silently blow the frame budget.
+//!
+//! Each scene is the worst case of something shown in play: every LED lit,
+//! turned and dimmed, with a slide animation or a move preview running. Each
+//! is built many times and its slowest build is reported. Any scene over
+//! budget panics, which is reported over RTT.
+//!
+//! Run with:
+//! `cargo run --release --example render_bench`
+
+#![deny(unsafe_code)]
+#![no_std]
+#![no_main]
+
+use cortex_m::peripheral::DWT;
+use cortex_m_rt::entry;
+use mmxlviii::{
+    animation::{SlideAnimation, SLIDE_MILLIS},
+    board::{Board, Direction, IntoBoard},
+    game_board::GameBoard,
+    monotonic::Instant,
+    render::{Orientation, RenderContext},
+    score_board::ScoreBoard,
+    theme::{Palette, PaletteCache, Theme},
+};
+use panic_rtt_target as _;
+use rtt_target::{rprintln, rtt_init_print};
+use stm32f3xx_hal::{pac, prelude::*};
+
+const SYSCLK_FREQ: u32 = 48_000_000; // Hz
+const RENDER_BUDGET: u32 = SYSCLK_FREQ / 1000; // Cycles, as in the firmware
+const RUNS: u32 = 100;
+
+/// Get the most cycles any run of `render` took.
+/// The red of the first LED of each result is added to `sink`, so no render
+/// can be optimised away.
+fn worst_cycles(sink: &mut u32, mut render: impl FnMut() -> Board) -> u32 {
+    let mut worst = 0;
+    for _ in 0..RUNS {
+        let start = DWT::cycle_count();
+        let board = render();
+        let cycles = DWT::cycle_count().wrapping_sub(start);
+        *sink = sink.wrapping_add(u32::from(board.into_iter().next().map_or(0, |led| led.r)));
+        worst = worst.max(cycles);
+    }
+    worst
+}
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+
+    let mut cp = cortex_m::Peripherals::take().unwrap();
+    let dp = pac::Peripherals::take().unwrap();
+    cp.DCB.enable_trace();
+    cp.DWT.enable_cycle_counter();
+
+    let mut flash = dp.FLASH.constrain();
+    let rcc = dp.RCC.constrain();
+    rcc.cfgr
+        .sysclk(SYSCLK_FREQ.Hz().into())
+        .freeze(&mut flash.acr);
+
+    // Every LED lit, with the picture turned and dimmed as it is by the firmware
+    let mut palettes = PaletteCache::new();
+    let palette = Theme::Rainbow.palette(Palette::default());
+    let context = |millis| RenderContext {
+        palette,
+        brightness: 127,
+        orientation: Orientation::Clockwise270,
+        now: Instant::from_millis(millis),
+        ..RenderContext::default()
+    };
+    let board = GameBoard::with_tiles([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+    // Moving right slides every tile, half of them merging
+    let mergeable = GameBoard::with_tiles([1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8]);
+    let mut moved = mergeable.clone();
+    let outcome = moved.make_move(Direction::Right);
+    let animation = SlideAnimation::new(outcome, moved, Instant::from_millis(0));
+
+    let mut sink = 0;
+    let scenes: [(&str, u32); 6] = [
+        (
+            "palette",
+            worst_cycles(&mut sink, || {
+                let palette = palettes.palette(Theme::Rainbow, Palette::default());
+                Board::from_leds([palette.tile_colour(1); 16])
+            }),
+        ),
+        (
+            "uncached palette",
+            worst_cycles(&mut sink, || {
+                let palette = Theme::Rainbow.palette(Palette::default());
+                Board::from_leds([palette.tile_colour(1); 16])
+            }),
+        ),
+        (
+            "board",
+            worst_cycles(&mut sink, || board.render(&context(0))),
+        ),
+        (
+            "slide",
+            worst_cycles(&mut sink, || animation.render(&context(SLIDE_MILLIS / 2))),
+        ),
+        (
+            "preview",
+            worst_cycles(&mut sink, || {
+                // Shown dimmed, as while a move is held
+                let preview = mergeable.peek_move(Direction::Right).unwrap();
+                preview.render(&RenderContext {
+                    brightness: 63,
+                    ..context(0)
+                })
+            }),
+        ),
+        (
+            "score",
+            worst_cycles(&mut sink, || {
+                ScoreBoard::from_score(u32::MAX).render(&context(0))
+            }),
+        ),
+    ];
+
+    rprintln!("render budget {} cycles", RENDER_BUDGET);
+    let mut over = 0;
+    for &(name, cycles) in scenes.iter() {
+        let verdict = if cycles > RENDER_BUDGET { "OVER" } else { "ok" };
+        rprintln!("{}: worst {} cycles, {}", name, cycles, verdict);
+        if cycles > RENDER_BUDGET {
+            over += 1;
+        }
+    }
+    if over > 0 {
+        panic!("{} scenes over the render budget", over);
+    }
+    rprintln!("all scenes within budget, checksum {}", sink);
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
@@ -1,65 +1,606 @@
 #![no_std]
 #![no_main]
 
-use core::convert::TryInto;
+use core::{
+    cell::RefCell,
+    convert::TryInto,
+    mem,
+    sync::atomic::{AtomicU32, Ordering},
+};
 
 use panic_rtt_target as _;
 
-use cortex_m::interrupt;
+use cortex_m::{
+    interrupt::{self, Mutex},
+    peripheral::DWT,
+};
 use rtic::cyccnt::U32Ext;
-use rtt_target::{rprintln, rtt_init_print};
-use stm32f3::stm32f303::{Peripherals, EXTI, I2C1, SPI1};
+use rtt_target::{rprint, rprintln, rtt_init, set_print_channel, DownChannel, UpChannel};
+use stm32f3::stm32f303::{Peripherals, ADC1, EXTI, I2C1, SPI1};
 use stm32f3xx_hal::{
+    adc::{self, Adc},
     gpio::{
         gpioa,
         gpiob::{self, PB6, PB7},
         Alternate, Edge, Input, OpenDrain, Output, PushPull,
     },
+    hal::adc::{Channel, OneShot},
     i2c::I2c,
     prelude::*,
     spi::Spi,
 };
 
 use eeprom24x::{addr_size::OneByte, page_size::B16, Eeprom24x, SlaveAddr};
-use smart_leds::{brightness, SmartLedsWrite};
+use smart_leds::SmartLedsWrite;
 use ws2812_spi::Ws2812;
 
 use mmxlviii::{
+    animation::SlideAnimation,
+    autoplay::{Autoplay, Policy},
     board::{Direction, IntoBoard},
-    game_board::GameBoard,
+    calibration::{supply_millivolts, Calibration, CalibrationReport},
+    chain::Chain,
+    checksum::BoardChecksum,
+    clock::{Date, TimeOfDay},
+    console::{Command, LineBuffer},
+    device::{DeviceId, UID_SIZE},
+    diagnostics::{
+        paint_stack, unused_stack_words, LedWriteEvent, LedWriteHealth, RenderTimer, StackMonitor,
+        StatusLed, LED_WRITE_RETRIES,
+    },
+    entropy::{EntropyPool, SeedSequence},
+    error::Error,
+    frame_recorder::FrameRecorder,
+    game_board::{GameBoard, ScorePolicy},
+    game_stats::{self, GameStats},
+    grading::MoveGrade,
+    high_score::{self, HighScore, HighScoreTable, ScoreCategory},
+    input::{ButtonMap, Shortcut},
+    light_show::LightShow,
+    migration::{self, MigrationMarker, CURRENT_VERSION},
+    mirror::{encode_frame, Broadcast},
+    monotonic::{Instant, MonotonicClock},
+    patterns::{HintArrow, Icon},
+    provisioning::{self, Provisioning, Receiver},
+    puzzles::{self, PuzzleProgress},
+    render::{Orientation, RenderContext},
+    rng_state::{self, RngState},
+    running_stats::{self, RunningStats},
+    save::{self, Save},
     score_board::ScoreBoard,
+    score_clock::ScoreClock,
+    script::{Exhibition, Instruction, Script, ScriptReceiver},
+    settings::{self, Settings},
+    share::SharedBoard,
+    soak::{self, SoakTest},
+    speedrun::{self, PersonalBests, Speedrun},
+    status_panel::{render_move_count, StatusPanel, FACE_FRAMES},
+    telemetry::{self, Counters, Telemetry},
+    tempo::MoveLimiter,
+    theme::{Palette, PaletteCache},
+    thermal::{temperature_from_adc, ThermalEvent, ThermalThrottle},
+    tutorial::Tutorial,
+    ui::{Screen, UiState},
+};
+
+#[cfg(feature = "extra-games")]
+use mmxlviii::{
+    board_builder::GameBoardBuilder,
+    puzzles::{PuzzleRun, PuzzleStatus, PUZZLES},
+    speedrun::TICKS_PER_SECOND,
 };
 
+#[cfg(feature = "ai")]
+use mmxlviii::ai;
+#[cfg(feature = "god-mode")]
+use mmxlviii::{console::Effect, tile::Tile};
+
 type EepromScl = PB6<Alternate<OpenDrain, 4>>;
 type EepromSda = PB7<Alternate<OpenDrain, 4>>;
 type EepromI2c = I2c<I2C1, (EepromScl, EepromSda)>;
 type Eeprom = Eeprom24x<EepromI2c, B16, OneByte>;
 
 const SYSCLK_FREQ: u32 = 48_000_000; // Hz
-const UPDATE_PERIOD: u32 = SYSCLK_FREQ / 60; // Cycles
-const MOVE_RATE_LIMIT: u32 = SYSCLK_FREQ / 3; // Cycles
+const UPDATE_RATE: u32 = 60; // Hz
+const UPDATE_PERIOD: u32 = SYSCLK_FREQ / UPDATE_RATE; // Cycles
+const PREVIEW_DELAY: u32 = SYSCLK_FREQ / 4; // Cycles
+const TAP_PERIOD: u32 = SYSCLK_FREQ / 4; // Cycles A can be held for and still redo
 const BRIGHTNESS: u8 = 31; // Out of 255
+const PANELS: usize = 1; // 4x4 panels chained on the LED data line, 2 with a status panel
+const TEMPERATURE_PERIOD: u32 = SYSCLK_FREQ; // Cycles
+const CONSOLE_PERIOD: u32 = SYSCLK_FREQ / 20; // Cycles
+const TELEMETRY_PERIOD: u32 = SYSCLK_FREQ * 60; // Cycles
+const CLOCK_PERIOD: u32 = SYSCLK_FREQ; // Cycles
+const RENDER_BUDGET: u32 = SYSCLK_FREQ / 1000; // Cycles to build a frame
+const IDLE_TIMEOUT: u32 = 60 * 60; // Frames
+const DEMO_TIMEOUT: u32 = 5 * 60 * 60; // Frames
+const DEMO_MOVE_FRAMES: u32 = 30; // Frames between moves in demo mode
+const BOOT_PATTERN_FRAMES: u32 = 60; // Frames
+const ACHIEVEMENT_FRAMES: u32 = 2 * 60; // Frames the trophy flashes for
+const FRAME_HISTORY: usize = 8; // Frames
+const BROADCAST_FRAMES: u32 = 2; // Frames between spectator broadcasts
+const ENTROPY_SAMPLES: usize = 8192; // Samples
+#[cfg(feature = "god-mode")]
+const WIN_TILE: Tile = Tile::from_exponent(11); // 2048
 
 const PAGE_SIZE: usize = 16;
-const DATA_SIZE: usize = 2 * PAGE_SIZE;
 const MEMORY_BASE: u32 = 0x00;
+const SETTINGS_BASE: u32 = 0x40;
+const PUZZLES_BASE: u32 = 0x60;
+const PROVISIONING_BASE: u32 = 0x80;
+const PERSONAL_BESTS_BASE: u32 = 0x100;
+const TELEMETRY_BASE: u32 = 0x120;
+const MIGRATION_BASE: u32 = 0x140;
+/// The single high score kept before they were split by variant, only read by migration.
+const HIGH_SCORE_BASE: u32 = 0x150;
+/// Implausible saves are moved here instead of being resumed.
+const RECOVERY_BASE: u32 = 0x160;
+const HIGH_SCORES_BASE: u32 = 0x1A0;
+const STATS_BASE: u32 = 0x1D0;
+const RUNNING_STATS_BASE: u32 = 0x1F0;
+/// Where the game's random number generator is, written along with every save.
+const RNG_STATE_BASE: u32 = 0x210;
+
+/// Bytes left unpainted below the stack pointer, for the painting itself.
+const STACK_PAINT_MARGIN: usize = 256;
+
+/// Tasks whose stack use is tracked, in the order they're reported.
+const TRACKED_TASKS: [&str; 4] = ["make_move", "update", "check_temperature", "poll_console"];
+const MAKE_MOVE_TASK: usize = 0;
+const UPDATE_TASK: usize = 1;
+const CHECK_TEMPERATURE_TASK: usize = 2;
+const POLL_CONSOLE_TASK: usize = 3;
+
+static STACK_MONITOR: StackMonitor<{ TRACKED_TASKS.len() }> = StackMonitor::new();
+
+/// Milliseconds since boot, counted from the cycle counter.
+/// The update task reads it every frame, well within the counter's 89 second wrap.
+static CLOCK: Mutex<RefCell<MonotonicClock>> =
+    Mutex::new(RefCell::new(MonotonicClock::new(SYSCLK_FREQ / 1000)));
+
+extern "C" {
+    /// End of statically allocated RAM, from cortex-m-rt. The stack can grow down to here.
+    static mut __sheap: u32;
+    /// Top of the stack, from cortex-m-rt.
+    static _stack_start: u32;
+}
+
+/// Address of the optional LIS3DH accelerometer, on the EEPROM's I2C bus.
+const ACCELEROMETER_ADDRESS: u8 = 0x19;
+const ACCELEROMETER_WHO_AM_I: u8 = 0x0F;
+const ACCELEROMETER_ID: u8 = 0x33;
+const ACCELEROMETER_CTRL_REG1: u8 = 0x20;
+/// Sample at 100 Hz with every axis enabled.
+const ACCELEROMETER_ENABLE: u8 = 0x57;
+/// Start of the X, Y and Z readings, with the top bit set to read them in one go.
+const ACCELEROMETER_OUT: u8 = 0x28 | 0x80;
+
+/// Address of the MCU's 96-bit unique device ID.
+const UID_ADDRESS: usize = 0x1FFF_F7AC;
+/// Address of the internal voltage reference's reading at 3.3 V, taken in the factory.
+const VREFINT_CAL_ADDRESS: usize = 0x1FFF_F7BA;
+
+/// Get the range of RAM the stack may grow into, from the end of statics to the stack pointer.
+fn free_stack() -> &'static mut [u32] {
+    let stack_pointer = cortex_m::register::msp::read() as usize;
+    // Safety: nothing is allocated between the end of statics and the stack pointer.
+    unsafe {
+        let bottom = &mut __sheap as *mut u32;
+        let length = (stack_pointer - STACK_PAINT_MARGIN - bottom as usize) / 4;
+        core::slice::from_raw_parts_mut(bottom, length)
+    }
+}
+
+/// Get the deepest the stack has ever been, in bytes.
+fn stack_high_water() -> u32 {
+    let unused = unused_stack_words(free_stack());
+    // Safety: only the address of the linker symbols is used.
+    let (bottom, top) = unsafe {
+        (
+            &__sheap as *const u32 as u32,
+            &_stack_start as *const u32 as u32,
+        )
+    };
+    top - bottom - (unused * 4) as u32
+}
 
-fn read_board_from_eeprom(eeprom: &mut Eeprom) -> Option<GameBoard> {
-    let mut bytes = [0; DATA_SIZE];
-    eeprom.read_data(MEMORY_BASE, &mut bytes).ok();
-    eeprom
-        .read_data(MEMORY_BASE + PAGE_SIZE as u32, &mut bytes[PAGE_SIZE..])
-        .ok();
+/// Record the stack pointer on entry to a tracked task.
+fn record_stack(task: usize) {
+    STACK_MONITOR.record(task, cortex_m::register::msp::read());
+}
+
+/// Number of failed EEPROM reads and writes since boot, reported by soak tests.
+static STORAGE_ERRORS: AtomicU32 = AtomicU32::new(0);
+
+/// Count a failed EEPROM access, turning it into a storage error.
+fn count_storage_error<E>(_: E) -> Error {
+    STORAGE_ERRORS.fetch_add(1, Ordering::Relaxed);
+    Error::Storage
+}
+
+/// Get the time since boot. Safe to call from any task or interrupt.
+fn now() -> Instant {
+    interrupt::free(|cs| CLOCK.borrow(cs).borrow_mut().now(DWT::cycle_count()))
+}
+
+/// Read the MCU's unique device ID.
+fn read_uid() -> [u8; UID_SIZE] {
+    // Safety: the unique device ID is always readable on the STM32F303.
+    unsafe { core::ptr::read_volatile(UID_ADDRESS as *const [u8; UID_SIZE]) }
+}
+
+/// How the game plays itself in demo mode, until another strategy is chosen
+/// with the `demo` console command.
+#[cfg(feature = "ai")]
+const DEMO_POLICY: Policy = ai::preferred_move;
+#[cfg(not(feature = "ai"))]
+const DEMO_POLICY: Policy = GameBoard::best_move;
+
+/// Start a new game for demo mode, seeded from the cycle counter as the demo needn't be fair.
+fn new_demo(policy: Policy) -> Autoplay {
+    let seed = u64::from(DWT::cycle_count());
+    Autoplay::new(GameBoard::new_game_with_seed(seed), policy)
+}
+
+/// The exhibition demo stored in flash, run by holding A at boot or with the
+/// `exhibit` console command.
+const EXHIBITION_SCRIPT: &[Instruction] = &[
+    Instruction::Icon(Icon::Trophy),
+    Instruction::Wait(120),
+    Instruction::NewGame(2048),
+    Instruction::Wait(60),
+    Instruction::Move(Direction::Right),
+    Instruction::Wait(20),
+    Instruction::Move(Direction::Down),
+    Instruction::Wait(20),
+    Instruction::Move(Direction::Right),
+    Instruction::Wait(20),
+    Instruction::Move(Direction::Down),
+    Instruction::Wait(20),
+    Instruction::Move(Direction::Left),
+    Instruction::Wait(20),
+    Instruction::Move(Direction::Down),
+    Instruction::Wait(20),
+    Instruction::Move(Direction::Right),
+    Instruction::Wait(20),
+    Instruction::Move(Direction::Down),
+    Instruction::Wait(60),
+    Instruction::Number(2048),
+    Instruction::Wait(240),
+    Instruction::Loop,
+];
+
+/// Start the exhibition demo stored in flash.
+fn builtin_exhibition() -> Exhibition {
+    // The built-in script is short enough to always fit
+    Exhibition::new(Script::from_instructions(EXHIBITION_SCRIPT).unwrap())
+}
+
+/// Read the factory calibration of the internal voltage reference.
+fn read_reference_calibration() -> u16 {
+    // Safety: the calibration values are always readable on the STM32F303.
+    unsafe { core::ptr::read_volatile(VREFINT_CAL_ADDRESS as *const u16) }
+}
 
-    GameBoard::from_bytes(&bytes)
+/// Read bytes from the EEPROM one page at a time.
+fn read_from_eeprom(eeprom: &mut Eeprom, address: u32, bytes: &mut [u8]) -> Result<(), Error> {
+    for (page, chunk) in bytes.chunks_mut(PAGE_SIZE).enumerate() {
+        eeprom
+            .read_data(address + (page * PAGE_SIZE) as u32, chunk)
+            .map_err(count_storage_error)?;
+    }
+    Ok(())
 }
 
-fn write_board_to_eeprom(eeprom: &mut Eeprom, board: &GameBoard) {
-    let mut bytes = board.to_bytes();
-    eeprom.write_page(MEMORY_BASE, &mut bytes[..PAGE_SIZE]).ok();
-    eeprom
-        .write_page(MEMORY_BASE + PAGE_SIZE as u32, &mut bytes[PAGE_SIZE..])
-        .ok();
+/// Write bytes to the EEPROM one page at a time.
+fn write_to_eeprom(eeprom: &mut Eeprom, address: u32, bytes: &[u8]) -> Result<(), Error> {
+    for (page, chunk) in bytes.chunks(PAGE_SIZE).enumerate() {
+        eeprom
+            .write_page(address + (page * PAGE_SIZE) as u32, chunk)
+            .map_err(count_storage_error)?;
+    }
+    Ok(())
+}
+
+/// Run one migration step on the data in the EEPROM.
+/// Steps are repeated if the power is lost part way through, so each must be safe to run again.
+fn run_migration_step(eeprom: &mut Eeprom, step: u16) -> Result<(), Error> {
+    match step {
+        // Saves used to hold only the board, so rewrite them with the UI state and grade
+        1 => match read_save_from_eeprom(eeprom) {
+            Ok(save) => write_save_to_eeprom(eeprom, &save.board, &save.ui, &save.grade),
+            // Nothing worth keeping, a new game will be saved instead
+            Err(_) => Ok(()),
+        },
+        // High scores used to be shared by every variant, so move the old one to its own
+        2 => match read_high_score_from_eeprom(eeprom) {
+            Ok(high_score) => {
+                write_high_scores_to_eeprom(eeprom, &HighScoreTable::from_legacy(high_score))
+            }
+            Err(_) => Ok(()),
+        },
+        // High score categories used to have no time attack flag, so rewrite them with it
+        3 => {
+            let mut bytes = [0; high_score::TABLE_BYTES_SIZE];
+            read_from_eeprom(eeprom, HIGH_SCORES_BASE, &mut bytes)?;
+            match HighScoreTable::from_untimed_bytes(&bytes) {
+                Ok(table) => write_high_scores_to_eeprom(eeprom, &table),
+                Err(_) => Ok(()),
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Bring the data in the EEPROM up to date after a firmware update, one step at a time.
+fn run_migrations(eeprom: &mut Eeprom) -> Result<(), Error> {
+    let mut bytes = [0; migration::BYTES_SIZE];
+    read_from_eeprom(eeprom, MIGRATION_BASE, &mut bytes)?;
+    let mut marker = MigrationMarker::from_bytes(&bytes);
+    while let Some(step) = marker.next_step(CURRENT_VERSION) {
+        marker.begin(step);
+        write_to_eeprom(eeprom, MIGRATION_BASE, &marker.to_bytes())?;
+        run_migration_step(eeprom, step)?;
+        marker.complete(step);
+        write_to_eeprom(eeprom, MIGRATION_BASE, &marker.to_bytes())?;
+        rprintln!("Migrated data to version {}", step);
+    }
+    Ok(())
+}
+
+/// Work out which way up the board is, if an accelerometer is fitted.
+/// It's only read once, at boot, and powered down again afterwards.
+fn detect_orientation(i2c: &mut EepromI2c) -> Option<Orientation> {
+    let mut id = [0];
+    i2c.write_read(ACCELEROMETER_ADDRESS, &[ACCELEROMETER_WHO_AM_I], &mut id)
+        .ok()?;
+    if id[0] != ACCELEROMETER_ID {
+        return None;
+    }
+    i2c.write(
+        ACCELEROMETER_ADDRESS,
+        &[ACCELEROMETER_CTRL_REG1, ACCELEROMETER_ENABLE],
+    )
+    .ok()?;
+    // Wait for a few samples, so the reading has settled
+    cortex_m::asm::delay(SYSCLK_FREQ / 20);
+    let mut bytes = [0; 6];
+    let reading = i2c.write_read(ACCELEROMETER_ADDRESS, &[ACCELEROMETER_OUT], &mut bytes);
+    let _ = i2c.write(ACCELEROMETER_ADDRESS, &[ACCELEROMETER_CTRL_REG1, 0]);
+    reading.ok()?;
+    let axis = |index: usize| i16::from_le_bytes([bytes[index], bytes[index + 1]]);
+    Orientation::from_gravity(axis(0), axis(2), axis(4))
+}
+
+/// Get which way up the board is: as chosen in the settings, otherwise as detected at boot.
+fn orientation(settings: &Settings, detected: Option<Orientation>) -> Orientation {
+    settings.orientation().or(detected).unwrap_or_default()
+}
+
+fn read_save_from_eeprom(eeprom: &mut Eeprom) -> Result<Save, Error> {
+    let mut bytes = [0; save::BYTES_SIZE];
+    read_from_eeprom(eeprom, MEMORY_BASE, &mut bytes)?;
+    Save::from_bytes(&bytes)
+}
+
+/// Copy the save into the recovery slot, where it's kept for inspection but never loaded.
+fn quarantine_save(eeprom: &mut Eeprom) -> Result<(), Error> {
+    let mut bytes = [0; save::BYTES_SIZE];
+    read_from_eeprom(eeprom, MEMORY_BASE, &mut bytes)?;
+    write_to_eeprom(eeprom, RECOVERY_BASE, &bytes)
+}
+
+fn write_save_to_eeprom(
+    eeprom: &mut Eeprom,
+    board: &GameBoard,
+    ui: &UiState,
+    grade: &MoveGrade,
+) -> Result<(), Error> {
+    let save = Save::encode(board, ui, grade)?;
+    write_to_eeprom(eeprom, MEMORY_BASE, &save)?;
+    // A game whose state can't be saved erases the last one, so it isn't
+    // restored into this game
+    let state = board
+        .rng_state()
+        .map_or(rng_state::ERASED, |state| state.to_bytes(&save));
+    write_to_eeprom(eeprom, RNG_STATE_BASE, &state)
+}
+
+/// Read the random number generator's state, if it was written with the save in storage.
+fn read_rng_state_from_eeprom(eeprom: &mut Eeprom) -> Result<RngState, Error> {
+    let mut save = [0; save::BYTES_SIZE];
+    read_from_eeprom(eeprom, MEMORY_BASE, &mut save)?;
+    let mut bytes = [0; rng_state::BYTES_SIZE];
+    read_from_eeprom(eeprom, RNG_STATE_BASE, &mut bytes)?;
+    RngState::from_bytes(&bytes, &save)
+}
+
+fn read_settings_from_eeprom(eeprom: &mut Eeprom) -> Result<Settings, Error> {
+    let mut bytes = [0; settings::BYTES_SIZE];
+    read_from_eeprom(eeprom, SETTINGS_BASE, &mut bytes)?;
+    Settings::from_bytes(&bytes)
+}
+
+fn write_settings_to_eeprom(eeprom: &mut Eeprom, settings: &Settings) -> Result<(), Error> {
+    write_to_eeprom(eeprom, SETTINGS_BASE, &settings.to_bytes()?)
+}
+
+fn read_puzzle_progress_from_eeprom(eeprom: &mut Eeprom) -> Result<PuzzleProgress, Error> {
+    let mut bytes = [0; puzzles::PROGRESS_BYTES_SIZE];
+    read_from_eeprom(eeprom, PUZZLES_BASE, &mut bytes)?;
+    PuzzleProgress::from_bytes(&bytes)
+}
+
+#[cfg(feature = "extra-games")]
+fn write_puzzle_progress_to_eeprom(
+    eeprom: &mut Eeprom,
+    progress: &PuzzleProgress,
+) -> Result<(), Error> {
+    write_to_eeprom(eeprom, PUZZLES_BASE, &progress.to_bytes()?)
+}
+
+fn read_personal_bests_from_eeprom(eeprom: &mut Eeprom) -> Result<PersonalBests, Error> {
+    let mut bytes = [0; speedrun::BYTES_SIZE];
+    read_from_eeprom(eeprom, PERSONAL_BESTS_BASE, &mut bytes)?;
+    PersonalBests::from_bytes(&bytes)
+}
+
+#[cfg(feature = "extra-games")]
+fn write_personal_bests_to_eeprom(eeprom: &mut Eeprom, bests: &PersonalBests) -> Result<(), Error> {
+    write_to_eeprom(eeprom, PERSONAL_BESTS_BASE, &bests.to_bytes()?)
+}
+
+fn read_high_score_from_eeprom(eeprom: &mut Eeprom) -> Result<HighScore, Error> {
+    let mut bytes = [0; high_score::BYTES_SIZE];
+    read_from_eeprom(eeprom, HIGH_SCORE_BASE, &mut bytes)?;
+    HighScore::from_bytes(&bytes)
+}
+
+fn read_high_scores_from_eeprom(eeprom: &mut Eeprom) -> Result<HighScoreTable, Error> {
+    let mut bytes = [0; high_score::TABLE_BYTES_SIZE];
+    read_from_eeprom(eeprom, HIGH_SCORES_BASE, &mut bytes)?;
+    HighScoreTable::from_bytes(&bytes)
+}
+
+fn write_high_scores_to_eeprom(eeprom: &mut Eeprom, table: &HighScoreTable) -> Result<(), Error> {
+    write_to_eeprom(eeprom, HIGH_SCORES_BASE, &table.to_bytes()?)
+}
+
+fn read_counters_from_eeprom(eeprom: &mut Eeprom) -> Result<Counters, Error> {
+    let mut bytes = [0; telemetry::BYTES_SIZE];
+    read_from_eeprom(eeprom, TELEMETRY_BASE, &mut bytes)?;
+    Counters::from_bytes(&bytes)
+}
+
+fn write_counters_to_eeprom(eeprom: &mut Eeprom, counters: &Counters) -> Result<(), Error> {
+    write_to_eeprom(eeprom, TELEMETRY_BASE, &counters.to_bytes()?)
+}
+
+fn read_stats_from_eeprom(eeprom: &mut Eeprom) -> Result<GameStats, Error> {
+    let mut bytes = [0; game_stats::BYTES_SIZE];
+    read_from_eeprom(eeprom, STATS_BASE, &mut bytes)?;
+    GameStats::from_bytes(&bytes)
+}
+
+fn write_stats_to_eeprom(eeprom: &mut Eeprom, stats: &GameStats) -> Result<(), Error> {
+    write_to_eeprom(eeprom, STATS_BASE, &stats.to_bytes()?)
+}
+
+fn read_running_stats_from_eeprom(eeprom: &mut Eeprom) -> Result<RunningStats, Error> {
+    let mut bytes = [0; running_stats::BYTES_SIZE];
+    read_from_eeprom(eeprom, RUNNING_STATS_BASE, &mut bytes)?;
+    RunningStats::from_bytes(&bytes)
+}
+
+fn write_running_stats_to_eeprom(eeprom: &mut Eeprom, stats: &RunningStats) -> Result<(), Error> {
+    write_to_eeprom(eeprom, RUNNING_STATS_BASE, &stats.to_bytes()?)
+}
+
+fn read_provisioning_from_eeprom(eeprom: &mut Eeprom) -> Result<Provisioning, Error> {
+    let mut bytes = [0; provisioning::BYTES_SIZE];
+    read_from_eeprom(eeprom, PROVISIONING_BASE, &mut bytes)?;
+    Provisioning::from_bytes(&bytes)
+}
+
+/// Save the game and UI state, logging if it fails.
+fn save_game(eeprom: &mut Eeprom, board: &GameBoard, ui: &UiState, grade: &MoveGrade) {
+    if let Err(error) = write_save_to_eeprom(eeprom, board, ui, grade) {
+        rprintln!("Could not save game: {}", error);
+    }
+}
+
+/// Save the game after it changed in play, with the statistics saved
+/// alongside it so the two always agree.
+fn save_progress(
+    eeprom: &mut Eeprom,
+    board: &GameBoard,
+    ui: &UiState,
+    grade: &MoveGrade,
+    status: &mut StatusLed,
+) {
+    save_game(eeprom, board, ui, grade);
+    if let Err(error) = write_stats_to_eeprom(eeprom, board.stats()) {
+        rprintln!("Could not save statistics: {}", error);
+    }
+    status.on_save();
+}
+
+/// Record a finished game's high score, end any speedrun and show the game's
+/// statistics, whether the board filled up or a time attack ran out.
+fn end_game(
+    eeprom: &mut Eeprom,
+    board: &GameBoard,
+    ui: &mut UiState,
+    grade: &MoveGrade,
+    high_score: &mut HighScoreTable,
+    speedrun: &mut Option<Speedrun>,
+) {
+    rprintln!("Game over, accuracy {}%", grade.accuracy());
+    let category = ScoreCategory::of(ui.variant, board);
+    // Only classic scores are comparable with the high scores
+    let classic = board.score_policy() == ScorePolicy::Classic;
+    if classic && high_score.record(category, board) {
+        if let Err(error) = write_high_scores_to_eeprom(eeprom, high_score) {
+            rprintln!("Could not save high score: {}", error);
+        }
+    }
+    *speedrun = None;
+    ui.screen = Screen::Stats;
+}
+
+/// Replace the game with a new one, carrying on the statistics of those
+/// before it and the rules chosen for it: how undoing is limited, how many
+/// tiles are added, how merges are scored and any time limit.
+fn start_new_game(board: &mut GameBoard, seeds: &mut SeedSequence) {
+    start_game_with_seed(board, seeds.next_seed());
+}
+
+/// Replace the game with one from a chosen seed, as `start_new_game`.
+fn start_game_with_seed(board: &mut GameBoard, seed: u64) {
+    let previous = mem::replace(board, GameBoard::new_game_with_seed(seed));
+    board.set_stats(*previous.stats());
+    board.set_undo_policy(previous.undo_policy());
+    board.set_spawn_rate(previous.spawn_rate());
+    board.set_score_policy(previous.score_policy());
+    board.set_time_limit(previous.time_limit());
+}
+
+/// The MCU's internal temperature sensor, connected to ADC1 channel 16.
+struct TemperatureSensor;
+
+/// Collect a random seed from noise in the temperature sensor's readings.
+fn collect_seed(adc: &mut Adc<ADC1>, sensor: &mut TemperatureSensor) -> Result<u64, Error> {
+    let mut pool = EntropyPool::new();
+    for _ in 0..ENTROPY_SAMPLES {
+        let reading: Result<u16, _> = adc.read(sensor);
+        if let Ok(sample) = reading {
+            pool.push(sample)?;
+        }
+        if let Some(seed) = pool.seed() {
+            return Ok(seed);
+        }
+    }
+    Err(Error::EntropyHealth)
+}
+
+impl Channel<ADC1> for TemperatureSensor {
+    type ID = u8;
+
+    fn channel() -> u8 {
+        16
+    }
+}
+
+/// The MCU's internal voltage reference, connected to ADC1 channel 18.
+/// Measuring it against the supply gives the supply voltage.
+struct InternalReference;
+
+impl Channel<ADC1> for InternalReference {
+    type ID = u8;
+
+    fn channel() -> u8 {
+        18
+    }
 }
 
 #[rtic::app(
@@ -70,6 +611,47 @@ fn write_board_to_eeprom(eeprom: &mut Eeprom, board: &GameBoard) {
 const APP: () = {
     struct Resources {
         board: GameBoard,
+        ui: UiState,
+        grade: MoveGrade,
+        seeds: SeedSequence,
+        puzzle_progress: PuzzleProgress,
+        personal_bests: PersonalBests,
+        high_score: HighScoreTable,
+        running_stats: RunningStats,
+        telemetry: Telemetry,
+        status: StatusLed,
+        #[init(None)]
+        speedrun: Option<Speedrun>,
+        #[init(None)]
+        time: Option<TimeOfDay>,
+        #[init(0)]
+        idle_frames: u32,
+        settings: Settings,
+        palette: Palette,
+        button_map: ButtonMap,
+        device_id: DeviceId,
+        detected_orientation: Option<Orientation>,
+
+        console: DownChannel,
+        #[init(LineBuffer::new())]
+        line_buffer: LineBuffer,
+        #[init(None)]
+        provisioning_receiver: Option<Receiver>,
+        #[init(None)]
+        soak: Option<SoakTest>,
+        #[init(None)]
+        calibration: Option<Calibration>,
+        #[init(None)]
+        demo: Option<Autoplay>,
+        #[init(DEMO_POLICY)]
+        demo_policy: Policy,
+        exhibition: Option<Exhibition>,
+        #[init(None)]
+        script_receiver: Option<ScriptReceiver>,
+        #[init(None)]
+        light_show: Option<LightShow>,
+        #[init(LedWriteHealth::new())]
+        led_health: LedWriteHealth,
 
         exti: EXTI,
 
@@ -96,22 +678,85 @@ const APP: () = {
 
         eeprom: Eeprom,
 
-        #[init(true)]
-        is_move_allowed: bool,
+        adc: Adc<ADC1>,
+        temperature_sensor: TemperatureSensor,
+        #[init(ThermalThrottle::new())]
+        thermal_throttle: ThermalThrottle,
+
+        #[init(MoveLimiter::new())]
+        move_limiter: MoveLimiter,
+        #[init(None)]
+        pending_move: Option<Direction>,
+        #[init(None)]
+        held_direction: Option<Direction>,
+        #[init(None)]
+        a_pressed: Option<rtic::cyccnt::Instant>,
+        #[init(None)]
+        preview_direction: Option<Direction>,
+        #[init(None)]
+        animation: Option<SlideAnimation>,
+
+        #[init(BOOT_PATTERN_FRAMES)]
+        boot_frames: u32,
+        #[init(0)]
+        achievement_frames: u32,
+        #[init(0)]
+        frame: u32,
+
+        #[init(FrameRecorder::new())]
+        frame_recorder: FrameRecorder<FRAME_HISTORY>,
+        #[init(RenderTimer::new(RENDER_BUDGET))]
+        render_timer: RenderTimer,
+        #[init(PaletteCache::new())]
+        palette_cache: PaletteCache,
+
+        mirror_channel: UpChannel,
+        #[init(false)]
+        is_mirroring: bool,
+        #[init(false)]
+        is_spectating: bool,
     }
 
-    #[init(spawn = [update])]
+    #[init(spawn = [update, check_temperature, poll_console, save_telemetry, tick_clock])]
     fn init(cx: init::Context) -> init::LateResources {
-        rtt_init_print!();
+        paint_stack(free_stack());
+
+        let channels = rtt_init! {
+            up: {
+                0: {
+                    size: 1024
+                    name: "Terminal"
+                }
+                1: {
+                    size: 512
+                    name: "Mirror"
+                }
+            }
+            down: {
+                0: {
+                    size: 64
+                    name: "Terminal"
+                }
+            }
+        };
+        set_print_channel(channels.up.0);
         rprintln!("2048-hw");
 
+        let device_id = DeviceId::from_uid(&read_uid());
+        rprintln!("Device ID: {}", device_id);
+
         // Prepare our core and device peripherals
         let cp: rtic::Peripherals = cx.core;
-        let dp: Peripherals = cx.device;
+        let mut dp: Peripherals = cx.device;
 
         let mut dcb = cp.DCB;
         let mut dwt = cp.DWT;
         let mut flash = dp.FLASH.constrain();
+        // A watchdog reset means the firmware crashed or hung
+        let reset_flags = dp.RCC.csr.read();
+        let crashed = reset_flags.iwdgrstf().bit_is_set() || reset_flags.wwdgrstf().bit_is_set();
+        dp.RCC.csr.modify(|_, w| w.rmvf().set_bit());
+
         let mut rcc = dp.RCC.constrain();
         let mut syscfg = dp.SYSCFG.constrain(&mut rcc.apb2);
         let mut exti = dp.EXTI;
@@ -161,15 +806,32 @@ const APP: () = {
         scl.internal_pull_up(&mut gpiob.pupdr, true);
         sda.internal_pull_up(&mut gpiob.pupdr, true);
 
-        let i2c = I2c::new(
+        let mut i2c = I2c::new(
             dp.I2C1,
             (scl, sda),
             100.kHz().try_into().unwrap(),
             clocks,
             &mut rcc.apb1,
         );
+        // The accelerometer, if fitted, shares the bus and is only needed now
+        let detected_orientation = detect_orientation(&mut i2c);
+        if let Some(orientation) = detected_orientation {
+            rprintln!("Board detected as {:?}", orientation);
+        }
         let mut eeprom = Eeprom24x::new_24x08(i2c, SlaveAddr::Alternative(false, true, true));
 
+        // Enable the internal temperature sensor, the voltage reference, and the ADC used to read them
+        dp.ADC1_2
+            .ccr
+            .modify(|_, w| w.tsen().set_bit().vrefen().set_bit());
+        let mut adc = Adc::adc1(
+            dp.ADC1,
+            &mut dp.ADC1_2,
+            &mut rcc.ahb,
+            adc::CkMode::default(),
+            clocks,
+        );
+
         // Prepare other useful bits
         let status_led = gpioa
             .pa3
@@ -179,54 +841,165 @@ const APP: () = {
             .pa8
             .into_pull_up_input(&mut gpioa.moder, &mut gpioa.pupdr);
         up_pin.make_interrupt_source(&mut syscfg);
-        up_pin.trigger_on_edge(&mut exti, Edge::Rising);
+        up_pin.trigger_on_edge(&mut exti, Edge::RisingFalling);
         up_pin.enable_interrupt(&mut exti);
         let mut down_pin = gpioa
             .pa9
             .into_pull_up_input(&mut gpioa.moder, &mut gpioa.pupdr);
         down_pin.make_interrupt_source(&mut syscfg);
-        down_pin.trigger_on_edge(&mut exti, Edge::Rising);
+        down_pin.trigger_on_edge(&mut exti, Edge::RisingFalling);
         down_pin.enable_interrupt(&mut exti);
         let mut left_pin = gpiob
             .pb1
             .into_pull_up_input(&mut gpiob.moder, &mut gpiob.pupdr);
         left_pin.make_interrupt_source(&mut syscfg);
-        left_pin.trigger_on_edge(&mut exti, Edge::Rising);
+        left_pin.trigger_on_edge(&mut exti, Edge::RisingFalling);
         left_pin.enable_interrupt(&mut exti);
         let mut right_pin = gpiob
             .pb0
             .into_pull_up_input(&mut gpiob.moder, &mut gpiob.pupdr);
         right_pin.make_interrupt_source(&mut syscfg);
-        right_pin.trigger_on_edge(&mut exti, Edge::Rising);
+        right_pin.trigger_on_edge(&mut exti, Edge::RisingFalling);
         right_pin.enable_interrupt(&mut exti);
 
-        let a_pin = gpioa
+        let mut a_pin = gpioa
             .pa12
             .into_pull_up_input(&mut gpioa.moder, &mut gpioa.pupdr);
-        let b_pin = gpioa
+        a_pin.make_interrupt_source(&mut syscfg);
+        a_pin.trigger_on_edge(&mut exti, Edge::RisingFalling);
+        a_pin.enable_interrupt(&mut exti);
+        let mut b_pin = gpioa
             .pa11
             .into_pull_up_input(&mut gpioa.moder, &mut gpioa.pupdr);
+        b_pin.make_interrupt_source(&mut syscfg);
+        b_pin.trigger_on_edge(&mut exti, Edge::Falling);
+        b_pin.enable_interrupt(&mut exti);
 
         // TODO: Tidy when crates are up to date
         // Give the pull-ups time to stabilise. At 48 MHz, this takes ~5ms
         cortex_m::asm::delay(240000);
 
-        // Create/read the 2048 board
+        // Seed new games from sensor noise, so each boot plays differently
+        let seed = collect_seed(&mut adc, &mut TemperatureSensor).unwrap_or_else(|error| {
+            rprintln!("Could not collect entropy: {}", error);
+            u64::from(device_id.value())
+        });
+        let mut seeds = SeedSequence::new(seed);
+
+        // Data from older firmware must be updated before anything is loaded
+        if let Err(error) = run_migrations(&mut eeprom) {
+            rprintln!("Could not migrate data: {}", error);
+        }
+
+        // Create/read the 2048 board, and resume the UI where it was left
         let should_restart = b_pin.is_low().unwrap();
-        let loaded_data = read_board_from_eeprom(&mut eeprom);
-        let board = match (should_restart, loaded_data) {
-            (false, Some(board)) => board,
-            _ => {
-                let board = GameBoard::new_game();
-                write_board_to_eeprom(&mut eeprom, &board);
-                board
+        // Holding A instead runs the exhibition demo, for shows without a host
+        let exhibition = if a_pin.is_low().unwrap() {
+            Some(builtin_exhibition())
+        } else {
+            None
+        };
+        // Saves that decode but couldn't have been played are set aside, so
+        // they can't reach the high score, as are saves from newer firmware,
+        // so they aren't lost
+        let loaded_data = read_save_from_eeprom(&mut eeprom)
+            .and_then(|save| save.validate().map(|()| save))
+            .map_err(|error| {
+                if matches!(error, Error::SaveImplausible | Error::UnsupportedVersion) {
+                    if let Err(error) = quarantine_save(&mut eeprom) {
+                        rprintln!("Could not quarantine save: {}", error);
+                    }
+                }
+                error
+            });
+        let Save {
+            mut board,
+            mut ui,
+            grade,
+        } = match (should_restart, loaded_data) {
+            (false, Ok(save)) => save,
+            (_, loaded_data) => {
+                if let (false, Err(error)) = (should_restart, loaded_data) {
+                    rprintln!("Could not load game: {}", error);
+                }
+                let save = Save::new(
+                    GameBoard::new_game_with_seed(seeds.next_seed()),
+                    UiState::default(),
+                    MoveGrade::new(),
+                );
+                save_game(&mut eeprom, &save.board, &save.ui, &save.grade);
+                save
             }
         };
 
+        // The random number generator is saved on its own, so the game goes on
+        // adding the tiles it would have. Saves from before it was kept, or
+        // whose state is missing, stale or too long to replay, are reseeded.
+        if read_rng_state_from_eeprom(&mut eeprom)
+            .and_then(|state| board.restore_rng(state))
+            .is_err()
+        {
+            board.reseed(seeds.next_seed());
+        }
+        // The statistics aren't saved with the game either
+        board.set_stats(read_stats_from_eeprom(&mut eeprom).unwrap_or_default());
+
+        // Provisioned defaults are used until the user changes something
+        let provisioning = read_provisioning_from_eeprom(&mut eeprom).unwrap_or_default();
+        let settings = read_settings_from_eeprom(&mut eeprom).unwrap_or(provisioning.settings);
+        let puzzle_progress = read_puzzle_progress_from_eeprom(&mut eeprom).unwrap_or_default();
+        let personal_bests = read_personal_bests_from_eeprom(&mut eeprom).unwrap_or_default();
+        let high_score = read_high_scores_from_eeprom(&mut eeprom).unwrap_or_default();
+        let running_stats = read_running_stats_from_eeprom(&mut eeprom).unwrap_or_default();
+        let counters = read_counters_from_eeprom(&mut eeprom).unwrap_or_default();
+        let mut telemetry = Telemetry::new(counters, settings.is_telemetry());
+        let status = StatusLed::new(settings.status_led());
+        if crashed {
+            telemetry.record_crash();
+        }
+
+        // Speedrun timers don't survive a power cycle, so neither does their result
+        if ui.screen == Screen::Speedrun {
+            ui.screen = Screen::Game;
+        }
+        // The save may come from firmware with more features
+        #[cfg(not(feature = "extra-games"))]
+        if ui.screen == Screen::PuzzleSelect || ui.puzzle.is_some() {
+            ui.screen = Screen::Game;
+            ui.puzzle = None;
+        }
+
+        // New players are walked through the basics first
+        if !settings.is_tutorial_done() && ui.screen != Screen::Tutorial {
+            ui.screen = Screen::Tutorial;
+            ui.menu_item = 0;
+        }
+
         cx.spawn.update().unwrap();
+        cx.spawn.check_temperature().unwrap();
+        cx.spawn.poll_console().unwrap();
+        cx.spawn.save_telemetry().unwrap();
+        cx.spawn.tick_clock().unwrap();
 
         init::LateResources {
             board,
+            ui,
+            grade,
+            seeds,
+            puzzle_progress,
+            personal_bests,
+            high_score,
+            running_stats,
+            telemetry,
+            status,
+            settings,
+            palette: provisioning.palette,
+            button_map: provisioning.button_map,
+            device_id,
+            detected_orientation,
+            exhibition,
+            console: channels.down.0,
+            mirror_channel: channels.up.1,
             exti,
             status_led,
             up_pin,
@@ -237,6 +1010,8 @@ const APP: () = {
             b_pin,
             board_leds,
             eeprom,
+            adc,
+            temperature_sensor: TemperatureSensor,
         }
     }
 
@@ -244,13 +1019,17 @@ const APP: () = {
         priority = 3,
         binds = EXTI0,
         resources = [exti, right_pin],
-        spawn = [make_move]
+        spawn = [make_move, press_direction]
     )]
     fn exti0(cx: exti0::Context) {
         let pr = cx.resources.exti.pr1.read();
         if pr.pr0().is_pending() {
             cx.resources.right_pin.clear_interrupt_pending_bit();
-            let _ = cx.spawn.make_move(Direction::Right);
+            if cx.resources.right_pin.is_low().unwrap() {
+                let _ = cx.spawn.press_direction(Direction::Right);
+            } else {
+                let _ = cx.spawn.make_move(Direction::Right);
+            }
         }
     }
 
@@ -258,13 +1037,17 @@ const APP: () = {
         priority = 3,
         binds = EXTI1,
         resources = [exti, left_pin],
-        spawn = [make_move]
+        spawn = [make_move, press_direction]
     )]
     fn exti1(cx: exti1::Context) {
         let pr = cx.resources.exti.pr1.read();
         if pr.pr1().is_pending() {
             cx.resources.left_pin.clear_interrupt_pending_bit();
-            let _ = cx.spawn.make_move(Direction::Left);
+            if cx.resources.left_pin.is_low().unwrap() {
+                let _ = cx.spawn.press_direction(Direction::Left);
+            } else {
+                let _ = cx.spawn.make_move(Direction::Left);
+            }
         }
     }
 
@@ -272,82 +1055,1435 @@ const APP: () = {
         priority = 3,
         binds = EXTI9_5,
         resources = [exti, down_pin, up_pin],
-        spawn = [make_move]
+        spawn = [make_move, press_direction]
     )]
     fn exti9_5(cx: exti9_5::Context) {
         let pr = cx.resources.exti.pr1.read();
         if pr.pr9().is_pending() {
             cx.resources.down_pin.clear_interrupt_pending_bit();
-            let _ = cx.spawn.make_move(Direction::Down);
+            if cx.resources.down_pin.is_low().unwrap() {
+                let _ = cx.spawn.press_direction(Direction::Down);
+            } else {
+                let _ = cx.spawn.make_move(Direction::Down);
+            }
         } else if pr.pr8().is_pending() {
             cx.resources.up_pin.clear_interrupt_pending_bit();
-            let _ = cx.spawn.make_move(Direction::Up);
+            if cx.resources.up_pin.is_low().unwrap() {
+                let _ = cx.spawn.press_direction(Direction::Up);
+            } else {
+                let _ = cx.spawn.make_move(Direction::Up);
+            }
         }
     }
 
     #[task(
         priority = 3,
         binds = EXTI15_10,
-        resources = [exti, b_pin, status_led],
-        spawn = [make_move]
+        resources = [exti, a_pin, b_pin, a_pressed],
+        spawn = [shortcut, redo]
     )]
     fn exti15_10(cx: exti15_10::Context) {
         let pr = cx.resources.exti.pr1.read();
         if pr.pr11().is_pending() {
             cx.resources.b_pin.clear_interrupt_pending_bit();
-            cx.resources.status_led.toggle().unwrap();
+            // B while holding A asks for a hint rather than its shortcut
+            if cx.resources.a_pin.is_low().unwrap_or(false) {
+                *cx.resources.a_pressed = None;
+            } else {
+                let _ = cx.spawn.shortcut();
+            }
+        }
+        if pr.pr12().is_pending() {
+            cx.resources.a_pin.clear_interrupt_pending_bit();
+            if cx.resources.a_pin.is_low().unwrap_or(false) {
+                *cx.resources.a_pressed = Some(rtic::cyccnt::Instant::now());
+            } else if let Some(pressed) = cx.resources.a_pressed.take() {
+                // Only a quick tap of A on its own redoes, as A is also held
+                // to show the score and along with other buttons
+                if rtic::cyccnt::Instant::now().duration_since(pressed) < TAP_PERIOD.cycles() {
+                    let _ = cx.spawn.redo();
+                }
+            }
         }
     }
 
     #[task(
         priority = 2,
-        resources = [board, eeprom, is_move_allowed],
-        schedule = [allow_moves]
+        resources = [
+            board,
+            ui,
+            grade,
+            high_score,
+            telemetry,
+            idle_frames,
+            animation,
+            eeprom,
+            settings
+        ]
     )]
-    fn make_move(cx: make_move::Context, direction: Direction) {
-        if *cx.resources.is_move_allowed && cx.resources.board.make_move(direction) {
-            cx.resources.board.set_random();
-            *cx.resources.is_move_allowed = false;
-            cx.schedule
-                .allow_moves(cx.scheduled + MOVE_RATE_LIMIT.cycles())
-                .unwrap();
-            write_board_to_eeprom(cx.resources.eeprom, cx.resources.board)
+    fn shortcut(cx: shortcut::Context) {
+        // The first input after going idle only wakes the display
+        let was_idle = *cx.resources.idle_frames >= IDLE_TIMEOUT;
+        *cx.resources.idle_frames = 0;
+        if was_idle {
+            return;
+        }
+
+        let ui = &mut *cx.resources.ui;
+        match cx.resources.settings.shortcut() {
+            Shortcut::Undo => {
+                // Puzzles are scored on moves, so they can't be undone
+                if ui.screen != Screen::Game || ui.puzzle.is_some() || ui.paused {
+                    return;
+                }
+                if !cx.resources.board.undo() {
+                    return;
+                }
+
+                *cx.resources.animation = None;
+                cx.resources.telemetry.record_undo();
+                save_game(
+                    cx.resources.eeprom,
+                    cx.resources.board,
+                    ui,
+                    cx.resources.grade,
+                );
+            }
+            Shortcut::Theme => {
+                let settings = &mut *cx.resources.settings;
+                settings.set_theme(settings.theme().next());
+                if let Err(error) = write_settings_to_eeprom(cx.resources.eeprom, settings) {
+                    rprintln!("Could not save settings: {}", error);
+                }
+            }
+            Shortcut::Score => {
+                match ui.screen {
+                    Screen::Game => {
+                        // Start from the bests for the game being played
+                        let category = ScoreCategory::of(ui.variant, cx.resources.board);
+                        ui.menu_item =
+                            cx.resources.high_score.position(category).unwrap_or(0) as u8;
+                        ui.screen = Screen::Score;
+                    }
+                    Screen::Score => {
+                        ui.menu_item = 0;
+                        ui.screen = Screen::Game;
+                    }
+                    _ => {}
+                }
+            }
         }
     }
 
-    #[task(priority = 2, resources = [is_move_allowed])]
-    fn allow_moves(cx: allow_moves::Context) {
-        *cx.resources.is_move_allowed = true;
+    #[task(
+        priority = 2,
+        resources = [board, ui, grade, idle_frames, animation, eeprom]
+    )]
+    fn redo(cx: redo::Context) {
+        // The first input after going idle only wakes the display
+        let was_idle = *cx.resources.idle_frames >= IDLE_TIMEOUT;
+        *cx.resources.idle_frames = 0;
+        if was_idle {
+            return;
+        }
+
+        let ui = &*cx.resources.ui;
+        if ui.screen != Screen::Game || ui.puzzle.is_some() || ui.paused {
+            return;
+        }
+        if !cx.resources.board.redo() {
+            return;
+        }
+
+        *cx.resources.animation = None;
+        save_game(
+            cx.resources.eeprom,
+            cx.resources.board,
+            ui,
+            cx.resources.grade,
+        );
+    }
+
+    #[task(
+        priority = 2,
+        resources = [
+            board,
+            ui,
+            grade,
+            seeds,
+            puzzle_progress,
+            personal_bests,
+            high_score,
+            running_stats,
+            speedrun,
+            telemetry,
+            idle_frames,
+            status,
+            settings,
+            eeprom,
+            move_limiter,
+            pending_move,
+            button_map,
+            detected_orientation,
+            a_pin,
+            a_pressed,
+            held_direction,
+            preview_direction,
+            animation,
+            achievement_frames,
+        ]
+    )]
+    fn make_move(mut cx: make_move::Context, direction: Direction) {
+        record_stack(MAKE_MOVE_TASK);
+
+        // Releasing a direction confirms the move, so any preview is finished with
+        *cx.resources.held_direction = None;
+        *cx.resources.preview_direction = None;
+
+        // The first input after going idle only wakes the display
+        let was_idle = *cx.resources.idle_frames >= IDLE_TIMEOUT;
+        *cx.resources.idle_frames = 0;
+        if was_idle {
+            return;
+        }
+
+        if cx.resources.ui.paused {
+            return;
+        }
+        // Moves made too quickly are held back until the rate limit allows them.
+        // Only the latest is kept, so inputs can't pile up.
+        if !cx
+            .resources
+            .move_limiter
+            .is_move_allowed(now(), cx.resources.animation.as_ref())
+        {
+            *cx.resources.pending_move = Some(direction);
+            return;
+        }
+
+        // The joystick turns with the board, so undo the board's rotation
+        let direction = orientation(cx.resources.settings, *cx.resources.detected_orientation)
+            .unrotate(cx.resources.button_map.map(direction));
+        let a_held = cx
+            .resources
+            .a_pin
+            .lock(|a_pin| a_pin.is_low().unwrap_or(false));
+        if a_held {
+            // Letting go of A after using it for the menu doesn't redo
+            cx.resources.a_pressed.lock(|pressed| *pressed = None);
+        }
+
+        let achievements = cx.resources.board.stats().achievements;
+        let ui = &mut *cx.resources.ui;
+        match ui.screen {
+            // Holding A while pressing a direction opens the menu
+            Screen::Game if a_held => {
+                ui.screen = Screen::Menu;
+                ui.menu_item = 0;
+            }
+            #[cfg(feature = "extra-games")]
+            Screen::Game if ui.puzzle.is_some() => {
+                if !cx.resources.board.make_move(direction).moved() {
+                    return;
+                }
+                cx.resources.telemetry.record_move();
+                cx.resources.status.on_move();
+                // Puzzles are played without new tiles appearing
+                let mut run = ui.puzzle.unwrap();
+                match run.record_move(cx.resources.board) {
+                    PuzzleStatus::InProgress => ui.puzzle = Some(run),
+                    status => {
+                        if status == PuzzleStatus::Solved {
+                            rprintln!("Puzzle {} solved", run.index() + 1);
+                            cx.resources.puzzle_progress.mark_solved(run.index());
+                            if let Err(error) = write_puzzle_progress_to_eeprom(
+                                cx.resources.eeprom,
+                                cx.resources.puzzle_progress,
+                            ) {
+                                rprintln!("Could not save puzzle progress: {}", error);
+                            }
+                        } else {
+                            rprintln!("Puzzle {} failed", run.index() + 1);
+                        }
+                        start_new_game(cx.resources.board, cx.resources.seeds);
+                        *cx.resources.grade = MoveGrade::new();
+                        ui.puzzle = None;
+                        ui.daily = false;
+                        ui.screen = Screen::PuzzleSelect;
+                    }
+                }
+            }
+            Screen::Game => {
+                // Presses that change nothing are dropped before the board is copied for grading
+                if !cx.resources.board.can_move(direction) {
+                    return;
+                }
+                let previous_board = cx.resources.board.clone();
+                #[cfg_attr(not(feature = "animations"), allow(unused_variables))]
+                let outcome = cx.resources.board.make_move(direction);
+                cx.resources.telemetry.record_move();
+                cx.resources.status.on_move();
+                cx.resources.grade.record(&previous_board, direction);
+                cx.resources.board.spawn_tiles();
+
+                #[cfg(feature = "animations")]
+                {
+                    *cx.resources.animation = Some(SlideAnimation::new(
+                        outcome,
+                        cx.resources.board.clone(),
+                        now(),
+                    ));
+                }
+
+                #[cfg(feature = "extra-games")]
+                if let Some(run) = cx.resources.speedrun.as_mut() {
+                    if let Some(index) = run.record_move(cx.resources.board.max_tile(), now()) {
+                        rprintln!(
+                            "Split {}: {} ticks, best {:?}",
+                            index + 1,
+                            run.split(index).unwrap_or(0),
+                            cx.resources.personal_bests.split(index)
+                        );
+                    }
+                    if run.is_finished() {
+                        if cx.resources.personal_bests.update(run) {
+                            if let Err(error) = write_personal_bests_to_eeprom(
+                                cx.resources.eeprom,
+                                cx.resources.personal_bests,
+                            ) {
+                                rprintln!("Could not save personal bests: {}", error);
+                            }
+                        }
+                        ui.screen = Screen::Speedrun;
+                    }
+                }
+
+                if cx.resources.board.is_game_over() {
+                    end_game(
+                        cx.resources.eeprom,
+                        cx.resources.board,
+                        ui,
+                        cx.resources.grade,
+                        cx.resources.high_score,
+                        cx.resources.speedrun,
+                    );
+                }
+            }
+            #[cfg(feature = "extra-games")]
+            Screen::Speedrun => {
+                // Carry on playing once the final time has been seen
+                *cx.resources.speedrun = None;
+                ui.screen = Screen::Game;
+            }
+            #[cfg(not(feature = "extra-games"))]
+            Screen::Speedrun | Screen::PuzzleSelect => ui.screen = Screen::Game,
+            Screen::Stats => {
+                // Finished games are averaged once their stats have been seen
+                let board = &*cx.resources.board;
+                cx.resources
+                    .running_stats
+                    .record_game(board.get_score(), board.get_move_count());
+                if let Err(error) =
+                    write_running_stats_to_eeprom(cx.resources.eeprom, cx.resources.running_stats)
+                {
+                    rprintln!("Could not save running statistics: {}", error);
+                }
+                // Any move after a game has ended starts a new one
+                start_new_game(cx.resources.board, cx.resources.seeds);
+                *cx.resources.grade = MoveGrade::new();
+                ui.daily = false;
+                ui.screen = Screen::Game;
+            }
+            Screen::Menu => match direction {
+                Direction::Up => match Icon::ALL[usize::from(ui.menu_item) % Icon::ALL.len()] {
+                    #[cfg(feature = "extra-games")]
+                    Icon::Trophy => {
+                        ui.screen = Screen::PuzzleSelect;
+                        ui.menu_item = 0;
+                    }
+                    #[cfg(feature = "extra-games")]
+                    Icon::Stopwatch => {
+                        // Speedruns always start from a fresh game
+                        start_new_game(cx.resources.board, cx.resources.seeds);
+                        *cx.resources.grade = MoveGrade::new();
+                        *cx.resources.speedrun = Some(Speedrun::new());
+                        ui.puzzle = None;
+                        ui.daily = false;
+                        ui.screen = Screen::Game;
+                    }
+                    Icon::Checksum => ui.screen = Screen::Checksum,
+                    _ => return,
+                },
+                Direction::Down => ui.screen = Screen::Game,
+                Direction::Left | Direction::Right => {
+                    ui.cycle_selection(direction, Icon::ALL.len() as u8);
+                }
+            },
+            #[cfg(feature = "extra-games")]
+            Screen::PuzzleSelect => match direction {
+                Direction::Up => {
+                    // Statistics and the undo policy carry on into the puzzle
+                    let rules = GameBoardBuilder::new()
+                        .stats(*cx.resources.board.stats())
+                        .undo_policy(cx.resources.board.undo_policy());
+                    if let Some((run, board)) =
+                        PuzzleRun::start_with(usize::from(ui.menu_item), rules)
+                    {
+                        *cx.resources.board = board;
+                        ui.puzzle = Some(run);
+                        ui.daily = false;
+                        ui.screen = Screen::Game;
+                    }
+                }
+                Direction::Down => {
+                    ui.screen = Screen::Menu;
+                    ui.menu_item = 0;
+                }
+                Direction::Left | Direction::Right => {
+                    ui.cycle_selection(direction, PUZZLES.len() as u8);
+                }
+            },
+            Screen::Tutorial => {
+                let mut tutorial = Tutorial::from_step(ui.menu_item);
+                if !tutorial.make_move(direction) {
+                    return;
+                }
+                ui.menu_item = tutorial.step();
+                if tutorial.is_finished() {
+                    ui.screen = Screen::Game;
+                    ui.menu_item = 0;
+                    cx.resources.settings.set_tutorial_done(true);
+                    if let Err(error) =
+                        write_settings_to_eeprom(cx.resources.eeprom, cx.resources.settings)
+                    {
+                        rprintln!("Could not save settings: {}", error);
+                    }
+                }
+            }
+            // Any move goes back to the menu
+            Screen::Checksum => ui.screen = Screen::Menu,
+            // Left and right choose which variant's bests are shown
+            Screen::Score => {
+                let count = cx.resources.high_score.len().max(1) as u8;
+                ui.cycle_selection(direction, count);
+                return;
+            }
+        }
+
+        let unlocked = cx
+            .resources
+            .board
+            .stats()
+            .achievements
+            .difference(achievements);
+        if !unlocked.is_empty() {
+            for name in unlocked.names() {
+                rprintln!("Achievement unlocked: {}", name);
+            }
+            *cx.resources.achievement_frames = ACHIEVEMENT_FRAMES;
+        }
+
+        cx.resources.move_limiter.record_move(now());
+        save_progress(
+            cx.resources.eeprom,
+            cx.resources.board,
+            ui,
+            cx.resources.grade,
+            cx.resources.status,
+        );
+    }
+
+    /// End a time attack whose time has run out, as a game that can't move
+    /// any more is ended in `make_move`.
+    #[task(
+        priority = 2,
+        resources = [board, ui, grade, high_score, speedrun, eeprom, status]
+    )]
+    fn time_up(cx: time_up::Context) {
+        let ui = &mut *cx.resources.ui;
+        if ui.screen != Screen::Game {
+            return;
+        }
+        rprintln!("Time up, score {}", cx.resources.board.get_score());
+        end_game(
+            cx.resources.eeprom,
+            cx.resources.board,
+            ui,
+            cx.resources.grade,
+            cx.resources.high_score,
+            cx.resources.speedrun,
+        );
+        save_progress(
+            cx.resources.eeprom,
+            cx.resources.board,
+            ui,
+            cx.resources.grade,
+            cx.resources.status,
+        );
+    }
+
+    #[task(
+        priority = 2,
+        resources = [settings, button_map, detected_orientation, held_direction, animation],
+        schedule = [show_preview]
+    )]
+    fn press_direction(cx: press_direction::Context, direction: Direction) {
+        // Pressing is the earliest sign of the next move, so finish any slide straight away
+        *cx.resources.animation = None;
+
+        if !cx.resources.settings.is_ghost_preview() {
+            return;
+        }
+
+        let direction = orientation(cx.resources.settings, *cx.resources.detected_orientation)
+            .unrotate(cx.resources.button_map.map(direction));
+        *cx.resources.held_direction = Some(direction);
+        let _ = cx
+            .schedule
+            .show_preview(cx.scheduled + PREVIEW_DELAY.cycles(), direction);
+    }
+
+    #[task(priority = 2, resources = [held_direction, preview_direction])]
+    fn show_preview(cx: show_preview::Context, direction: Direction) {
+        // Only preview if the direction has been held the whole time
+        if *cx.resources.held_direction == Some(direction) {
+            *cx.resources.preview_direction = Some(direction);
+        }
     }
 
     #[task(
         priority = 1,
-        resources = [board, a_pin, board_leds],
-        schedule = [update]
+        resources = [
+            board,
+            ui,
+            grade,
+            puzzle_progress,
+            high_score,
+            running_stats,
+            speedrun,
+            preview_direction,
+            animation,
+            move_limiter,
+            pending_move,
+            telemetry,
+            idle_frames,
+            time,
+            status,
+            status_led,
+            a_pin,
+            b_pin,
+            board_leds,
+            thermal_throttle,
+            device_id,
+            boot_frames,
+            achievement_frames,
+            frame,
+            palette,
+            palette_cache,
+            frame_recorder,
+            render_timer,
+            mirror_channel,
+            is_mirroring,
+            is_spectating,
+            soak,
+            calibration,
+            adc,
+            demo,
+            demo_policy,
+            exhibition,
+            light_show,
+            led_health,
+            settings,
+        ],
+        schedule = [update],
+        spawn = [make_move, time_up]
     )]
     fn update(mut cx: update::Context) {
-        let show_score = cx.resources.a_pin.is_low();
+        record_stack(UPDATE_TASK);
+        let render_start = DWT::cycle_count();
 
-        let leds = cx.resources.board.lock(|board| match show_score {
-            Ok(true) => ScoreBoard::from_score(board.get_score()).into_board(),
-            Ok(false) | Err(_) => board.into_board(),
+        let detected = cx.resources.detected_orientation.lock(|detected| *detected);
+        let (numerals, theme, orientation) = cx.resources.settings.lock(|settings| {
+            (
+                settings.numerals(),
+                settings.theme(),
+                orientation(settings, detected),
+            )
         });
+        let context = RenderContext {
+            palette: cx
+                .resources
+                .palette_cache
+                .palette(theme, *cx.resources.palette),
+            brightness: cx.resources.thermal_throttle.scale_brightness(BRIGHTNESS),
+            frame: {
+                let current = *cx.resources.frame;
+                *cx.resources.frame = current.wrapping_add(1);
+                current
+            },
+            now: now(),
+            orientation,
+            numerals,
+        };
 
-        // Prevent interrupts occurring during LED write.
-        // If this were to occur, the LEDs would display incorrect data
-        // manifesting as a momentary flicker.
-        interrupt::free(|_| {
-            cx.resources
-                .board_leds
-                .write(brightness(leds.into_iter().cloned(), BRIGHTNESS))
-                .unwrap()
+        // A move held back by the limiter is made as soon as the limiter allows
+        let limiter = cx.resources.move_limiter.lock(|limiter| *limiter);
+        if cx
+            .resources
+            .animation
+            .lock(|animation| limiter.is_move_allowed(context.now, animation.as_ref()))
+        {
+            if let Some(direction) = cx.resources.pending_move.lock(|pending| pending.take()) {
+                let _ = cx.spawn.make_move(direction);
+            } else if let Some(soak) = cx.resources.soak.as_mut() {
+                let _ = cx.spawn.make_move(soak.next_move());
+            }
+        }
+
+        if let Some(soak) = cx.resources.soak.as_mut() {
+            // A frame starting a whole period late has missed its slot
+            if rtic::cyccnt::Instant::now().duration_since(cx.scheduled) >= UPDATE_PERIOD.cycles() {
+                soak.record_frame_miss();
+            }
+            if context.frame % soak::REPORT_FRAMES == 0 {
+                let report = soak.report(context.now, STORAGE_ERRORS.load(Ordering::Relaxed));
+                rprintln!(
+                    "{}, {} LED write failures, stack high water {} bytes",
+                    report,
+                    cx.resources.led_health.failures(),
+                    stack_high_water()
+                );
+            }
+        }
+
+        // Holding A shows the score, unless it's always shown on a status panel
+        let a_held = cx.resources.a_pin.lock(|a_pin| a_pin.is_low());
+        let show_score = a_held.map(|is_low| is_low && PANELS == 1);
+        // Holding B as well flashes a suggested move instead
+        let show_hint = a_held.unwrap_or(false)
+            && cx
+                .resources
+                .b_pin
+                .lock(|b_pin| b_pin.is_low().unwrap_or(false));
+        if cx
+            .resources
+            .status
+            .lock(|status| status.update(context.frame))
+        {
+            cx.resources.status_led.set_high().unwrap();
+        } else {
+            cx.resources.status_led.set_low().unwrap();
+        }
+        cx.resources
+            .telemetry
+            .lock(|telemetry| telemetry.record_frame());
+
+        let ui = cx.resources.ui.lock(|ui| *ui);
+        // A time attack counts down a tick a frame, only while it's being played
+        if ui.screen == Screen::Game && !ui.paused {
+            if cx.resources.board.lock(|board| board.tick()) {
+                // Ended by a task that shares the storage with moves, so the
+                // score is recorded as it is when the board fills up
+                let _ = cx.spawn.time_up();
+            }
+        }
+        let idle_frames = cx.resources.idle_frames.lock(|idle_frames| {
+            *idle_frames = idle_frames.saturating_add(1);
+            *idle_frames
+        });
+        let is_idle = idle_frames >= IDLE_TIMEOUT;
+
+        // Left idle for long enough, the game plays itself until woken
+        if idle_frames < DEMO_TIMEOUT || ui.screen != Screen::Game {
+            *cx.resources.demo = None;
+        } else if context.frame % DEMO_MOVE_FRAMES == 0 {
+            let policy = *cx.resources.demo_policy;
+            let demo = cx.resources.demo.get_or_insert_with(|| new_demo(policy));
+            if demo.next().is_none() {
+                *demo = new_demo(policy);
+            }
+        }
+
+        let celebrating = cx.resources.achievement_frames.lock(|frames| {
+            let celebrating = *frames > 0;
+            *frames = frames.saturating_sub(1);
+            celebrating
         });
 
+        let leds = if *cx.resources.boot_frames > 0 {
+            *cx.resources.boot_frames -= 1;
+            cx.resources.device_id.render(&context)
+        } else if celebrating && context.blink_on() {
+            // A new achievement flashes a trophy over the game
+            Icon::Trophy.render(&context)
+        } else if let Some(calibration) = cx.resources.calibration.as_ref() {
+            calibration.render(&context)
+        } else if let Some(light_show) = cx.resources.light_show.as_ref() {
+            // The host relays the leader's frame to the other boards
+            if let Some(show_frame) = light_show.announcement(context.frame) {
+                rprintln!("sync {}", show_frame);
+            }
+            light_show.slice(context.frame).render(&context)
+        } else if let Some(exhibition) = cx.resources.exhibition.as_mut() {
+            let leds = exhibition.render(&context);
+            if !exhibition.tick() {
+                *cx.resources.exhibition = None;
+            }
+            leds
+        } else {
+            match (ui.screen, show_score) {
+                (Screen::Game, _) if is_idle => match cx.resources.demo.as_ref() {
+                    Some(demo) => demo.board().render(&context),
+                    None => {
+                        let time = *cx.resources.time;
+                        cx.resources.board.lock(|board| {
+                            ScoreClock {
+                                time,
+                                score: board.get_score(),
+                                max_tile: board.max_tile(),
+                            }
+                            .render(&context)
+                        })
+                    }
+                },
+                (Screen::Game, _) if show_hint => {
+                    cx.resources.board.lock(|board| match board.best_move() {
+                        Some(direction) if context.blink_on() => {
+                            HintArrow(direction).render(&context)
+                        }
+                        _ => board.render(&context),
+                    })
+                }
+                (Screen::Menu, _) => {
+                    Icon::ALL[usize::from(ui.menu_item) % Icon::ALL.len()].render(&context)
+                }
+                (Screen::Score, _) | (Screen::Game, Ok(true)) => {
+                    // The score takes turns with the best score and tile
+                    if (context.frame / FACE_FRAMES) % 2 == 1 {
+                        let category = cx
+                            .resources
+                            .board
+                            .lock(|board| ScoreCategory::of(ui.variant, board));
+                        cx.resources.high_score.lock(|table| {
+                            let high_score = if ui.screen == Screen::Score {
+                                let selected = table.nth(usize::from(ui.menu_item));
+                                selected
+                                    .map(|(_, high_score)| high_score)
+                                    .unwrap_or_default()
+                            } else {
+                                table.get(category)
+                            };
+                            high_score.render(&context)
+                        })
+                    } else {
+                        cx.resources.board.lock(|board| {
+                            ScoreBoard::from_score(board.get_score()).render(&context)
+                        })
+                    }
+                }
+                #[cfg(feature = "extra-games")]
+                (Screen::Speedrun, _) => {
+                    let time = cx
+                        .resources
+                        .speedrun
+                        .lock(|run| run.and_then(|run| run.final_time()).unwrap_or(0));
+                    ScoreBoard::from_score(time / TICKS_PER_SECOND).render(&context)
+                }
+                (Screen::Tutorial, _) => Tutorial::from_step(ui.menu_item).render(&context),
+                (Screen::Checksum, _) => cx
+                    .resources
+                    .board
+                    .lock(|board| BoardChecksum::from_board(board).render(&context)),
+                #[cfg(feature = "extra-games")]
+                (Screen::PuzzleSelect, _) => {
+                    let index = usize::from(ui.menu_item) % PUZZLES.len();
+                    // Solved puzzles are shown dimmed
+                    let solved = cx
+                        .resources
+                        .puzzle_progress
+                        .lock(|progress| progress.is_solved(index));
+                    let context = RenderContext {
+                        brightness: if solved {
+                            context.brightness / 4
+                        } else {
+                            context.brightness
+                        },
+                        ..context
+                    };
+                    PUZZLES[index].board().render(&context)
+                }
+                #[cfg(not(feature = "extra-games"))]
+                (Screen::Speedrun, _) | (Screen::PuzzleSelect, _) => {
+                    cx.resources.board.lock(|board| board.render(&context))
+                }
+                // Accuracy takes turns with the number of moves the game took
+                // and the average score of every game before it
+                (Screen::Stats, _) if (context.frame / FACE_FRAMES) % 3 == 1 => {
+                    let moves = cx.resources.board.lock(|board| board.get_move_count());
+                    context.finish(render_move_count(moves, &context))
+                }
+                (Screen::Stats, _) if (context.frame / FACE_FRAMES) % 3 == 2 => {
+                    let average = cx.resources.running_stats.lock(|stats| stats.score.mean());
+                    ScoreBoard::from_score(average).render(&context)
+                }
+                (Screen::Stats, _) => cx
+                    .resources
+                    .grade
+                    .lock(|grade| ScoreBoard::from_score(grade.accuracy()).render(&context)),
+                (Screen::Game, Ok(false)) | (Screen::Game, Err(_)) => {
+                    let preview = cx.resources.preview_direction.lock(|direction| *direction);
+                    let sliding = cx.resources.animation.lock(|animation| match animation {
+                        Some(slide) if !slide.is_finished(context.now) => {
+                            Some(slide.render(&context))
+                        }
+                        _ => {
+                            *animation = None;
+                            None
+                        }
+                    });
+                    sliding.unwrap_or_else(|| {
+                        cx.resources.board.lock(|board| {
+                            // A held move is shown dimmed, as it would land
+                            match preview.and_then(|direction| board.peek_move(direction)) {
+                                Some(preview) => preview.render(&RenderContext {
+                                    brightness: context.brightness / 2,
+                                    ..context
+                                }),
+                                None => board.render(&context),
+                            }
+                        })
+                    })
+                }
+            }
+        };
+
+        if let Some(calibration) = cx.resources.calibration.as_mut() {
+            if calibration.tick() {
+                let reading: Result<u16, _> = cx.resources.adc.read(&mut InternalReference);
+                if let Ok(raw) = reading {
+                    let supply = supply_millivolts(raw, read_reference_calibration());
+                    if let Some(report) = calibration.finish_step(supply) {
+                        rprintln!("{}", report);
+                    }
+                }
+            }
+            if calibration.is_done() {
+                rprintln!("calibration done");
+                *cx.resources.calibration = None;
+            }
+        }
+
+        cx.resources.frame_recorder.record(leds);
+        if *cx.resources.is_mirroring {
+            cx.resources
+                .mirror_channel
+                .write(encode_frame(&leds).as_bytes());
+        }
+        if *cx.resources.is_spectating && context.frame % BROADCAST_FRAMES == 0 {
+            let broadcast = cx
+                .resources
+                .board
+                .lock(|board| Broadcast::from_board(context.now.millis(), board, leds));
+            cx.resources
+                .mirror_channel
+                .write(broadcast.encode().as_bytes());
+        }
+
+        // The game is always shown on the first panel
+        let mut chain = Chain::<PANELS>::new();
+        chain.set_panel(0, leds).unwrap();
+        if PANELS > 1 {
+            let status = cx.resources.board.lock(|board| StatusPanel {
+                score: board.get_score(),
+                moves: board.get_move_count(),
+                max_tile: board.max_tile(),
+            });
+            chain.set_panel(1, status.render(&context)).unwrap();
+        }
+
+        if cx
+            .resources
+            .render_timer
+            .record(render_start, DWT::cycle_count())
+            && cx.resources.render_timer.over_budget() == 1
+        {
+            rprintln!(
+                "Frame took {} cycles to build, over the budget of {}",
+                cx.resources.render_timer.last(),
+                RENDER_BUDGET
+            );
+        }
+
+        let health = cx.resources.led_health;
+        if health.should_write(context.frame) {
+            let board_leds = &mut *cx.resources.board_leds;
+            let mut written = false;
+            for _ in 0..=LED_WRITE_RETRIES {
+                // Prevent interrupts occurring during LED write.
+                // If this were to occur, the LEDs would display incorrect data
+                // manifesting as a momentary flicker.
+                if interrupt::free(|_| board_leds.write(chain.leds().cloned())).is_ok() {
+                    written = true;
+                    break;
+                }
+                health.record_failure();
+            }
+            // A faulty LED chain slows the display down rather than stopping the game
+            match health.record_frame(written) {
+                Some(LedWriteEvent::Degraded) => {
+                    rprintln!("LED writes failing, reducing the refresh rate");
+                    cx.resources.status.lock(|status| status.set_fault(true));
+                }
+                Some(LedWriteEvent::Recovered) => {
+                    rprintln!("LED writes working again, restoring the refresh rate");
+                    cx.resources.status.lock(|status| status.set_fault(false));
+                }
+                None => {}
+            }
+        }
+
         cx.schedule
             .update(cx.scheduled + UPDATE_PERIOD.cycles())
             .unwrap();
     }
 
+    #[task(
+        priority = 1,
+        resources = [adc, temperature_sensor, thermal_throttle],
+        schedule = [check_temperature]
+    )]
+    fn check_temperature(cx: check_temperature::Context) {
+        record_stack(CHECK_TEMPERATURE_TASK);
+
+        let reading: Result<u16, _> = cx.resources.adc.read(cx.resources.temperature_sensor);
+        if let Ok(raw) = reading {
+            let temperature = temperature_from_adc(raw);
+            match cx.resources.thermal_throttle.update(temperature) {
+                Some(ThermalEvent::Throttled(t)) => {
+                    rprintln!("Temperature {}°C too high, dimming LEDs", t)
+                }
+                Some(ThermalEvent::Restored(t)) => {
+                    rprintln!("Temperature {}°C back to normal, restoring LEDs", t)
+                }
+                None => {}
+            }
+        }
+
+        cx.schedule
+            .check_temperature(cx.scheduled + TEMPERATURE_PERIOD.cycles())
+            .unwrap();
+    }
+
+    #[task(priority = 1, resources = [time], schedule = [tick_clock])]
+    fn tick_clock(cx: tick_clock::Context) {
+        if let Some(time) = cx.resources.time.as_mut() {
+            time.tick(1);
+        }
+
+        cx.schedule
+            .tick_clock(cx.scheduled + CLOCK_PERIOD.cycles())
+            .unwrap();
+    }
+
+    #[task(priority = 1, resources = [telemetry, eeprom], schedule = [save_telemetry])]
+    fn save_telemetry(mut cx: save_telemetry::Context) {
+        let telemetry = &mut cx.resources.telemetry;
+        // Only moves mark the counters as changed, so an idle device doesn't wear out the EEPROM
+        let counters = telemetry.lock(|telemetry| {
+            if telemetry.needs_saving() {
+                telemetry.mark_saved();
+                Some(*telemetry.counters())
+            } else {
+                None
+            }
+        });
+        if let Some(counters) = counters {
+            let result = cx
+                .resources
+                .eeprom
+                .lock(|eeprom| write_counters_to_eeprom(eeprom, &counters));
+            if let Err(error) = result {
+                rprintln!("Could not save counters: {}", error);
+            }
+        }
+
+        cx.schedule
+            .save_telemetry(cx.scheduled + TELEMETRY_PERIOD.cycles())
+            .unwrap();
+    }
+
+    #[task(
+        priority = 1,
+        resources = [
+            console,
+            line_buffer,
+            provisioning_receiver,
+            board,
+            ui,
+            grade,
+            running_stats,
+            settings,
+            palette,
+            button_map,
+            device_id,
+            eeprom,
+            frame_recorder,
+            render_timer,
+            is_mirroring,
+            is_spectating,
+            telemetry,
+            time,
+            status,
+            soak,
+            calibration,
+            demo,
+            demo_policy,
+            exhibition,
+            script_receiver,
+            light_show,
+            frame,
+            idle_frames,
+            boot_frames,
+            animation,
+        ],
+        schedule = [poll_console]
+    )]
+    fn poll_console(mut cx: poll_console::Context) {
+        record_stack(POLL_CONSOLE_TASK);
+
+        let mut bytes = [0; 16];
+        let count = cx.resources.console.read(&mut bytes);
+
+        for &byte in bytes[..count].iter() {
+            // A provisioning blob is being received, so the bytes aren't a command
+            if let Some(receiver) = cx.resources.provisioning_receiver.as_mut() {
+                if let Some(blob) = receiver.push(byte) {
+                    *cx.resources.provisioning_receiver = None;
+                    let result = Provisioning::from_bytes(&blob).and_then(|provisioning| {
+                        cx.resources
+                            .eeprom
+                            .lock(|eeprom| write_to_eeprom(eeprom, PROVISIONING_BASE, &blob))?;
+                        Ok(provisioning)
+                    });
+                    match result {
+                        Ok(provisioning) => {
+                            cx.resources
+                                .settings
+                                .lock(|settings| *settings = provisioning.settings);
+                            *cx.resources.palette = provisioning.palette;
+                            cx.resources
+                                .button_map
+                                .lock(|button_map| *button_map = provisioning.button_map);
+                            rprintln!("ok");
+                        }
+                        Err(error) => rprintln!("Could not provision: {}", error),
+                    }
+                }
+                continue;
+            }
+            // Likewise for a script
+            if let Some(receiver) = cx.resources.script_receiver.as_mut() {
+                if let Some(result) = receiver.push(byte) {
+                    *cx.resources.script_receiver = None;
+                    match result {
+                        Ok(script) => {
+                            *cx.resources.exhibition = Some(Exhibition::new(script));
+                            rprintln!("ok");
+                        }
+                        Err(error) => rprintln!("Could not run script: {}", error),
+                    }
+                }
+                continue;
+            }
+
+            let line = match cx.resources.line_buffer.push(byte) {
+                Some(line) => line,
+                None => continue,
+            };
+            match Command::parse(&line) {
+                Some(Command::Id) => {
+                    let device_id = cx.resources.device_id;
+                    cx.resources
+                        .settings
+                        .lock(|settings| rprintln!("{} {}", device_id, settings.name()))
+                }
+                Some(Command::Name(name)) => {
+                    let eeprom = &mut cx.resources.eeprom;
+                    let result = cx.resources.settings.lock(|settings| {
+                        settings.set_name(name)?;
+                        eeprom.lock(|eeprom| write_settings_to_eeprom(eeprom, settings))
+                    });
+                    match result {
+                        Ok(()) => rprintln!("ok"),
+                        Err(error) => rprintln!("Could not set name: {}", error),
+                    }
+                }
+                Some(Command::Frames) => {
+                    // One line per frame: the frame number, then each LED's RGB in PCB order
+                    for (number, frame) in cx.resources.frame_recorder.frames() {
+                        rprint!("{}:", number);
+                        for led in frame.into_iter() {
+                            rprint!(" {:02x}{:02x}{:02x}", led.r, led.g, led.b);
+                        }
+                        rprintln!();
+                    }
+                }
+                Some(Command::Mirror(enabled)) => {
+                    *cx.resources.is_mirroring = enabled;
+                    rprintln!("ok");
+                }
+                Some(Command::Spectate(enabled)) => {
+                    *cx.resources.is_spectating = enabled;
+                    rprintln!("ok");
+                }
+                Some(Command::Preview(enabled)) => {
+                    let eeprom = &mut cx.resources.eeprom;
+                    let result = cx.resources.settings.lock(|settings| {
+                        settings.set_ghost_preview(enabled);
+                        eeprom.lock(|eeprom| write_settings_to_eeprom(eeprom, settings))
+                    });
+                    match result {
+                        Ok(()) => rprintln!("ok"),
+                        Err(error) => rprintln!("Could not save settings: {}", error),
+                    }
+                }
+                Some(Command::Telemetry(enabled)) => {
+                    cx.resources
+                        .telemetry
+                        .lock(|telemetry| telemetry.set_enabled(enabled));
+                    let eeprom = &mut cx.resources.eeprom;
+                    let result = cx.resources.settings.lock(|settings| {
+                        settings.set_telemetry(enabled);
+                        eeprom.lock(|eeprom| write_settings_to_eeprom(eeprom, settings))
+                    });
+                    match result {
+                        Ok(()) => rprintln!("ok"),
+                        Err(error) => rprintln!("Could not save settings: {}", error),
+                    }
+                }
+                Some(Command::Counters) => {
+                    let counters = cx.resources.telemetry.lock(|telemetry| {
+                        if telemetry.is_enabled() {
+                            Some(*telemetry.counters())
+                        } else {
+                            None
+                        }
+                    });
+                    match counters {
+                        Some(counters) => rprintln!(
+                            "frames {} moves {} undos {} crashes {}",
+                            counters.frames,
+                            counters.moves,
+                            counters.undos,
+                            counters.crashes
+                        ),
+                        None => rprintln!("telemetry is off, enable with: telemetry on"),
+                    }
+                }
+                Some(Command::Board) => {
+                    // Copied out, so moves aren't held up while printing
+                    let board = cx.resources.board.lock(|board| board.clone());
+                    rprintln!("{}", board);
+                }
+                Some(Command::Stats) => {
+                    let stats = cx.resources.board.lock(|board| *board.stats());
+                    rprintln!(
+                        "moves {} merges {} spawns {} highest tile {} games completed {}",
+                        stats.moves,
+                        stats.merges,
+                        stats.spawns,
+                        stats.highest_tile,
+                        stats.games_completed
+                    );
+                    let averages = cx.resources.running_stats.lock(|stats| *stats);
+                    rprintln!(
+                        "average score {} average moves {} over {} games",
+                        averages.score.mean(),
+                        averages.moves.mean(),
+                        averages.score.count()
+                    );
+                }
+                Some(Command::Share) => {
+                    let shared = cx.resources.board.lock(SharedBoard::from_board);
+                    match shared.encode() {
+                        Ok(code) => rprintln!("{}", code.as_str()),
+                        Err(error) => rprintln!("Could not share board: {}", error),
+                    }
+                }
+                Some(Command::Moves) => {
+                    // Copied out, so moves aren't held up while printing
+                    let (log, rule) = cx
+                        .resources
+                        .board
+                        .lock(|board| (board.move_log().clone(), board.rule().clone()));
+                    for (index, logged) in log.iter().enumerate() {
+                        rprintln!("{}: {}", index, logged.display(&rule));
+                    }
+                    rprintln!("{} moves", log.len());
+                }
+                Some(Command::Time(text)) => match TimeOfDay::parse(text) {
+                    Some(time) => {
+                        *cx.resources.time = Some(time);
+                        rprintln!("ok");
+                    }
+                    None => rprintln!("invalid time: {}, expected HH:MM", text),
+                },
+                Some(Command::Daily(text)) => match Date::parse(text) {
+                    Some(date) => {
+                        // Saved with the game after the first move
+                        let (board, grade) = (&mut cx.resources.board, &mut cx.resources.grade);
+                        cx.resources.ui.lock(|ui| {
+                            board.lock(|board| start_game_with_seed(board, date.daily_seed()));
+                            grade.lock(|grade| *grade = MoveGrade::new());
+                            ui.puzzle = None;
+                            ui.daily = true;
+                            ui.screen = Screen::Game;
+                        });
+                        rprintln!("ok");
+                    }
+                    None => rprintln!("invalid date: {}, expected YYYY-MM-DD", text),
+                },
+                Some(Command::Stack) => {
+                    // Safety: only the address of the linker symbol is used.
+                    let top = unsafe { &_stack_start as *const u32 as u32 };
+                    rprintln!("high water: {} bytes", stack_high_water());
+                    for (task, name) in TRACKED_TASKS.iter().enumerate() {
+                        match STACK_MONITOR.depth(task, top) {
+                            Some(depth) => rprintln!("{}: {} bytes on entry", name, depth),
+                            None => rprintln!("{}: not run", name),
+                        }
+                    }
+                }
+                Some(Command::FrameTime) => {
+                    let timer = &*cx.resources.render_timer;
+                    rprintln!(
+                        "last {} cycles, worst {} cycles, {} frames over the budget of {}",
+                        timer.last(),
+                        timer.worst(),
+                        timer.over_budget(),
+                        timer.budget()
+                    );
+                }
+                Some(Command::StatusLed(policy)) => {
+                    cx.resources.status.lock(|status| status.set_policy(policy));
+                    let eeprom = &mut cx.resources.eeprom;
+                    let result = cx.resources.settings.lock(|settings| {
+                        settings.set_status_led(policy);
+                        eeprom.lock(|eeprom| write_settings_to_eeprom(eeprom, settings))
+                    });
+                    match result {
+                        Ok(()) => rprintln!("ok"),
+                        Err(error) => rprintln!("Could not save settings: {}", error),
+                    }
+                }
+                Some(Command::Shortcut(shortcut)) => {
+                    let eeprom = &mut cx.resources.eeprom;
+                    let result = cx.resources.settings.lock(|settings| {
+                        settings.set_shortcut(shortcut);
+                        eeprom.lock(|eeprom| write_settings_to_eeprom(eeprom, settings))
+                    });
+                    match result {
+                        Ok(()) => rprintln!("ok"),
+                        Err(error) => rprintln!("Could not save settings: {}", error),
+                    }
+                }
+                Some(Command::Undo(policy)) => {
+                    // Saved with the game after the next move
+                    cx.resources
+                        .board
+                        .lock(|board| board.set_undo_policy(policy));
+                    rprintln!("ok");
+                }
+                Some(Command::Spawns(rate)) => {
+                    // Saved with the game after the next move
+                    cx.resources.board.lock(|board| board.set_spawn_rate(rate));
+                    rprintln!("ok");
+                }
+                Some(Command::Scoring(policy)) => {
+                    // Saved with the game after the next move
+                    cx.resources
+                        .board
+                        .lock(|board| board.set_score_policy(policy));
+                    rprintln!("ok");
+                }
+                Some(Command::TimeAttack(seconds)) => {
+                    let limit = seconds.map(|seconds| seconds.saturating_mul(UPDATE_RATE));
+                    cx.resources.board.lock(|board| board.set_time_limit(limit));
+                    rprintln!("ok");
+                }
+                #[cfg(feature = "ai")]
+                Some(Command::Demo(strategy)) => {
+                    *cx.resources.demo_policy = strategy.policy();
+                    // Any demo running starts again with the new strategy
+                    *cx.resources.demo = None;
+                    rprintln!("ok");
+                }
+                Some(Command::Orient(orientation)) => {
+                    let eeprom = &mut cx.resources.eeprom;
+                    let result = cx.resources.settings.lock(|settings| {
+                        settings.set_orientation(orientation);
+                        eeprom.lock(|eeprom| write_settings_to_eeprom(eeprom, settings))
+                    });
+                    match result {
+                        Ok(()) => rprintln!("ok"),
+                        Err(error) => rprintln!("Could not save settings: {}", error),
+                    }
+                }
+                Some(Command::Numerals(style)) => {
+                    let eeprom = &mut cx.resources.eeprom;
+                    let result = cx.resources.settings.lock(|settings| {
+                        settings.set_numerals(style);
+                        eeprom.lock(|eeprom| write_settings_to_eeprom(eeprom, settings))
+                    });
+                    match result {
+                        Ok(()) => rprintln!("ok"),
+                        Err(error) => rprintln!("Could not save settings: {}", error),
+                    }
+                }
+                Some(Command::Soak(enabled)) => {
+                    *cx.resources.soak = if enabled {
+                        Some(SoakTest::new(u64::from(DWT::cycle_count()), now()))
+                    } else {
+                        None
+                    };
+                    rprintln!("ok");
+                }
+                Some(Command::Calibrate) => {
+                    *cx.resources.calibration = Some(Calibration::new());
+                    rprintln!("{}", CalibrationReport::HEADER);
+                }
+                Some(Command::Exhibit(enabled)) => {
+                    *cx.resources.exhibition = if enabled {
+                        Some(builtin_exhibition())
+                    } else {
+                        None
+                    };
+                    rprintln!("ok");
+                }
+                Some(Command::Show(Some((unit, units)))) => match LightShow::new(unit, units) {
+                    Ok(light_show) => {
+                        *cx.resources.light_show = Some(light_show);
+                        rprintln!("ok");
+                    }
+                    Err(error) => rprintln!("Could not join show: {}", error),
+                },
+                Some(Command::Show(None)) => {
+                    *cx.resources.light_show = None;
+                    rprintln!("ok");
+                }
+                Some(Command::Sync(leader_frame)) => {
+                    if let Some(light_show) = cx.resources.light_show.as_mut() {
+                        light_show.sync(leader_frame, *cx.resources.frame);
+                    }
+                }
+                Some(Command::Script(length)) => match ScriptReceiver::new(length) {
+                    Some(receiver) => {
+                        *cx.resources.script_receiver = Some(receiver);
+                        rprintln!("send {} bytes", length);
+                    }
+                    None => rprintln!("Could not run script: {}", Error::StorageFull),
+                },
+                #[cfg(feature = "god-mode")]
+                Some(Command::Spawn(coord, tile)) => {
+                    match cx
+                        .resources
+                        .board
+                        .lock(|board| board.place_tile(coord, tile))
+                    {
+                        Ok(()) => rprintln!("ok"),
+                        Err(error) => rprintln!("Could not spawn: {}", error),
+                    }
+                }
+                #[cfg(feature = "god-mode")]
+                Some(Command::Win) => {
+                    let won = cx.resources.board.lock(|board| {
+                        // Blockers sort above every tile, but can't become one
+                        let largest = board
+                            .tiles()
+                            .filter(|(_, tile)| !tile.is_blocker())
+                            .max_by_key(|&(_, tile)| tile);
+                        if let Some((coord, _)) = largest {
+                            board.replace_tile(coord, WIN_TILE);
+                        }
+                        largest.is_some()
+                    });
+                    if won {
+                        rprintln!("ok");
+                    } else {
+                        rprintln!("no tile to replace");
+                    }
+                }
+                #[cfg(all(feature = "god-mode", feature = "ai"))]
+                Some(Command::FastForward(moves)) => {
+                    let mut played = 0;
+                    // One move at a time, so real moves aren't held up for long
+                    while played < moves {
+                        let moved = cx.resources.board.lock(|board| {
+                            ai::preferred_move(board).map(|direction| {
+                                board.make_move(direction);
+                                board.spawn_tiles();
+                            })
+                        });
+                        if moved.is_none() {
+                            break;
+                        }
+                        played += 1;
+                    }
+                    rprintln!("played {} moves", played);
+                }
+                #[cfg(all(feature = "god-mode", not(feature = "ai")))]
+                Some(Command::FastForward(_)) => rprintln!("built without the AI"),
+                #[cfg(feature = "god-mode")]
+                Some(Command::Effect(Effect::Boot)) => {
+                    *cx.resources.boot_frames = BOOT_PATTERN_FRAMES;
+                    rprintln!("ok");
+                }
+                #[cfg(feature = "god-mode")]
+                Some(Command::Effect(Effect::Idle)) => {
+                    cx.resources
+                        .idle_frames
+                        .lock(|idle_frames| *idle_frames = IDLE_TIMEOUT);
+                    rprintln!("ok");
+                }
+                #[cfg(all(feature = "god-mode", feature = "animations"))]
+                Some(Command::Effect(Effect::Slide)) => {
+                    // Slide a copy, so the board snaps back once the animation ends
+                    let slide = cx.resources.board.lock(|board| {
+                        Direction::ALL.iter().find_map(|&direction| {
+                            let mut destination = board.clone();
+                            let outcome = destination.make_move(direction);
+                            if outcome.moved() {
+                                Some(SlideAnimation::new(outcome, destination, now()))
+                            } else {
+                                None
+                            }
+                        })
+                    });
+                    match slide {
+                        Some(slide) => {
+                            cx.resources
+                                .animation
+                                .lock(|animation| *animation = Some(slide));
+                            rprintln!("ok");
+                        }
+                        None => rprintln!("no tiles can slide"),
+                    }
+                }
+                #[cfg(all(feature = "god-mode", not(feature = "animations")))]
+                Some(Command::Effect(Effect::Slide)) => rprintln!("built without animations"),
+                Some(Command::Provision) => {
+                    *cx.resources.provisioning_receiver = Some(Receiver::new());
+                    rprintln!("send {} bytes", provisioning::BYTES_SIZE);
+                }
+                None => rprintln!("unknown command: {}", line.as_str()),
+            }
+        }
+
+        cx.schedule
+            .poll_console(cx.scheduled + CONSOLE_PERIOD.cycles())
+            .unwrap();
+    }
+
     extern "C" {
         fn USB_WKUP();
         fn USB_LP();
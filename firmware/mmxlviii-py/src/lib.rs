@@ -0,0 +1,152 @@
+//! Python bindings for the game's rules.
+//!
+//! Everything is played by `mmxlviii` itself, so games analysed or played in
+//! Python follow exactly the same rules as on the device, including which
+//! tiles appear for a given seed.
+
+use mmxlviii::{board::Direction, game_board::GameBoard};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+/// Parse a direction from its name, e.g. `"up"`.
+fn parse_direction(name: &str) -> PyResult<Direction> {
+    match name {
+        "up" => Ok(Direction::Up),
+        "down" => Ok(Direction::Down),
+        "left" => Ok(Direction::Left),
+        "right" => Ok(Direction::Right),
+        _ => Err(PyValueError::new_err(format!(
+            "unknown direction: {}",
+            name
+        ))),
+    }
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+/// A game of 2048, with tiles given as exponents, e.g. 11 for a 2048.
+#[pyclass(name = "Game")]
+#[derive(Clone)]
+struct PyGame {
+    board: GameBoard,
+}
+
+#[pymethods]
+impl PyGame {
+    /// Start a new game, with the same tiles as the device for the same seed.
+    #[new]
+    fn new(seed: u64) -> PyGame {
+        PyGame {
+            board: GameBoard::new_game_with_seed(seed),
+        }
+    }
+
+    /// Create a game from 16 tiles, bottom row first.
+    #[staticmethod]
+    fn from_tiles(tiles: [u8; 16]) -> PyGame {
+        PyGame {
+            board: GameBoard::with_tiles(tiles),
+        }
+    }
+
+    /// Get the 16 tiles as bytes, bottom row first.
+    fn tiles(&self) -> [u8; 16] {
+        let mut tiles = [0; 16];
+        for (coord, tile) in self.board.tiles() {
+            tiles[coord.board_index()] = tile.into();
+        }
+        tiles
+    }
+
+    #[getter]
+    fn score(&self) -> u32 {
+        self.board.get_score()
+    }
+
+    #[getter]
+    fn move_count(&self) -> u32 {
+        self.board.get_move_count()
+    }
+
+    #[getter]
+    fn max_tile(&self) -> u8 {
+        self.board.max_tile().into()
+    }
+
+    fn is_game_over(&self) -> bool {
+        self.board.is_game_over()
+    }
+
+    /// Make a move, adding new tiles if anything moved, as in play.
+    /// Returns true if anything moved.
+    fn make_move(&mut self, direction: &str) -> PyResult<bool> {
+        let moved = self.board.make_move(parse_direction(direction)?).moved();
+        if moved {
+            self.board.spawn_tiles();
+        }
+        Ok(moved)
+    }
+
+    /// Undo the last move. Returns false if there is nothing to undo.
+    fn undo(&mut self) -> bool {
+        self.board.undo()
+    }
+
+    /// Get the move the device would suggest as a hint, or `None` if no move is possible.
+    fn best_move(&self) -> Option<&'static str> {
+        self.board.best_move().map(direction_name)
+    }
+
+    /// Get the move the device's AI would make, or `None` if no move is possible.
+    fn ai_move(&self) -> Option<&'static str> {
+        mmxlviii::ai::preferred_move(&self.board).map(direction_name)
+    }
+
+    /// Get the move with the best expected value searching `depth` moves
+    /// ahead, or `None` if no move is possible.
+    fn expectimax_move(&self, depth: u8) -> Option<&'static str> {
+        mmxlviii::ai::expectimax_move(&self.board, depth).map(direction_name)
+    }
+
+    fn copy(&self) -> PyGame {
+        self.clone()
+    }
+
+    fn __str__(&self) -> String {
+        self.board.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Game(tiles={:?}, score={})",
+            self.tiles(),
+            self.board.get_score()
+        )
+    }
+}
+
+/// Play a game again from its seed and moves, such as a replay dumped from
+/// the device. Returns the starting game, then the game after each move.
+#[pyfunction]
+fn replay(seed: u64, moves: Vec<String>) -> PyResult<Vec<PyGame>> {
+    let directions = moves
+        .iter()
+        .map(|name| parse_direction(name))
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(GameBoard::replay(seed, directions)
+        .map(|board| PyGame { board })
+        .collect())
+}
+
+#[pymodule]
+fn mmxlviii_py(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyGame>()?;
+    module.add_function(wrap_pyfunction!(replay, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! A small leaderboard server, for collecting scores from boards on a home or office network.
+//!
+//! Run with `cargo run --example leaderboard -- [address] [scores file]`.
+//! Boards `POST /scores` with a postcard encoded `ScoreSubmission` as the body,
+//! and `GET /scores` returns the best scores as JSON. Submissions are appended
+//! to the scores file, so they survive a restart.
+
+use std::{
+    cmp::Reverse,
+    env,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use mmxlviii::protocol::{ScoreSubmission, SUBMISSION_BYTES_SIZE};
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:2048";
+const DEFAULT_SCORES_FILE: &str = "scores.bin";
+/// Number of scores returned by `GET /scores`.
+const LEADERBOARD_SIZE: usize = 20;
+
+/// Load every submission saved so far. Each is stored as a fixed size record.
+fn load_scores(path: &str) -> io::Result<Vec<ScoreSubmission>> {
+    let mut bytes = Vec::new();
+    match File::open(path) {
+        Ok(mut file) => file.read_to_end(&mut bytes)?,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+    Ok(bytes
+        .chunks_exact(SUBMISSION_BYTES_SIZE)
+        .filter_map(|record| ScoreSubmission::from_bytes(record).ok())
+        .collect())
+}
+
+fn save_score(path: &str, submission: &ScoreSubmission) -> io::Result<()> {
+    let bytes = submission
+        .to_bytes()
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(&bytes)
+}
+
+/// Get the best scores as a JSON array, highest first.
+fn leaderboard_json(scores: &[ScoreSubmission]) -> String {
+    let mut best: Vec<&ScoreSubmission> = scores.iter().collect();
+    best.sort_by_key(|submission| Reverse(submission.score));
+
+    let mut json = String::from("[");
+    for (index, submission) in best.iter().take(LEADERBOARD_SIZE).enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        submission.write_json(&mut json).unwrap();
+    }
+    json.push(']');
+    json
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+fn handle(stream: TcpStream, scores: &mut Vec<ScoreSubmission>, path: &str) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    let mut stream = stream;
+    let mut words = request_line.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some("GET"), Some("/scores")) => respond(
+            &mut stream,
+            "200 OK",
+            "application/json",
+            &leaderboard_json(scores),
+        ),
+        (Some("POST"), Some("/scores")) if content_length <= SUBMISSION_BYTES_SIZE => {
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body)?;
+            match ScoreSubmission::from_bytes(&body) {
+                Ok(submission) => {
+                    println!("{} scored {}", submission.name, submission.score);
+                    save_score(path, &submission)?;
+                    scores.push(submission);
+                    respond(&mut stream, "201 Created", "text/plain", "")
+                }
+                Err(error) => respond(
+                    &mut stream,
+                    "400 Bad Request",
+                    "text/plain",
+                    &error.to_string(),
+                ),
+            }
+        }
+        (Some("POST"), Some("/scores")) => {
+            respond(&mut stream, "413 Payload Too Large", "text/plain", "")
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", ""),
+    }
+}
+
+fn main() -> io::Result<()> {
+    let mut args = env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    let path = args
+        .next()
+        .unwrap_or_else(|| DEFAULT_SCORES_FILE.to_string());
+
+    let mut scores = load_scores(&path)?;
+    let listener = TcpListener::bind(&address)?;
+    println!("Serving {} scores on {}", scores.len(), address);
+
+    for stream in listener.incoming() {
+        if let Err(error) = stream.and_then(|stream| handle(stream, &mut scores, &path)) {
+            eprintln!("Request failed: {}", error);
+        }
+    }
+    Ok(())
+}
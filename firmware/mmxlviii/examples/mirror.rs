@@ -0,0 +1,36 @@
+//! Live-render frames streamed from the hardware in a terminal.
+//!
+//! Enable mirroring with the `mirror on` console command, then pipe the
+//! "Mirror" RTT channel into this program, e.g.
+//! `probe-rs attach --rtt-channel 1 ... | cargo run --example mirror`
+
+use std::io::{self, BufRead, Write};
+
+use mmxlviii::{
+    board::{Coord, SIZE},
+    mirror::decode_frame,
+};
+
+fn main() -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let frame = match decode_frame(&line?) {
+            Some(frame) => frame,
+            None => continue,
+        };
+
+        // Move the cursor home and draw the top row first
+        write!(stdout, "\x1b[H")?;
+        for y in (0..SIZE).rev() {
+            for x in 0..SIZE {
+                let led = frame.get_led(Coord::new(x, y).unwrap());
+                write!(stdout, "\x1b[48;2;{};{};{}m    ", led.r, led.g, led.b)?;
+            }
+            writeln!(stdout, "\x1b[0m")?;
+        }
+        stdout.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,57 @@
+//! Time moves on a `GameBoard` against the same moves on a `BitBoard`.
+//!
+//! Run with `cargo run --release --example move_speed -- [moves]`, with and
+//! without `--features move-table`, to compare the table against the
+//! computed rows the firmware uses. It times the host, not the device, so
+//! it only shows how the two compare, not how fast either is on the Cortex-M4.
+
+use std::{env, hint::black_box, time::Instant};
+
+use mmxlviii::{
+    bitboard::BitBoard,
+    board::{Direction, SIZE},
+    game_board::GameBoard,
+};
+
+const DEFAULT_MOVES: u32 = 1_000_000;
+
+/// Get a board with a spread of tiles, so most moves change something.
+fn test_board() -> GameBoard {
+    let mut tiles = [0; SIZE * SIZE];
+    for (i, tile) in tiles.iter_mut().enumerate() {
+        *tile = [1, 2, 0, 1, 3, 0, 2, 4][i % 8];
+    }
+    GameBoard::with_tiles(tiles)
+}
+
+fn main() {
+    let moves: u32 = env::args()
+        .nth(1)
+        .and_then(|moves| moves.parse().ok())
+        .unwrap_or(DEFAULT_MOVES);
+    let board = test_board();
+    let bits = BitBoard::from_game_board(&board).unwrap();
+    let directions = Direction::ALL.iter().copied().cycle().take(moves as usize);
+
+    // Each move starts from the same board, as a search does at every node
+    let start = Instant::now();
+    for direction in directions.clone() {
+        let mut moved = black_box(&board).clone();
+        black_box(moved.make_move(direction).moved());
+    }
+    let game_board = start.elapsed();
+
+    let start = Instant::now();
+    for direction in directions {
+        black_box(black_box(&bits).make_move(direction));
+    }
+    let bit_board = start.elapsed();
+
+    let per_move = |elapsed: std::time::Duration| elapsed.as_nanos() as f64 / f64::from(moves);
+    println!("GameBoard: {:.1} ns a move", per_move(game_board));
+    println!("BitBoard:  {:.1} ns a move", per_move(bit_board));
+    println!(
+        "BitBoard is {:.1} times as fast",
+        game_board.as_secs_f64() / bit_board.as_secs_f64()
+    );
+}
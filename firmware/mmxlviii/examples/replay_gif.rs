@@ -0,0 +1,176 @@
+//! Render a replayed game as an animated GIF of the LED panel, for sharing.
+//!
+//! Run with `cargo run --example replay_gif -- <replay file> <gif file> [theme]`.
+//! The replay file starts with the game's seed, then has one move per line,
+//! either as a direction (`left`) or as printed by the `moves` console command
+//! (`3: Left, 4 at 1,3`). Blank lines and lines starting with `#` are ignored.
+//! The theme is one of `rainbow` (the default) or `greyscale`.
+
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, BufWriter},
+    process,
+};
+
+use mmxlviii::{
+    animation::{SlideAnimation, SLIDE_MILLIS},
+    board::{Board, Coord, Direction, IntoBoard, SIZE},
+    game_board::GameBoard,
+    monotonic::Instant,
+    render::RenderContext,
+    theme::{Palette, Theme},
+};
+use smart_leds::RGB8;
+
+/// Width of each LED's square of the image, in pixels.
+const CELL: usize = 32;
+const IMAGE_SIZE: usize = CELL * SIZE;
+/// Colour of the panel between LEDs.
+const BACKGROUND: [f32; 3] = [12.0, 12.0, 14.0];
+/// Radius of each LED's fully lit centre, as a fraction of a cell.
+const LED_RADIUS: f32 = 0.3;
+/// How far each LED's glow spreads, as a fraction of a cell.
+const GLOW_SPREAD: f32 = 0.35;
+/// Brightness of the glow relative to the LED itself.
+const GLOW_STRENGTH: f32 = 0.6;
+/// Milliseconds between frames of a slide.
+const SLIDE_FRAME_MILLIS: u32 = 40;
+/// Milliseconds each board is shown for once its tiles have stopped.
+const HOLD_MILLIS: u32 = 300;
+/// Milliseconds the final board is shown for before the GIF loops.
+const FINAL_HOLD_MILLIS: u32 = 3000;
+
+/// Parse a move from a line of the replay file.
+fn parse_move(line: &str) -> Option<Direction> {
+    // Lines from the `moves` command start with an index and end with the added tile
+    let line = line.split_once(": ").map_or(line, |(_, rest)| rest);
+    let word = line.split(',').next()?.trim().to_ascii_lowercase();
+    match word.as_str() {
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+/// Parse a replay file into the game's seed and moves.
+fn parse_replay(text: &str) -> Result<(u64, Vec<Direction>), String> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let seed = lines.next().ok_or("replay file is empty")?;
+    let seed = seed
+        .parse()
+        .map_err(|_| format!("invalid seed: {}", seed))?;
+    let moves = lines
+        .map(|line| parse_move(line).ok_or(format!("invalid move: {}", line)))
+        .collect::<Result<_, _>>()?;
+    Ok((seed, moves))
+}
+
+/// Draw the panel as it looks lit, with each LED glowing onto its neighbours.
+fn draw_panel(leds: &Board) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(IMAGE_SIZE * IMAGE_SIZE * 3);
+    for py in 0..IMAGE_SIZE {
+        for px in 0..IMAGE_SIZE {
+            let mut colour = BACKGROUND;
+            for coord in Coord::all() {
+                let led: RGB8 = leds.get_led(coord);
+                // The top row of the image is the top row of LEDs
+                let centre_x = (coord.x() as f32 + 0.5) * CELL as f32;
+                let centre_y = ((SIZE - 1 - coord.y()) as f32 + 0.5) * CELL as f32;
+                let dx = (px as f32 + 0.5 - centre_x) / CELL as f32;
+                let dy = (py as f32 + 0.5 - centre_y) / CELL as f32;
+                let distance = (dx * dx + dy * dy).sqrt();
+                let weight = if distance < LED_RADIUS {
+                    1.0
+                } else {
+                    let beyond = (distance - LED_RADIUS) / GLOW_SPREAD;
+                    GLOW_STRENGTH * (-beyond * beyond).exp()
+                };
+                colour[0] += weight * f32::from(led.r);
+                colour[1] += weight * f32::from(led.g);
+                colour[2] += weight * f32::from(led.b);
+            }
+            pixels.extend(colour.iter().map(|&channel| channel.min(255.0) as u8));
+        }
+    }
+    pixels
+}
+
+/// Render every frame of the game, with how long each is shown in milliseconds.
+fn render_frames(seed: u64, moves: &[Direction], palette: Palette) -> Vec<(Board, u32)> {
+    let context = |millis| RenderContext {
+        palette,
+        now: Instant::from_millis(millis),
+        ..RenderContext::default()
+    };
+    let mut board = GameBoard::new_game_with_seed(seed);
+    let mut frames = vec![(board.render(&context(0)), HOLD_MILLIS)];
+    for &direction in moves {
+        let outcome = board.make_move(direction);
+        // Moves that change nothing don't add a tile, just as in play
+        if !outcome.moved() {
+            continue;
+        }
+        board.set_random();
+        let slide = SlideAnimation::new(outcome, board.clone(), Instant::from_millis(0));
+        for millis in (0..SLIDE_MILLIS).step_by(SLIDE_FRAME_MILLIS as usize) {
+            frames.push((slide.render(&context(millis)), SLIDE_FRAME_MILLIS));
+        }
+        frames.push((board.render(&context(SLIDE_MILLIS)), HOLD_MILLIS));
+    }
+    if let Some(last) = frames.last_mut() {
+        last.1 = FINAL_HOLD_MILLIS;
+    }
+    frames
+}
+
+fn write_gif(path: &str, frames: &[(Board, u32)]) -> Result<(), String> {
+    let file = BufWriter::new(File::create(path).map_err(|error| error.to_string())?);
+    let size = IMAGE_SIZE as u16;
+    let mut encoder =
+        gif::Encoder::new(file, size, size, &[]).map_err(|error| error.to_string())?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|error| error.to_string())?;
+    for (leds, millis) in frames {
+        let mut frame = gif::Frame::from_rgb_speed(size, size, &draw_panel(leds), 10);
+        // GIF delays are in hundredths of a second
+        frame.delay = (*millis / 10) as u16;
+        encoder
+            .write_frame(&frame)
+            .map_err(|error| error.to_string())?;
+    }
+    Ok(())
+}
+
+fn run(arguments: &[String]) -> Result<(), String> {
+    let (replay_path, gif_path) = match arguments {
+        [replay_path, gif_path, ..] => (replay_path, gif_path),
+        _ => return Err("usage: replay_gif <replay file> <gif file> [theme]".into()),
+    };
+    // There's no provisioned palette off the device, so that's the default too
+    let theme = match arguments.get(2) {
+        Some(name) => Theme::parse(name).ok_or(format!("unknown theme: {}", name))?,
+        None => Theme::Rainbow,
+    };
+    let text = fs::read_to_string(replay_path)
+        .map_err(|error: io::Error| format!("{}: {}", replay_path, error))?;
+    let (seed, moves) = parse_replay(&text)?;
+    let frames = render_frames(seed, &moves, theme.palette(Palette::default()));
+    write_gif(gif_path, &frames)?;
+    println!("wrote {} frames to {}", frames.len(), gif_path);
+    Ok(())
+}
+
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    if let Err(error) = run(&arguments) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,74 @@
+//! Play many games with a fixed policy and check that the rules hold up.
+//!
+//! Run with `cargo run --example self_play -- [games]` as a quick regression
+//! check whenever move logic changes.
+
+use std::env;
+
+use mmxlviii::{board::Direction, game_board::GameBoard, merge_rule::Doubling, tile::Tile};
+
+const DEFAULT_GAMES: u32 = 1000;
+const MAX_MOVES: u32 = 100_000;
+
+/// A small LCG used to pick move order, so each game follows a different path.
+struct Policy(u32);
+
+impl Policy {
+    /// Get the order in which to try each direction for the next move.
+    fn next_order(&mut self) -> impl Iterator<Item = Direction> {
+        self.0 = self.0.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        let start = (self.0 >> 30) as usize;
+        (0..Direction::ALL.len())
+            .map(move |offset| Direction::ALL[(start + offset) % Direction::ALL.len()])
+    }
+}
+
+/// Play a game to completion, returning the final score, best tile and number of moves.
+fn play_game(seed: u32) -> (u32, Tile, u32) {
+    let mut board = GameBoard::new_game();
+    let mut policy = Policy(seed);
+    let mut moves = 0;
+
+    loop {
+        let score = board.get_score();
+        let max_tile = board.max_tile();
+
+        let moved = policy
+            .next_order()
+            .any(|direction| board.make_move(direction).moved());
+        if !moved {
+            assert!(board.is_full(), "game {} ended with vacant tiles", seed);
+            return (board.get_score(), board.max_tile(), moves);
+        }
+        assert!(board.set_random(), "game {} had no room to spawn", seed);
+        moves += 1;
+
+        assert!(board.get_score() >= score, "game {} score decreased", seed);
+        assert!(
+            board.max_tile() >= max_tile,
+            "game {} lost its best tile",
+            seed
+        );
+        assert!(moves < MAX_MOVES, "game {} did not terminate", seed);
+    }
+}
+
+fn main() {
+    let games = env::args()
+        .nth(1)
+        .map(|arg| arg.parse().expect("number of games should be an integer"))
+        .unwrap_or(DEFAULT_GAMES);
+
+    let (mut total_score, mut total_moves, mut best_tile) = (0u64, 0u64, Tile::EMPTY);
+    for seed in 0..games {
+        let (score, max_tile, moves) = play_game(seed);
+        total_score += u64::from(score);
+        total_moves += u64::from(moves);
+        best_tile = best_tile.max(max_tile);
+    }
+
+    println!("Played {} games", games);
+    println!("Average score: {}", total_score / u64::from(games.max(1)));
+    println!("Average moves: {}", total_moves / u64::from(games.max(1)));
+    println!("Best tile: {}", best_tile.value_in(&Doubling));
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+/// Number of moves in a row that must each merge for `MERGE_CHAIN`.
+pub const MERGE_CHAIN_LENGTH: u8 = 10;
+
+/// A set of achievements, each a bit, unlocked during play and kept forever.
+///
+/// They are kept by `GameBoard` in its `GameStats`, so they are carried from
+/// one game to the next and saved along with the statistics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Achievements(u16);
+
+impl Achievements {
+    /// Made a 512 tile.
+    pub const FIRST_512: Achievements = Achievements(1 << 0);
+    /// Made a 2048 tile.
+    pub const FIRST_2048: Achievements = Achievements(1 << 1);
+    /// Merged on `MERGE_CHAIN_LENGTH` moves in a row.
+    pub const MERGE_CHAIN: Achievements = Achievements(1 << 2);
+    /// Made a 2048 tile without undoing a move that game.
+    pub const NO_UNDO_WIN: Achievements = Achievements(1 << 3);
+
+    /// Every achievement and its name, for listing them.
+    pub const ALL: [(Achievements, &'static str); 4] = [
+        (Achievements::FIRST_512, "first 512"),
+        (Achievements::FIRST_2048, "first 2048"),
+        (Achievements::MERGE_CHAIN, "merge chain"),
+        (Achievements::NO_UNDO_WIN, "no undo win"),
+    ];
+
+    pub const fn empty() -> Achievements {
+        Achievements(0)
+    }
+
+    pub const fn from_bits(bits: u16) -> Achievements {
+        Achievements(bits)
+    }
+
+    pub const fn bits(&self) -> u16 {
+        self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns true if every achievement of `other` is in the set.
+    pub fn contains(&self, other: Achievements) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Add achievements to the set. Returns true if any weren't already in it.
+    pub fn insert(&mut self, other: Achievements) -> bool {
+        let unlocked = !self.contains(other);
+        self.0 |= other.0;
+        unlocked
+    }
+
+    /// Get the achievements in the set that aren't in `other`, e.g. those
+    /// unlocked since an earlier copy of the set.
+    pub fn difference(&self, other: Achievements) -> Achievements {
+        Achievements(self.0 & !other.0)
+    }
+
+    /// Iterate over the names of the achievements in the set.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        Achievements::ALL
+            .iter()
+            .filter(move |(achievement, _)| self.contains(*achievement))
+            .map(|&(_, name)| name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set() {
+        let mut achievements = Achievements::empty();
+        assert!(achievements.is_empty());
+        assert!(achievements.insert(Achievements::FIRST_512));
+        assert!(!achievements.insert(Achievements::FIRST_512));
+        let earlier = achievements;
+        assert!(achievements.insert(Achievements::NO_UNDO_WIN));
+        assert!(achievements.contains(Achievements::FIRST_512));
+        assert!(!achievements.contains(Achievements::FIRST_2048));
+        assert_eq!(achievements.difference(earlier), Achievements::NO_UNDO_WIN);
+        let mut names = achievements.names();
+        assert_eq!(names.next(), Some("first 512"));
+        assert_eq!(names.next(), Some("no undo win"));
+        assert_eq!(names.next(), None);
+        assert_eq!(Achievements::from_bits(achievements.bits()), achievements);
+    }
+}
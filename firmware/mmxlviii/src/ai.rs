@@ -0,0 +1,331 @@
+use rand::RngCore;
+
+use crate::{
+    autoplay::Policy,
+    bitboard::BitBoard,
+    board::{Coord, Direction, SIZE},
+    game_board::GameBoard,
+    tile::Tile,
+};
+
+/// Value of each empty tile after a move, relative to a point of score.
+const EMPTY_WEIGHT: u32 = 16;
+/// Value of having the largest tile in a corner, relative to a point of score.
+const CORNER_WEIGHT: u32 = 64;
+
+const CORNERS: [usize; 4] = [0, SIZE - 1, SIZE * (SIZE - 1), SIZE * SIZE - 1];
+
+/// Moves searched ahead by `Strategy::Expectimax`, quick enough to run on the device.
+const DEVICE_DEPTH: u8 = 2;
+
+/// The ways the AI can choose moves, so one can be picked while running,
+/// e.g. for the demo.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// Looks one move ahead, see `preferred_move`.
+    #[default]
+    Greedy,
+    /// Searches every new tile a few moves ahead, see `expectimax_move`.
+    Expectimax,
+    /// Plays random games after each move, see `monte_carlo_move`.
+    MonteCarlo,
+}
+
+impl Strategy {
+    pub fn parse(name: &str) -> Option<Strategy> {
+        match name {
+            "greedy" => Some(Strategy::Greedy),
+            "expectimax" => Some(Strategy::Expectimax),
+            "montecarlo" => Some(Strategy::MonteCarlo),
+            _ => None,
+        }
+    }
+
+    /// Get the strategy as a policy, tuned to choose a move quickly enough on the device.
+    pub fn policy(&self) -> Policy {
+        match self {
+            Strategy::Greedy => preferred_move,
+            Strategy::Expectimax => |board| expectimax_move(board, DEVICE_DEPTH),
+            Strategy::MonteCarlo => |board| monte_carlo_move(board, Rollouts::DEVICE),
+        }
+    }
+}
+
+/// How much `monte_carlo_move` plays ahead, trading how well it plays for
+/// how long it takes. Time taken grows with both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rollouts {
+    /// Random games played after each possible move.
+    pub count: u16,
+    /// Most moves each random game lasts, if it isn't over sooner.
+    pub length: u16,
+}
+
+impl Rollouts {
+    /// Quick enough to choose a demo move on the device.
+    pub const DEVICE: Rollouts = Rollouts {
+        count: 8,
+        length: 16,
+    };
+}
+
+/// Score a board position using cheap heuristics. Higher is better.
+fn evaluate(board: &GameBoard) -> u32 {
+    let empty = board.tiles().filter(|(_, tile)| tile.is_empty()).count() as u32;
+    let max_tile = board.max_tile();
+    let corner = if board
+        .tiles()
+        .any(|(coord, tile)| tile == max_tile && CORNERS.contains(&coord.board_index()))
+    {
+        CORNER_WEIGHT
+    } else {
+        0
+    };
+    board.get_score() + empty * EMPTY_WEIGHT + corner
+}
+
+/// Score a packed board position with the same heuristics as `evaluate`,
+/// leaving out the score, which the search adds up as it goes.
+fn evaluate_packed(board: BitBoard) -> u32 {
+    let empty = board.empty_spaces().count() as u32;
+    let max_tile = board.max_tile();
+    let corner = if Coord::all()
+        .any(|coord| board.tile(coord) == max_tile && CORNERS.contains(&coord.board_index()))
+    {
+        CORNER_WEIGHT
+    } else {
+        0
+    };
+    empty * EMPTY_WEIGHT + corner
+}
+
+/// Get the best value of any move from a position, searching `depth` moves ahead.
+fn best_move_value(board: BitBoard, depth: u8, four_odds: u8) -> u32 {
+    if depth == 0 {
+        return evaluate_packed(board);
+    }
+    Direction::ALL
+        .iter()
+        .filter_map(|&direction| board.make_move(direction))
+        .map(|(next, score)| score.saturating_add(new_tile_value(next, depth - 1, four_odds)))
+        .max()
+        // No move is left, so the game is over
+        .unwrap_or(0)
+}
+
+/// Get the expected value of a position once a new tile has been added, over
+/// every space it could be added to, as a 2 or as a 4 one time in `four_odds`.
+fn new_tile_value(board: BitBoard, depth: u8, four_odds: u8) -> u32 {
+    let mut total = 0u64;
+    let mut outcomes = 0u64;
+    for coord in board.empty_spaces() {
+        let two = best_move_value(
+            board.with_tile(coord, Tile::from_exponent(1)),
+            depth,
+            four_odds,
+        );
+        if four_odds == 0 {
+            total += u64::from(two);
+            outcomes += 1;
+        } else {
+            let four = best_move_value(
+                board.with_tile(coord, Tile::from_exponent(2)),
+                depth,
+                four_odds,
+            );
+            total += u64::from(two) * u64::from(four_odds - 1) + u64::from(four);
+            outcomes += u64::from(four_odds);
+        }
+    }
+    match outcomes {
+        0 => evaluate_packed(board),
+        _ => (total / outcomes) as u32,
+    }
+}
+
+/// Get the move with the best expected value `depth` moves ahead, taking
+/// every way a new tile could be added into account, or `None` if no move is
+/// possible. Each move searched further multiplies the time taken by up to
+/// 120, so a depth of 2 or 3 is plenty on the device.
+///
+/// Boards with tiles too large to pack into a `BitBoard` fall back to
+/// `preferred_move`.
+pub fn expectimax_move(board: &GameBoard, depth: u8) -> Option<Direction> {
+    // There's nothing to search if there's no choice to make
+    let moves = board.available_moves();
+    if moves.len() < 2 {
+        return moves.first().copied();
+    }
+    let packed = match BitBoard::from_game_board(board) {
+        Ok(packed) => packed,
+        Err(_) => return preferred_move(board),
+    };
+    let depth = depth.max(1);
+    Direction::ALL
+        .iter()
+        .filter_map(|&direction| {
+            packed.make_move(direction).map(|(next, score)| {
+                let value = new_tile_value(next, depth - 1, board.four_odds());
+                (direction, score.saturating_add(value))
+            })
+        })
+        .max_by_key(|&(_, value)| value)
+        .map(|(direction, _)| direction)
+}
+
+/// Add a new tile to a random empty space as in play, or `None` if there's no space.
+fn add_random_tile(board: BitBoard, rng: &mut impl RngCore, four_odds: u8) -> Option<BitBoard> {
+    let spaces = board.empty_spaces().count();
+    if spaces == 0 {
+        return None;
+    }
+    let coord = board.empty_spaces().nth(rng.next_u32() as usize % spaces)?;
+    let four_odds = u32::from(four_odds);
+    let tile = if four_odds != 0 && rng.next_u32() % four_odds == 0 {
+        Tile::from_exponent(2)
+    } else {
+        Tile::from_exponent(1)
+    };
+    Some(board.with_tile(coord, tile))
+}
+
+/// Play random moves from a position, adding new tiles as in play, and get
+/// what they scored plus the value of where they ended up.
+fn rollout_value(mut board: BitBoard, length: u16, rng: &mut impl RngCore, four_odds: u8) -> u32 {
+    let mut score = 0u32;
+    for _ in 0..length {
+        // Start from a random direction, taking the first after it that moves
+        let start = rng.next_u32() as usize;
+        let next = (0..Direction::ALL.len()).find_map(|offset| {
+            board.make_move(Direction::ALL[(start + offset) % Direction::ALL.len()])
+        });
+        match next {
+            Some((moved, gained)) => {
+                score = score.saturating_add(gained);
+                board = add_random_tile(moved, rng, four_odds).unwrap_or(moved);
+            }
+            None => return score,
+        }
+    }
+    score.saturating_add(evaluate_packed(board))
+}
+
+/// Get the move whose random games score best on average, playing
+/// `rollouts.count` of them after each possible move, or `None` if no move is
+/// possible. Games are played with a copy of the board's random number
+/// generator, so the same board always gives the same move.
+///
+/// Boards with tiles too large to pack into a `BitBoard` fall back to
+/// `preferred_move`.
+pub fn monte_carlo_move(board: &GameBoard, rollouts: Rollouts) -> Option<Direction> {
+    let moves = board.available_moves();
+    if moves.len() < 2 {
+        return moves.first().copied();
+    }
+    let packed = match BitBoard::from_game_board(board) {
+        Ok(packed) => packed,
+        Err(_) => return preferred_move(board),
+    };
+    let mut rng = board.rng().clone();
+    let four_odds = board.four_odds();
+    Direction::ALL
+        .iter()
+        .filter_map(|&direction| {
+            packed.make_move(direction).map(|(next, score)| {
+                let total: u64 = (0..rollouts.count)
+                    .map(|_| {
+                        let start = add_random_tile(next, &mut rng, four_odds).unwrap_or(next);
+                        u64::from(rollout_value(start, rollouts.length, &mut rng, four_odds))
+                    })
+                    .sum();
+                let average = total / u64::from(rollouts.count.max(1));
+                (direction, u64::from(score) + average)
+            })
+        })
+        .max_by_key(|&(_, value)| value)
+        .map(|(direction, _)| direction)
+}
+
+/// Get the move the AI prefers, or `None` if no move is possible.
+pub fn preferred_move(board: &GameBoard) -> Option<Direction> {
+    Direction::ALL
+        .iter()
+        .filter_map(|&direction| {
+            board
+                .peek_move(direction)
+                .map(|next| (direction, evaluate(&next)))
+        })
+        .max_by_key(|&(_, value)| value)
+        .map(|(direction, _)| direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autoplay::Autoplay;
+
+    #[test]
+    fn test_preferred_move_merges() {
+        let mut tiles = [0; SIZE * SIZE];
+        tiles[0] = 3;
+        tiles[SIZE] = 3;
+        // Board looks like
+        // |         |
+        // |         |
+        // | 3       |
+        // | 3       |
+        let board = GameBoard::with_tiles(tiles);
+        let direction = preferred_move(&board).unwrap();
+        assert!(direction == Direction::Up || direction == Direction::Down);
+    }
+
+    #[test]
+    fn test_preferred_move_none() {
+        let mut tiles = [0; SIZE * SIZE];
+        for (index, tile) in tiles.iter_mut().enumerate() {
+            *tile = (index % 2 + 2 * ((index / SIZE) % 2)) as u8 + 1;
+        }
+        assert_eq!(preferred_move(&GameBoard::with_tiles(tiles)), None);
+        assert_eq!(expectimax_move(&GameBoard::with_tiles(tiles), 2), None);
+    }
+
+    #[test]
+    fn test_expectimax() {
+        // Board looks like
+        // |         |
+        // |         |
+        // | 1       |
+        // | 4 3 3 1 |
+        let board = GameBoard::with_tiles([4, 3, 3, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        // Merging the 3s and then the 4s keeps the largest tile in the corner
+        assert_eq!(expectimax_move(&board, 2), Some(Direction::Left));
+
+        let mut tiles = [0; SIZE * SIZE];
+        tiles[0] = 16;
+        tiles[SIZE] = 16;
+        let too_large = GameBoard::with_tiles(tiles);
+        assert_eq!(expectimax_move(&too_large, 2), preferred_move(&too_large));
+    }
+
+    #[test]
+    fn test_monte_carlo() {
+        let mut tiles = [0; SIZE * SIZE];
+        tiles[0] = 3;
+        tiles[SIZE] = 3;
+        let board = GameBoard::with_tiles(tiles);
+        let direction = monte_carlo_move(&board, Rollouts::DEVICE).unwrap();
+        assert!(direction == Direction::Up || direction == Direction::Down);
+        // The board's generator is copied, not used up
+        assert_eq!(monte_carlo_move(&board, Rollouts::DEVICE), Some(direction));
+    }
+
+    #[test]
+    fn test_strategies() {
+        assert_eq!(Strategy::parse("montecarlo"), Some(Strategy::MonteCarlo));
+        assert_eq!(Strategy::parse("random"), None);
+        for strategy in [Strategy::Greedy, Strategy::Expectimax, Strategy::MonteCarlo] {
+            let mut demo = Autoplay::new(GameBoard::new_game_with_seed(7), strategy.policy());
+            assert_eq!(demo.by_ref().take(20).count(), 20);
+        }
+    }
+}
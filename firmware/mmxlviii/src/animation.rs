@@ -0,0 +1,189 @@
+use crate::{
+    board::{Board, IntoBoard},
+    game_board::{GameBoard, MoveOutcome, Slides},
+    monotonic::Instant,
+    render::{blend_colour, RenderContext},
+    tile::Tile,
+};
+
+/// Milliseconds a slide takes, kept well under the time between moves.
+pub const SLIDE_MILLIS: u32 = 130;
+/// Milliseconds a new tile takes to fade in, once the slide has finished.
+pub const FADE_MILLIS: u32 = 100;
+
+/// Tiles sliding from where they were to where a move left them, then the
+/// tile added after the move fading in.
+///
+/// The game board has already been updated by the time this is shown, so the
+/// animation can be dropped at any point to jump straight to the result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlideAnimation {
+    outcome: MoveOutcome,
+    /// The board after the move, including any new tile.
+    destination: GameBoard,
+    /// When the slide started.
+    start: Instant,
+}
+
+impl SlideAnimation {
+    pub fn new(outcome: MoveOutcome, destination: GameBoard, start: Instant) -> SlideAnimation {
+        SlideAnimation {
+            outcome,
+            destination,
+            start,
+        }
+    }
+
+    /// Returns true once the tiles have reached their destination and any
+    /// new tile has faded in.
+    pub fn is_finished(&self, now: Instant) -> bool {
+        let fade = match self.destination.last_spawn() {
+            Some(_) => FADE_MILLIS,
+            None => 0,
+        };
+        now.since(self.start) >= SLIDE_MILLIS + fade
+    }
+}
+
+/// Get how far along a distance a tile has moved, rounded to the nearest LED.
+fn travelled(distance: isize, elapsed: u32) -> isize {
+    let duration = SLIDE_MILLIS as isize;
+    (2 * distance * elapsed as isize + distance.signum() * duration) / (2 * duration)
+}
+
+impl IntoBoard for SlideAnimation {
+    fn into_board(&self, context: &RenderContext) -> Board {
+        let elapsed = context.now.since(self.start);
+        if elapsed >= SLIDE_MILLIS {
+            let mut board = self.destination.into_board(context);
+            if let Some((coord, value)) = self.destination.last_spawn() {
+                let faded = (elapsed - SLIDE_MILLIS).min(FADE_MILLIS);
+                board.set_led(
+                    coord,
+                    blend_colour(
+                        context.palette.tile_colour(Tile::EMPTY),
+                        context.palette.tile_colour(value),
+                        (faded * 255 / FADE_MILLIS) as u8,
+                    ),
+                );
+            }
+            return board;
+        }
+
+        let mut board = Board::new();
+        // Tiles that stay put are drawn first, so moving tiles pass over them
+        let (still, moving): (Slides, Slides) = self
+            .outcome
+            .slides()
+            .iter()
+            .partition(|slide| slide.from == slide.to);
+        for slide in still.iter().chain(moving.iter()) {
+            let dx = slide.to.x() as isize - slide.from.x() as isize;
+            let dy = slide.to.y() as isize - slide.from.y() as isize;
+            if let Ok(coord) = slide
+                .from
+                .offset(travelled(dx, elapsed), travelled(dy, elapsed))
+            {
+                board.set_led(coord, context.palette.tile_colour(slide.value));
+            }
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::{Coord, Direction},
+        game_board::Event,
+    };
+
+    fn at(millis: u32) -> Instant {
+        Instant::from_millis(millis)
+    }
+
+    fn render(animation: &SlideAnimation, millis: u32) -> Board {
+        animation.into_board(&RenderContext {
+            now: at(millis),
+            ..RenderContext::default()
+        })
+    }
+
+    fn slide_right(start: u32) -> (GameBoard, SlideAnimation) {
+        let before = GameBoard::with_tiles([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+        let mut after = before.clone();
+        let outcome = after.make_move(Direction::Right);
+        let animation = SlideAnimation::new(outcome, after.clone(), at(start));
+        (before, animation)
+    }
+
+    #[test]
+    fn test_travelled() {
+        assert_eq!(travelled(3, 0), 0);
+        assert_eq!(travelled(3, SLIDE_MILLIS / 2), 2);
+        assert_eq!(travelled(-3, SLIDE_MILLIS / 2), -2);
+        assert_eq!(travelled(3, SLIDE_MILLIS), 3);
+        assert_eq!(travelled(0, SLIDE_MILLIS / 2), 0);
+    }
+
+    #[test]
+    fn test_slide_animation() {
+        let (before, animation) = slide_right(100);
+        let context = RenderContext::default();
+        assert_eq!(render(&animation, 100), before.into_board(&context));
+
+        // Part way through, the sliding tile is between its start and end
+        let middle = render(&animation, 100 + SLIDE_MILLIS / 2);
+        let colour = context.palette.tile_colour(Tile::from(1));
+        assert_eq!(middle.get_led(Coord::new(2, 0).unwrap()), colour);
+        assert_eq!(
+            middle.get_led(Coord::new(0, 0).unwrap()),
+            context.palette.tile_colour(Tile::EMPTY)
+        );
+        assert_eq!(
+            middle.get_led(Coord::new(3, 3).unwrap()),
+            context.palette.tile_colour(Tile::from(2))
+        );
+
+        assert!(!animation.is_finished(at(100 + SLIDE_MILLIS - 1)));
+        assert!(animation.is_finished(at(100 + SLIDE_MILLIS)));
+        assert_eq!(
+            render(&animation, 100 + SLIDE_MILLIS),
+            animation.destination.into_board(&context)
+        );
+    }
+
+    #[test]
+    fn test_fade_in() {
+        let mut after = GameBoard::with_tiles([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let outcome = after.make_move(Direction::Right);
+        let spawned = Coord::new(0, 3).unwrap();
+        after.apply(Event::TileSpawned(spawned, Tile::from(2)));
+        let animation = SlideAnimation::new(outcome, after.clone(), at(0));
+        let context = RenderContext::default();
+
+        // The new tile only appears once the slide is over
+        assert_eq!(
+            render(&animation, SLIDE_MILLIS - 1).get_led(spawned),
+            context.palette.tile_colour(Tile::EMPTY)
+        );
+        let half = render(&animation, SLIDE_MILLIS + FADE_MILLIS / 2).get_led(spawned);
+        assert_ne!(half, context.palette.tile_colour(Tile::EMPTY));
+        assert_ne!(half, context.palette.tile_colour(Tile::from(2)));
+
+        assert!(!animation.is_finished(at(SLIDE_MILLIS)));
+        assert!(animation.is_finished(at(SLIDE_MILLIS + FADE_MILLIS)));
+        assert_eq!(
+            render(&animation, SLIDE_MILLIS + FADE_MILLIS),
+            after.into_board(&context)
+        );
+    }
+
+    #[test]
+    fn test_slide_animation_clock_wrap() {
+        let (_, animation) = slide_right(u32::MAX);
+        assert!(!animation.is_finished(at(SLIDE_MILLIS - 2)));
+        assert!(animation.is_finished(at(SLIDE_MILLIS - 1)));
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{board::Direction, game_board::GameBoard};
+
+/// A way of choosing moves, such as `ai::preferred_move` or `GameBoard::best_move`.
+/// Returns `None` if no move is possible.
+pub type Policy = fn(&GameBoard) -> Option<Direction>;
+
+/// Plays a game by itself, making one move each time it is advanced, so a
+/// demo can be shown at whatever pace the caller advances it.
+///
+/// Ends once the policy finds no move, which for a policy that only chooses
+/// moves that change the board is when the game is over.
+#[derive(Clone)]
+pub struct Autoplay {
+    board: GameBoard,
+    policy: Policy,
+}
+
+impl Autoplay {
+    pub fn new(board: GameBoard, policy: Policy) -> Autoplay {
+        Autoplay { board, policy }
+    }
+
+    /// Get the game being played.
+    pub fn board(&self) -> &GameBoard {
+        &self.board
+    }
+}
+
+impl Iterator for Autoplay {
+    type Item = Direction;
+
+    /// Make the policy's next move, adding a new tile as in play.
+    fn next(&mut self) -> Option<Direction> {
+        let direction = (self.policy)(&self.board)?;
+        if self.board.make_move(direction).moved() {
+            self.board.spawn_tiles();
+        }
+        Some(direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plays_until_game_over() {
+        let mut demo = Autoplay::new(GameBoard::new_game_with_seed(3), GameBoard::best_move);
+        let moves = demo.by_ref().take(10_000).count();
+        assert!(moves > 0 && moves < 10_000);
+        assert!(demo.board().is_game_over());
+        assert_eq!(demo.board().get_move_count(), moves as u32);
+    }
+
+    #[test]
+    fn test_matches_replay() {
+        let mut demo = Autoplay::new(GameBoard::new_game_with_seed(5), GameBoard::best_move);
+        let moves: heapless::Vec<Direction, 16> = demo.by_ref().take(16).collect();
+        let replayed = GameBoard::replay(5, moves).last().unwrap();
+        assert_eq!(&replayed, demo.board());
+    }
+}
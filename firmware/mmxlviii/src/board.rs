@@ -1,8 +1,11 @@
+use serde::{Deserialize, Serialize};
 use smart_leds::RGB8;
 
+use crate::{error::Error, render::RenderContext};
+
 pub const SIZE: usize = 4;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
@@ -10,39 +13,162 @@ pub enum Direction {
     Right,
 }
 
+impl Direction {
+    /// Every direction, clockwise from up
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    /// Get the direction pointing the opposite way
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// Get the direction after a quarter turn clockwise
+    pub fn rotate_cw(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    /// Get the direction after a quarter turn anticlockwise
+    pub fn rotate_ccw(&self) -> Direction {
+        self.opposite().rotate_cw()
+    }
+
+    /// Get the direction after mirroring left to right
+    pub fn mirror(&self) -> Direction {
+        match self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            vertical => *vertical,
+        }
+    }
+}
+
+/// A location on an N by N grid, with (0, 0) in the bottom left.
 #[derive(Clone, Copy, Debug, Eq)]
-pub struct Coord {
+pub struct GridCoord<const N: usize> {
     x: usize,
     y: usize,
 }
 
-impl Coord {
+/// A location on the 4x4 board.
+pub type Coord = GridCoord<SIZE>;
+
+impl<const N: usize> GridCoord<N> {
     /// Create a new Coord from x and y coordinates
-    pub fn new(x: usize, y: usize) -> Option<Coord> {
-        if x < SIZE && y < SIZE {
-            Some(Coord { x, y })
+    pub fn new(x: usize, y: usize) -> Result<GridCoord<N>, Error> {
+        if x < N && y < N {
+            Ok(GridCoord { x, y })
         } else {
-            None
+            Err(Error::InvalidCoord)
         }
     }
 
     /// Create a new Coord from an index on the board
-    pub fn from_index(index: usize) -> Option<Coord> {
-        if index < SIZE * SIZE {
-            Some(Coord {
-                x: index % SIZE,
-                y: index / SIZE,
+    pub fn from_index(index: usize) -> Result<GridCoord<N>, Error> {
+        if index < N * N {
+            Ok(GridCoord {
+                x: index % N,
+                y: index / N,
             })
         } else {
-            None
+            Err(Error::InvalidCoord)
         }
     }
 
+    /// Iterate over every Coord on the board, in board index order
+    pub fn all() -> impl Iterator<Item = GridCoord<N>> {
+        (0..N * N).map(|index| GridCoord {
+            x: index % N,
+            y: index / N,
+        })
+    }
+
+    /// Iterate over the Coords in a row, from left to right
+    pub fn row(y: usize) -> impl Iterator<Item = GridCoord<N>> {
+        (0..N).filter_map(move |x| GridCoord::new(x, y).ok())
+    }
+
+    /// Iterate over the Coords in a column, from bottom to top
+    pub fn column(x: usize) -> impl Iterator<Item = GridCoord<N>> {
+        (0..N).filter_map(move |y| GridCoord::new(x, y).ok())
+    }
+
+    /// Get the x coordinate
+    pub fn x(&self) -> usize {
+        self.x
+    }
+
+    /// Get the y coordinate
+    pub fn y(&self) -> usize {
+        self.y
+    }
+
+    /// Get the Coord offset from this one, if it is still on the board
+    pub fn offset(&self, dx: isize, dy: isize) -> Result<GridCoord<N>, Error> {
+        let x = self.x as isize + dx;
+        let y = self.y as isize + dy;
+        if x < 0 || y < 0 {
+            return Err(Error::InvalidCoord);
+        }
+        GridCoord::new(x as usize, y as usize)
+    }
+
     /// Get the board index for this Coord
     pub fn board_index(&self) -> usize {
-        self.x + SIZE * self.y
+        self.x + N * self.y
     }
 
+    /// Get the Coord this one moves to when the board is turned a quarter turn clockwise
+    pub fn rotate_cw(&self) -> GridCoord<N> {
+        GridCoord {
+            x: self.y,
+            y: N - self.x - 1,
+        }
+    }
+
+    /// Get the Coord this one moves to when the board is turned a quarter turn anticlockwise
+    pub fn rotate_ccw(&self) -> GridCoord<N> {
+        GridCoord {
+            x: N - self.y - 1,
+            y: self.x,
+        }
+    }
+
+    /// Get the Coord this one moves to when the board is mirrored left to right
+    pub fn mirror(&self) -> GridCoord<N> {
+        GridCoord {
+            x: N - self.x - 1,
+            y: self.y,
+        }
+    }
+
+    /// Get the neighbouring coordinate in a specified direction
+    pub fn neighbour(&self, direction: Direction) -> Option<GridCoord<N>> {
+        match direction {
+            Direction::Up => self.offset(0, 1),
+            Direction::Down => self.offset(0, -1),
+            Direction::Left => self.offset(-1, 0),
+            Direction::Right => self.offset(1, 0),
+        }
+        .ok()
+    }
+}
+
+impl Coord {
     /// Get the corresponding LED's index as wired on the PCB
     fn led_index(&self) -> usize {
         // Odd rows are reversed.
@@ -52,40 +178,15 @@ impl Coord {
             _ => 0,
         }
     }
-
-    /// Get the neighbouring coordinate in a specified direction
-    pub fn neighbour(&self, direction: Direction) -> Option<Coord> {
-        // We need to check that underflow will not occur.
-        // No need to worry about components > SIZE, Coord::new() will handle this.
-        // TODO: investigate using i8 instead of usize to make this much neater.
-        match direction {
-            Direction::Up => Coord::new(self.x, self.y + 1),
-            Direction::Down => {
-                if self.y == 0 {
-                    None
-                } else {
-                    Coord::new(self.x, self.y - 1)
-                }
-            }
-            Direction::Left => {
-                if self.x == 0 {
-                    None
-                } else {
-                    Coord::new(self.x - 1, self.y)
-                }
-            }
-            Direction::Right => Coord::new(self.x + 1, self.y),
-        }
-    }
 }
 
-impl PartialEq for Coord {
+impl<const N: usize> PartialEq for GridCoord<N> {
     fn eq(&self, other: &Self) -> bool {
         self.x == other.x && self.y == other.y
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Board {
     leds: [RGB8; SIZE * SIZE],
 }
@@ -97,11 +198,37 @@ impl Board {
         }
     }
 
+    /// Create a board from LED colours in the order they are on the PCB
+    pub fn from_leds(leds: [RGB8; SIZE * SIZE]) -> Board {
+        Board { leds }
+    }
+
     /// Set the LED at some location to the provided colour
     pub fn set_led(&mut self, coord: Coord, colour: RGB8) {
         self.leds[coord.led_index()] = colour;
     }
 
+    /// Get the colour of the LED at some location
+    pub fn get_led(&self, coord: Coord) -> RGB8 {
+        self.leds[coord.led_index()]
+    }
+
+    /// Get the LEDs that differ in another board, along with their colour in the other board
+    pub fn diff<'a>(&'a self, other: &'a Board) -> impl Iterator<Item = (Coord, RGB8)> + 'a {
+        Coord::all()
+            .map(move |coord| (coord, other.get_led(coord)))
+            .filter(move |&(coord, colour)| self.get_led(coord) != colour)
+    }
+
+    /// Get a copy of the board turned a quarter turn clockwise
+    pub fn rotated_cw(&self) -> Board {
+        let mut board = Board::new();
+        for coord in Coord::all() {
+            board.set_led(coord.rotate_cw(), self.get_led(coord));
+        }
+        board
+    }
+
     /// Get an iterator to the board's LEDs in the order they are on the PCB
     pub fn into_iter(&self) -> impl Iterator<Item = &RGB8> {
         self.leds.iter()
@@ -109,13 +236,47 @@ impl Board {
 }
 
 pub trait IntoBoard {
-    fn into_board(&self) -> Board;
+    /// Render to a board, without applying orientation or brightness
+    fn into_board(&self, context: &RenderContext) -> Board;
+
+    /// Render to a board that is ready to be displayed
+    fn render(&self, context: &RenderContext) -> Board {
+        context.finish(self.into_board(context))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_opposite() {
+        for &direction in Direction::ALL.iter() {
+            assert_ne!(direction.opposite(), direction);
+            assert_eq!(direction.opposite().opposite(), direction);
+        }
+        assert_eq!(Direction::Left.opposite(), Direction::Right);
+    }
+
+    #[test]
+    fn test_rotate() {
+        for (index, &direction) in Direction::ALL.iter().enumerate() {
+            let next = Direction::ALL[(index + 1) % Direction::ALL.len()];
+            assert_eq!(direction.rotate_cw(), next);
+            assert_eq!(next.rotate_ccw(), direction);
+            assert_eq!(direction.rotate_cw().rotate_cw(), direction.opposite());
+        }
+    }
+
+    #[test]
+    fn test_direction_serialisation() {
+        let mut bytes = [0; 1];
+        for &direction in Direction::ALL.iter() {
+            postcard::to_slice(&direction, &mut bytes).unwrap();
+            assert_eq!(postcard::from_bytes::<Direction>(&bytes), Ok(direction));
+        }
+    }
+
     #[test]
     fn test_new_valid_coord() {
         let (x, y) = (0, 3);
@@ -125,7 +286,7 @@ mod tests {
 
     #[test]
     fn test_new_invalid_coord() {
-        assert!(Coord::new(0, SIZE).is_none())
+        assert_eq!(Coord::new(0, SIZE), Err(Error::InvalidCoord))
     }
 
     #[test]
@@ -140,7 +301,7 @@ mod tests {
 
     #[test]
     fn test_from_invalid_index() {
-        assert!(Coord::from_index(SIZE * SIZE).is_none())
+        assert_eq!(Coord::from_index(SIZE * SIZE), Err(Error::InvalidCoord))
     }
 
     #[test]
@@ -151,19 +312,116 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_all() {
+        assert_eq!(Coord::all().count(), SIZE * SIZE);
+        for (index, coord) in Coord::all().enumerate() {
+            assert_eq!(coord.board_index(), index);
+        }
+    }
+
+    #[test]
+    fn test_row_and_column() {
+        let row: heapless::Vec<Coord, SIZE> = Coord::row(2).collect();
+        assert_eq!(row.len(), SIZE);
+        assert!(row
+            .iter()
+            .enumerate()
+            .all(|(x, c)| (c.x(), c.y()) == (x, 2)));
+
+        let column: heapless::Vec<Coord, SIZE> = Coord::column(1).collect();
+        assert_eq!(column.len(), SIZE);
+        assert!(column
+            .iter()
+            .enumerate()
+            .all(|(y, c)| (c.x(), c.y()) == (1, y)));
+
+        assert_eq!(Coord::row(SIZE).count(), 0);
+        assert_eq!(Coord::column(SIZE).count(), 0);
+    }
+
+    #[test]
+    fn test_offset() {
+        let coord = Coord::new(1, 2).unwrap();
+        assert_eq!(coord.offset(0, 0), Ok(coord));
+        assert_eq!(coord.offset(2, -2), Coord::new(3, 0));
+        assert_eq!(coord.offset(-2, 0), Err(Error::InvalidCoord));
+        assert_eq!(coord.offset(0, 2), Err(Error::InvalidCoord));
+    }
+
     #[test]
     fn test_neighbour() {
         let coord = Coord::new(0, 0).unwrap();
-        assert_eq!(coord.neighbour(Direction::Up), Coord::new(0, 1));
+        assert_eq!(coord.neighbour(Direction::Up), Coord::new(0, 1).ok());
         assert_eq!(coord.neighbour(Direction::Down), None);
         assert_eq!(coord.neighbour(Direction::Left), None);
-        assert_eq!(coord.neighbour(Direction::Right), Coord::new(1, 0));
+        assert_eq!(coord.neighbour(Direction::Right), Coord::new(1, 0).ok());
 
         let coord = Coord::new(3, 3).unwrap();
         assert_eq!(coord.neighbour(Direction::Up), None);
-        assert_eq!(coord.neighbour(Direction::Down), Coord::new(3, 2));
-        assert_eq!(coord.neighbour(Direction::Left), Coord::new(2, 3));
+        assert_eq!(coord.neighbour(Direction::Down), Coord::new(3, 2).ok());
+        assert_eq!(coord.neighbour(Direction::Left), Coord::new(2, 3).ok());
         assert_eq!(coord.neighbour(Direction::Right), None);
+
+        let coord = Coord::new(1, 2).unwrap();
+        assert_eq!(coord.neighbour(Direction::Up), Coord::new(1, 3).ok());
+        assert_eq!(coord.neighbour(Direction::Down), Coord::new(1, 1).ok());
+        assert_eq!(coord.neighbour(Direction::Left), Coord::new(0, 2).ok());
+        assert_eq!(coord.neighbour(Direction::Right), Coord::new(2, 2).ok());
+    }
+
+    #[test]
+    fn test_get_led() {
+        let mut board = Board::new();
+        let coord = Coord::new(1, 1).unwrap();
+        let colour = RGB8 { r: 1, g: 2, b: 3 };
+        board.set_led(coord, colour);
+        assert_eq!(board.get_led(coord), colour);
+        assert_eq!(board.leds[6], colour);
+    }
+
+    #[test]
+    fn test_diff() {
+        let board1 = Board::new();
+        assert_eq!(board1.diff(&board1).count(), 0);
+
+        let mut board2 = Board::new();
+        let coord = Coord::new(3, 1).unwrap();
+        let colour = RGB8 { r: 1, g: 2, b: 3 };
+        board2.set_led(coord, colour);
+        let mut diff = board1.diff(&board2);
+        assert_eq!(diff.next(), Some((coord, colour)));
+        assert_eq!(diff.next(), None);
+
+        let (_, reverted) = board2.diff(&board1).next().unwrap();
+        assert_eq!(reverted, RGB8::default());
+    }
+
+    #[test]
+    fn test_rotate_cw() {
+        let coord = Coord::new(0, 3).unwrap();
+        assert_eq!(coord.rotate_cw(), Coord::new(3, 3).unwrap());
+        assert_eq!(coord.rotate_cw().rotate_cw(), Coord::new(3, 0).unwrap());
+        let mut rotated = coord;
+        for _ in 0..4 {
+            rotated = rotated.rotate_cw();
+        }
+        assert_eq!(rotated, coord);
+        assert_eq!(coord.rotate_cw().rotate_ccw(), coord);
+        assert_eq!(coord.mirror(), Coord::new(3, 3).unwrap());
+        assert_eq!(Direction::Up.rotate_cw(), Direction::Right);
+        assert_eq!(Direction::Left.mirror(), Direction::Right);
+        assert_eq!(Direction::Up.mirror(), Direction::Up);
+    }
+
+    #[test]
+    fn test_other_sizes() {
+        assert_eq!(GridCoord::<5>::all().count(), 25);
+        assert_eq!(GridCoord::<3>::new(3, 0), Err(Error::InvalidCoord));
+        let corner = GridCoord::<5>::new(4, 4).unwrap();
+        assert_eq!(corner.board_index(), 24);
+        assert_eq!(corner.neighbour(Direction::Right), None);
+        assert_eq!(corner.rotate_cw(), GridCoord::new(4, 0).unwrap());
     }
 
     #[test]
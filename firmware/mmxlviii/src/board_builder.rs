@@ -0,0 +1,284 @@
+use crate::{
+    board::{Coord, SIZE},
+    error::Error,
+    game_board::{
+        EndlessPolicy, GameBoard, History, ScorePolicy, SpawnRate, UndoPolicy, Variant,
+        DEFAULT_FOUR_ODDS, MAX_TILE,
+    },
+    game_stats::GameStats,
+    tile::Tile,
+};
+
+/// Describes a board to set up all at once, e.g. for a puzzle, a restored
+/// save or a test, rather than making a board and then changing it setter by
+/// setter.
+///
+/// Anything not given is as on a new game: no tiles, no score and the
+/// classic rules. How the game is played is set by `variant` and
+/// `time_limit`, rather than a single mode, as the rest of the crate keeps
+/// them apart, e.g. a time attack can be of any variant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameBoardBuilder {
+    tiles: [Tile; SIZE * SIZE],
+    score: u32,
+    seed: Option<u64>,
+    variant: Variant,
+    four_odds: u8,
+    endless: EndlessPolicy,
+    undo_policy: UndoPolicy,
+    spawn_rate: SpawnRate,
+    scoring: ScorePolicy,
+    time_limit: Option<u32>,
+    stats: GameStats,
+    history: History,
+    moves: u32,
+    best_merge: Tile,
+    undos: u8,
+}
+
+impl GameBoardBuilder {
+    pub fn new() -> GameBoardBuilder {
+        GameBoardBuilder::default()
+    }
+
+    /// Start from a board's tiles, score and rules, e.g. to change some of
+    /// them or to carry on a game. The random number generator isn't copied.
+    pub fn from_board(board: &GameBoard) -> GameBoardBuilder {
+        let mut tiles = [Tile::EMPTY; SIZE * SIZE];
+        for (coord, tile) in board.tiles() {
+            tiles[coord.board_index()] = tile;
+        }
+        GameBoardBuilder {
+            tiles,
+            score: board.get_score(),
+            seed: None,
+            variant: *board.rule(),
+            four_odds: board.four_odds(),
+            endless: board.endless(),
+            undo_policy: board.undo_policy(),
+            spawn_rate: board.spawn_rate(),
+            scoring: board.score_policy(),
+            time_limit: board.time_limit(),
+            stats: *board.stats(),
+            history: board.history().clone(),
+            moves: board.get_move_count(),
+            best_merge: board.best_merge(),
+            undos: board.get_undo_count(),
+        }
+    }
+
+    /// Set every tile, in board index order.
+    pub fn tiles(mut self, tiles: [Tile; SIZE * SIZE]) -> GameBoardBuilder {
+        self.tiles = tiles;
+        self
+    }
+
+    /// Set one tile, or `Tile::BLOCKER`.
+    pub fn tile(mut self, coord: Coord, tile: Tile) -> GameBoardBuilder {
+        self.tiles[coord.board_index()] = tile;
+        self
+    }
+
+    pub fn score(mut self, score: u32) -> GameBoardBuilder {
+        self.score = score;
+        self
+    }
+
+    /// Seed the random number generator new tiles are placed with.
+    pub fn seed(mut self, seed: u64) -> GameBoardBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set the variant, whose rule tiles merge by.
+    pub fn variant(mut self, variant: Variant) -> GameBoardBuilder {
+        self.variant = variant;
+        self
+    }
+
+    /// Set how often new tiles are a 4, see `GameBoard::set_four_odds`.
+    pub fn four_odds(mut self, four_odds: u8) -> GameBoardBuilder {
+        self.four_odds = four_odds;
+        self
+    }
+
+    pub fn endless(mut self, policy: EndlessPolicy) -> GameBoardBuilder {
+        self.endless = policy;
+        self
+    }
+
+    pub fn undo_policy(mut self, policy: UndoPolicy) -> GameBoardBuilder {
+        self.undo_policy = policy;
+        self
+    }
+
+    pub fn spawn_rate(mut self, rate: SpawnRate) -> GameBoardBuilder {
+        self.spawn_rate = rate;
+        self
+    }
+
+    pub fn score_policy(mut self, policy: ScorePolicy) -> GameBoardBuilder {
+        self.scoring = policy;
+        self
+    }
+
+    /// Make the game a time attack of some number of ticks.
+    pub fn time_limit(mut self, ticks: u32) -> GameBoardBuilder {
+        self.time_limit = Some(ticks);
+        self
+    }
+
+    /// Carry on the statistics of the games before, see `GameBoard::stats`.
+    pub fn stats(mut self, stats: GameStats) -> GameBoardBuilder {
+        self.stats = stats;
+        self
+    }
+
+    /// Set the moves that can be undone, e.g. from a save.
+    pub fn history(mut self, history: History) -> GameBoardBuilder {
+        self.history = history;
+        self
+    }
+
+    /// Set how many moves have been made this game.
+    pub fn move_count(mut self, moves: u32) -> GameBoardBuilder {
+        self.moves = moves;
+        self
+    }
+
+    /// Set the largest tile made by a merge this game.
+    pub fn best_merge(mut self, best_merge: Tile) -> GameBoardBuilder {
+        self.best_merge = best_merge;
+        self
+    }
+
+    /// Set how many moves have been undone this game.
+    pub fn undos(mut self, undos: u8) -> GameBoardBuilder {
+        self.undos = undos;
+        self
+    }
+
+    /// Make the board. Fails with `InvalidTile` if a tile is larger than
+    /// `MAX_TILE` and isn't a blocker.
+    pub fn build(&self) -> Result<GameBoard, Error> {
+        if self
+            .tiles
+            .iter()
+            .any(|tile| tile.exponent() > MAX_TILE && !tile.is_blocker())
+        {
+            return Err(Error::InvalidTile);
+        }
+        let mut board = GameBoard::with_tiles(self.tiles.map(u8::from));
+        if let Some(seed) = self.seed {
+            board.reseed(seed);
+        }
+        board.set_score(self.score);
+        board.set_rule(self.variant);
+        board.set_four_odds(self.four_odds);
+        board.set_endless(self.endless);
+        board.set_undo_policy(self.undo_policy);
+        board.set_spawn_rate(self.spawn_rate);
+        board.set_score_policy(self.scoring);
+        board.set_time_limit(self.time_limit);
+        board.set_stats(self.stats);
+        board.set_history(self.history.clone());
+        board.set_move_count(self.moves);
+        board.set_best_merge(self.best_merge);
+        board.set_undo_count(self.undos);
+        Ok(board)
+    }
+}
+
+impl Default for GameBoardBuilder {
+    fn default() -> GameBoardBuilder {
+        GameBoardBuilder {
+            tiles: [Tile::EMPTY; SIZE * SIZE],
+            score: 0,
+            seed: None,
+            variant: Variant::default(),
+            four_odds: DEFAULT_FOUR_ODDS,
+            endless: EndlessPolicy::default(),
+            undo_policy: UndoPolicy::default(),
+            spawn_rate: SpawnRate::default(),
+            scoring: ScorePolicy::default(),
+            time_limit: None,
+            stats: GameStats::default(),
+            history: History::default(),
+            moves: 0,
+            best_merge: Tile::EMPTY,
+            undos: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Direction;
+
+    #[test]
+    fn test_build() {
+        let corner = Coord::new(0, 0).unwrap();
+        let board = GameBoardBuilder::new()
+            .tile(corner, Tile::from(11))
+            .tile(Coord::new(1, 0).unwrap(), Tile::BLOCKER)
+            .score(20_000)
+            .variant(Variant::Threes)
+            .spawn_rate(SpawnRate::Double)
+            .time_limit(600)
+            .build()
+            .unwrap();
+        assert_eq!(board.tiles().next(), Some((corner, Tile::from(11))));
+        assert_eq!(board.blockers().count(), 1);
+        assert_eq!(board.get_score(), 20_000);
+        assert_eq!(*board.rule(), Variant::Threes);
+        assert_eq!(board.spawn_rate(), SpawnRate::Double);
+        assert_eq!(board.ticks_left(), Some(600));
+        assert_eq!(board.four_odds(), DEFAULT_FOUR_ODDS);
+
+        assert_eq!(
+            GameBoardBuilder::new()
+                .tile(corner, Tile::from(MAX_TILE + 1))
+                .build(),
+            Err(Error::InvalidTile)
+        );
+    }
+
+    #[test]
+    fn test_from_board() {
+        let mut board = GameBoardBuilder::new()
+            .tiles([1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2].map(Tile::from))
+            .undo_policy(UndoPolicy::Limited(2))
+            .time_limit(600)
+            .build()
+            .unwrap();
+        board.make_move(Direction::Left);
+        let copy = GameBoardBuilder::from_board(&board).build().unwrap();
+        assert_eq!(copy, board);
+        assert_eq!(copy.get_move_count(), 1);
+        assert_eq!(copy.best_merge(), Tile::from(2));
+        assert_eq!(copy.undo_policy(), UndoPolicy::Limited(2));
+        assert_eq!(copy.time_limit(), Some(600));
+        assert_eq!(copy.stats(), board.stats());
+
+        // The copy can be undone just as the board can
+        let mut copy = copy;
+        assert!(copy.undo());
+        assert!(board.undo());
+        assert_eq!(copy, board);
+    }
+
+    #[test]
+    fn test_seed() {
+        let build = |seed| {
+            let mut board = GameBoardBuilder::new().seed(seed).build().unwrap();
+            board.set_random();
+            board
+        };
+        assert_eq!(build(5), build(5));
+        let mut seeded = GameBoard::empty();
+        seeded.reseed(5);
+        seeded.set_random();
+        assert_eq!(build(5), seeded);
+    }
+}
@@ -0,0 +1,72 @@
+//! RAM budget for the library's larger types.
+//!
+//! Each type is checked against its share of the budget when the crate is
+//! built, so growth that would no longer fit is a compile error rather than a
+//! link failure or stack overflow on the device. The checks also run in host
+//! builds, where `usize` is twice as wide, so budgets leave room for that.
+
+use core::mem::size_of;
+
+use crate::{
+    animation::SlideAnimation,
+    autoplay::Autoplay,
+    board::Board,
+    console::LineBuffer,
+    game_board::GameBoard,
+    save::Save,
+    script::{Exhibition, SCRIPT_LENGTH},
+};
+
+/// RAM the firmware is expected to fit in.
+pub const RAM_BYTES: usize = 40 * 1024;
+/// RAM kept free for the stacks of every task.
+pub const STACK_BYTES: usize = 8 * 1024;
+
+/// The game in progress, including its undo history and move log.
+pub const GAME_BOARD_BYTES: usize = 304;
+/// The game played by itself in demo mode, alongside the game in progress.
+pub const DEMO_BYTES: usize = GAME_BOARD_BYTES + 16;
+/// An exhibition script and the game it plays, alongside the game in progress.
+pub const EXHIBITION_BYTES: usize = GAME_BOARD_BYTES + SCRIPT_LENGTH + 32;
+/// A save, as held while it is written or restored.
+pub const SAVE_BYTES: usize = 336;
+/// A slide animation, which keeps every tile's slide from a move.
+pub const ANIMATION_BYTES: usize = 1024;
+/// One rendered frame, of which a few are kept for recording and diffing.
+pub const FRAME_BYTES: usize = 64;
+/// Console input waiting for a full line.
+pub const INPUT_QUEUE_BYTES: usize = 64;
+
+const _: () = assert!(
+    size_of::<GameBoard>() <= GAME_BOARD_BYTES,
+    "GameBoard is over budget"
+);
+const _: () = assert!(
+    size_of::<Autoplay>() <= DEMO_BYTES,
+    "Autoplay is over budget"
+);
+const _: () = assert!(
+    size_of::<Exhibition>() <= EXHIBITION_BYTES,
+    "Exhibition is over budget"
+);
+const _: () = assert!(size_of::<Save>() <= SAVE_BYTES, "Save is over budget");
+const _: () = assert!(
+    size_of::<SlideAnimation>() <= ANIMATION_BYTES,
+    "SlideAnimation is over budget"
+);
+const _: () = assert!(size_of::<Board>() <= FRAME_BYTES, "Board is over budget");
+const _: () = assert!(
+    size_of::<LineBuffer>() <= INPUT_QUEUE_BYTES,
+    "LineBuffer is over budget"
+);
+const _: () = assert!(
+    GAME_BOARD_BYTES
+        + DEMO_BYTES
+        + EXHIBITION_BYTES
+        + SAVE_BYTES
+        + ANIMATION_BYTES
+        + FRAME_BYTES
+        + INPUT_QUEUE_BYTES
+        <= RAM_BYTES - STACK_BYTES,
+    "budgets leave too little RAM for the stacks"
+);
@@ -0,0 +1,254 @@
+use core::fmt::{self, Display, Formatter};
+
+use smart_leds::{
+    colors::{BLACK, BLUE, LIME, RED, WHITE},
+    RGB8,
+};
+
+use crate::{
+    board::{Board, Coord, IntoBoard, SIZE},
+    render::RenderContext,
+};
+
+/// Number of frames each step is shown for, so the supply settles before it is measured.
+pub const STEP_FRAMES: u32 = 120;
+/// Brightness levels stepped through, out of 255.
+pub const LEVELS: [u8; 4] = [31, 63, 127, 255];
+
+/// Current drawn by one colour channel of a WS2812 at full brightness, in µA.
+const CHANNEL_MICROAMPS: u32 = 20_000;
+/// Current drawn by a WS2812 with every channel off, in µA.
+const IDLE_MICROAMPS: u32 = 1_000;
+/// Supply voltage the internal reference was calibrated at in the factory, in mV.
+const REFERENCE_CALIBRATION_MILLIVOLTS: u32 = 3300;
+
+/// Estimate the current drawn by the LEDs showing a board, in mA.
+pub fn estimate_milliamps(board: &Board) -> u32 {
+    let microamps: u32 = board
+        .into_iter()
+        .map(|led| {
+            let channels = u32::from(led.r) + u32::from(led.g) + u32::from(led.b);
+            IDLE_MICROAMPS + channels * CHANNEL_MICROAMPS / 255
+        })
+        .sum();
+    microamps / 1000
+}
+
+/// Convert a reading of the internal voltage reference to the supply voltage, in mV.
+/// `calibration` is the reading taken in the factory with a 3.3 V supply.
+pub fn supply_millivolts(reading: u16, calibration: u16) -> u32 {
+    if reading == 0 {
+        return 0;
+    }
+    REFERENCE_CALIBRATION_MILLIVOLTS * u32::from(calibration) / u32::from(reading)
+}
+
+/// What the LEDs show during a calibration step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalibrationPattern {
+    /// Every LED off, to measure the supply with no load.
+    Off,
+    White,
+    Red,
+    Green,
+    Blue,
+    /// Every other LED white.
+    Checkerboard,
+}
+
+impl CalibrationPattern {
+    /// Patterns shown at every brightness level.
+    const LIT: [CalibrationPattern; 5] = [
+        CalibrationPattern::White,
+        CalibrationPattern::Red,
+        CalibrationPattern::Green,
+        CalibrationPattern::Blue,
+        CalibrationPattern::Checkerboard,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CalibrationPattern::Off => "off",
+            CalibrationPattern::White => "white",
+            CalibrationPattern::Red => "red",
+            CalibrationPattern::Green => "green",
+            CalibrationPattern::Blue => "blue",
+            CalibrationPattern::Checkerboard => "checkerboard",
+        }
+    }
+
+    fn colour(&self) -> RGB8 {
+        match self {
+            CalibrationPattern::Off => BLACK,
+            CalibrationPattern::Red => RED,
+            CalibrationPattern::Green => LIME,
+            CalibrationPattern::Blue => BLUE,
+            CalibrationPattern::White | CalibrationPattern::Checkerboard => WHITE,
+        }
+    }
+}
+
+impl IntoBoard for CalibrationPattern {
+    fn into_board(&self, _context: &RenderContext) -> Board {
+        let mut board = Board::new();
+        for index in 0..SIZE * SIZE {
+            let (x, y) = (index % SIZE, index / SIZE);
+            if *self != CalibrationPattern::Checkerboard || (x + y) % 2 == 0 {
+                board.set_led(Coord::new(x, y).unwrap(), self.colour());
+            }
+        }
+        board
+    }
+}
+
+/// Steps through brightness levels and patterns, measuring the supply at the
+/// end of each step, to find how much current a power supply can deliver
+/// before its voltage sags.
+///
+/// The first step has every LED off, and its measurement is the baseline the
+/// sag of every later step is measured from.
+#[derive(Clone, Debug, Default)]
+pub struct Calibration {
+    step: usize,
+    frames: u32,
+    baseline_millivolts: Option<u32>,
+}
+
+impl Calibration {
+    pub fn new() -> Calibration {
+        Calibration::default()
+    }
+
+    /// Get the pattern and brightness of the current step.
+    /// Returns `None` once every step has been measured.
+    pub fn current(&self) -> Option<(CalibrationPattern, u8)> {
+        if self.step == 0 {
+            return Some((CalibrationPattern::Off, 0));
+        }
+        let index = self.step - 1;
+        let pattern = CalibrationPattern::LIT[index % CalibrationPattern::LIT.len()];
+        let brightness = *LEVELS.get(index / CalibrationPattern::LIT.len())?;
+        Some((pattern, brightness))
+    }
+
+    /// Returns true once every step has been measured.
+    pub fn is_done(&self) -> bool {
+        self.current().is_none()
+    }
+
+    /// Render the current step at its own brightness, ignoring the global brightness.
+    pub fn render(&self, context: &RenderContext) -> Board {
+        let (pattern, brightness) = self.current().unwrap_or((CalibrationPattern::Off, 0));
+        pattern.render(&RenderContext {
+            brightness,
+            ..*context
+        })
+    }
+
+    /// Count a frame shown. Returns true once the current step has been shown
+    /// long enough to be measured.
+    pub fn tick(&mut self) -> bool {
+        self.frames = self.frames.saturating_add(1);
+        self.frames >= STEP_FRAMES
+    }
+
+    /// Record the supply voltage measured at the end of the current step,
+    /// and move on to the next step.
+    pub fn finish_step(&mut self, supply_millivolts: u32) -> Option<CalibrationReport> {
+        let (pattern, brightness) = self.current()?;
+        let baseline = *self.baseline_millivolts.get_or_insert(supply_millivolts);
+        let report = CalibrationReport {
+            pattern,
+            brightness,
+            estimated_milliamps: estimate_milliamps(&self.render(&RenderContext::default())),
+            supply_millivolts,
+            sag_millivolts: baseline.saturating_sub(supply_millivolts),
+        };
+        self.step += 1;
+        self.frames = 0;
+        Some(report)
+    }
+}
+
+/// A measurement of one calibration step, written as one CSV line for the console.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CalibrationReport {
+    pub pattern: CalibrationPattern,
+    pub brightness: u8,
+    pub estimated_milliamps: u32,
+    pub supply_millivolts: u32,
+    pub sag_millivolts: u32,
+}
+
+impl CalibrationReport {
+    /// Column names of the lines written by `Display`.
+    pub const HEADER: &'static str = "pattern,brightness,estimated mA,supply mV,sag mV";
+}
+
+impl Display for CalibrationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{}",
+            self.pattern.name(),
+            self.brightness,
+            self.estimated_milliamps,
+            self.supply_millivolts,
+            self.sag_millivolts
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::String;
+
+    use super::*;
+    use core::fmt::Write;
+
+    #[test]
+    fn test_estimate_milliamps() {
+        assert_eq!(estimate_milliamps(&Board::new()), (SIZE * SIZE) as u32);
+        let white = CalibrationPattern::White.into_board(&RenderContext::default());
+        assert_eq!(estimate_milliamps(&white), (SIZE * SIZE) as u32 * 61);
+    }
+
+    #[test]
+    fn test_supply_millivolts() {
+        assert_eq!(supply_millivolts(1500, 1500), 3300);
+        // A lower supply makes the reference a larger share of full scale
+        assert_eq!(supply_millivolts(1650, 1500), 3000);
+        assert_eq!(supply_millivolts(0, 1500), 0);
+    }
+
+    #[test]
+    fn test_steps() {
+        let mut calibration = Calibration::new();
+        let mut reports = 0;
+        let mut supply = 5000;
+        while !calibration.is_done() {
+            while !calibration.tick() {}
+            let report = calibration.finish_step(supply).unwrap();
+            assert_eq!(report.sag_millivolts, 5000 - supply);
+            supply -= 10;
+            reports += 1;
+        }
+        assert_eq!(reports, 1 + LEVELS.len() * CalibrationPattern::LIT.len());
+        assert_eq!(calibration.finish_step(supply), None);
+    }
+
+    #[test]
+    fn test_brighter_steps_draw_more() {
+        let mut calibration = Calibration::new();
+        let off = calibration.finish_step(5000).unwrap();
+        let dim_white = calibration.finish_step(5000).unwrap();
+        assert_eq!(off.pattern, CalibrationPattern::Off);
+        assert_eq!(dim_white.pattern, CalibrationPattern::White);
+        assert!(dim_white.estimated_milliamps > off.estimated_milliamps);
+
+        let mut line: String<64> = String::new();
+        write!(line, "{}", dim_white).unwrap();
+        assert!(line.starts_with("white,31,"));
+        assert!(line.ends_with(",5000,0"));
+    }
+}
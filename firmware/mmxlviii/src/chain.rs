@@ -0,0 +1,97 @@
+use smart_leds::RGB8;
+
+use crate::{
+    board::{Board, SIZE},
+    error::Error,
+};
+
+/// Number of LEDs on a single panel.
+pub const PANEL_LEDS: usize = SIZE * SIZE;
+
+/// Panels of LEDs chained on a single data line, each passing data on to the next.
+///
+/// Panel 0 is the one connected to the MCU. Each panel is drawn as a normal
+/// `Board`, so the game doesn't need to know how many panels there are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chain<const PANELS: usize> {
+    panels: [Board; PANELS],
+}
+
+impl<const PANELS: usize> Chain<PANELS> {
+    /// Number of LEDs across the whole chain.
+    pub const LEDS: usize = PANELS * PANEL_LEDS;
+
+    /// Create a chain with every LED off.
+    pub fn new() -> Chain<PANELS> {
+        Chain {
+            panels: [Board::new(); PANELS],
+        }
+    }
+
+    /// Get the number of panels in the chain.
+    pub fn len(&self) -> usize {
+        PANELS
+    }
+
+    pub fn is_empty(&self) -> bool {
+        PANELS == 0
+    }
+
+    /// Get the panel at some position along the chain.
+    pub fn panel(&self, index: usize) -> Result<&Board, Error> {
+        self.panels.get(index).ok_or(Error::InvalidPanel)
+    }
+
+    /// Replace the panel at some position along the chain.
+    pub fn set_panel(&mut self, index: usize, board: Board) -> Result<(), Error> {
+        let panel = self.panels.get_mut(index).ok_or(Error::InvalidPanel)?;
+        *panel = board;
+        Ok(())
+    }
+
+    /// Get an iterator to every LED in the order they are sent down the data line.
+    pub fn leds(&self) -> impl Iterator<Item = &RGB8> {
+        self.panels.iter().flat_map(|panel| panel.into_iter())
+    }
+}
+
+impl<const PANELS: usize> Default for Chain<PANELS> {
+    fn default() -> Chain<PANELS> {
+        Chain::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smart_leds::colors::{BLACK, BLUE, RED};
+
+    use super::*;
+    use crate::board::Coord;
+
+    #[test]
+    fn test_panel_order() {
+        let mut first = Board::new();
+        first.set_led(Coord::new(0, 0).unwrap(), RED);
+        let mut second = Board::new();
+        second.set_led(Coord::new(0, 0).unwrap(), BLUE);
+
+        let mut chain = Chain::<2>::new();
+        chain.set_panel(0, first).unwrap();
+        chain.set_panel(1, second).unwrap();
+        assert_eq!(chain.panel(1), Ok(&second));
+
+        let leds = chain.leds().collect::<heapless::Vec<_, 32>>();
+        assert_eq!(leds.len(), Chain::<2>::LEDS);
+        assert_eq!(*leds[0], RED);
+        assert_eq!(*leds[PANEL_LEDS], BLUE);
+        assert_eq!(*leds[1], BLACK);
+    }
+
+    #[test]
+    fn test_invalid_panel() {
+        let mut chain = Chain::<1>::new();
+        assert_eq!(chain.len(), 1);
+        assert_eq!(chain.panel(1), Err(Error::InvalidPanel));
+        assert_eq!(chain.set_panel(1, Board::new()), Err(Error::InvalidPanel));
+    }
+}
@@ -0,0 +1,92 @@
+use smart_leds::{
+    colors::{BLUE, CYAN, LIME, MAGENTA, ORANGE, RED, WHITE, YELLOW},
+    RGB8,
+};
+use wyhash::wyhash;
+
+use crate::{
+    board::{Board, Coord, IntoBoard, SIZE},
+    game_board::GameBoard,
+    render::RenderContext,
+};
+
+/// Colours a checksum is drawn in, chosen to be told apart at a glance.
+const COLOURS: [RGB8; 8] = [RED, LIME, BLUE, YELLOW, CYAN, MAGENTA, WHITE, ORANGE];
+/// Bits of the hash shown by each pixel.
+const BITS_PER_PIXEL: u32 = 3;
+/// Pixels the checksum is drawn with, in the middle of the board, top left first.
+const PIXELS: [(usize, usize); 4] = [(1, 2), (2, 2), (1, 1), (2, 1)];
+
+/// A board's tiles and score as four coloured pixels.
+///
+/// Two boards that claim to have played the same seeded game can be checked
+/// side by side: if their checksums differ, so do their boards. Equal
+/// checksums only make it very likely that they match, as 4096 are possible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoardChecksum {
+    hash: u64,
+}
+
+impl BoardChecksum {
+    pub fn from_board(board: &GameBoard) -> BoardChecksum {
+        let mut bytes = [0; SIZE * SIZE + 4];
+        bytes[..SIZE * SIZE].copy_from_slice(&board.tile_array());
+        bytes[SIZE * SIZE..].copy_from_slice(&board.get_score().to_le_bytes());
+        BoardChecksum {
+            hash: wyhash(&bytes, 0),
+        }
+    }
+
+    /// Get the colours of the four pixels, top left first.
+    pub fn colours(&self) -> [RGB8; PIXELS.len()] {
+        let mut colours = [RGB8::default(); PIXELS.len()];
+        for (index, colour) in colours.iter_mut().enumerate() {
+            let bits = self.hash >> (index as u32 * BITS_PER_PIXEL);
+            *colour = COLOURS[bits as usize % COLOURS.len()];
+        }
+        colours
+    }
+}
+
+impl IntoBoard for BoardChecksum {
+    fn into_board(&self, _context: &RenderContext) -> Board {
+        let mut board = Board::new();
+        for (&(x, y), &colour) in PIXELS.iter().zip(self.colours().iter()) {
+            board.set_led(Coord::new(x, y).unwrap(), colour);
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Direction;
+
+    #[test]
+    fn test_same_game_same_checksum() {
+        let mut first = GameBoard::new_game_with_seed(11);
+        let mut second = GameBoard::new_game_with_seed(11);
+        for &direction in Direction::ALL.iter() {
+            first.make_move(direction);
+            second.make_move(direction);
+        }
+        assert_eq!(
+            BoardChecksum::from_board(&first).colours(),
+            BoardChecksum::from_board(&second).colours()
+        );
+    }
+
+    #[test]
+    fn test_score_changes_checksum() {
+        // Same tiles, different scores
+        let mut merged = GameBoard::with_tiles([1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        merged.make_move(Direction::Left);
+        let unmerged = GameBoard::with_tiles(merged.tile_array());
+        assert_ne!(merged.get_score(), unmerged.get_score());
+        assert_ne!(
+            BoardChecksum::from_board(&merged),
+            BoardChecksum::from_board(&unmerged)
+        );
+    }
+}
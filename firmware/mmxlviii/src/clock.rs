@@ -0,0 +1,207 @@
+use smart_leds::{colors::CYAN, RGB8};
+use wyhash::wyhash;
+
+use crate::{
+    board::{Board, Coord, IntoBoard, SIZE},
+    numerals,
+    render::RenderContext,
+};
+
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+const CLOCK_COLOUR: RGB8 = CYAN;
+/// Mixed into daily seeds, so they don't match seeds picked for other reasons.
+const DAILY_SALT: u64 = 0x2048_da11;
+
+/// Time of day, kept in software as the board has no RTC crystal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimeOfDay {
+    seconds: u32,
+}
+
+impl TimeOfDay {
+    /// Create a time from hours and minutes.
+    /// Returns `None` if either is out of range.
+    pub fn new(hours: u32, minutes: u32) -> Option<TimeOfDay> {
+        if hours < 24 && minutes < 60 {
+            Some(TimeOfDay {
+                seconds: (hours * 60 + minutes) * 60,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Parse a time written as `HH:MM`.
+    pub fn parse(text: &str) -> Option<TimeOfDay> {
+        let (hours, minutes) = text.split_once(':')?;
+        TimeOfDay::new(hours.parse().ok()?, minutes.parse().ok()?)
+    }
+
+    pub fn hours(&self) -> u32 {
+        self.seconds / 3600
+    }
+
+    pub fn minutes(&self) -> u32 {
+        self.seconds / 60 % 60
+    }
+
+    /// Advance the time, wrapping at midnight.
+    pub fn tick(&mut self, seconds: u32) {
+        self.seconds = (self.seconds + seconds % SECONDS_PER_DAY) % SECONDS_PER_DAY;
+    }
+}
+
+/// A calendar date, given by the host as the board has no RTC crystal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    /// Create a date from its year, month and day of the month, each counted from 1.
+    /// Returns `None` if the day isn't in that month.
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Date> {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days = match month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            1..=12 => 31,
+            _ => return None,
+        };
+        if (1..=days).contains(&day) {
+            Some(Date { year, month, day })
+        } else {
+            None
+        }
+    }
+
+    /// Parse a date written as `YYYY-MM-DD`.
+    pub fn parse(text: &str) -> Option<Date> {
+        let mut parts = text.splitn(3, '-');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        Date::new(year, month, day)
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// Get the seed of the day's challenge, so every board given the same
+    /// date plays the same tiles.
+    pub fn daily_seed(&self) -> u64 {
+        let [high, low] = self.year.to_le_bytes();
+        wyhash(&[high, low, self.month, self.day], DAILY_SALT)
+    }
+}
+
+/// Shows the time as a binary clock: one column per digit of `HH:MM`,
+/// with the least significant bit at the bottom.
+/// Numeral styles that show digits in turn show the four digits in turn.
+impl IntoBoard for TimeOfDay {
+    fn into_board(&self, context: &RenderContext) -> Board {
+        let base = context.numerals.base();
+        let digits = [
+            self.hours() / base,
+            self.hours() % base,
+            self.minutes() / base,
+            self.minutes() % base,
+        ];
+        if context.numerals.is_in_turn() {
+            let digits = digits.map(|digit| digit as u8);
+            return numerals::render_in_turn(&digits, context.numerals, CLOCK_COLOUR, context);
+        }
+
+        let mut board = Board::new();
+        for (x, digit) in digits.iter().enumerate() {
+            for y in 0..SIZE {
+                if digit & (1 << y) != 0 {
+                    board.set_led(Coord::new(x, y).unwrap(), CLOCK_COLOUR);
+                }
+            }
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smart_leds::colors::BLACK;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let time = TimeOfDay::parse("13:07").unwrap();
+        assert_eq!((time.hours(), time.minutes()), (13, 7));
+        assert_eq!(TimeOfDay::parse("24:00"), None);
+        assert_eq!(TimeOfDay::parse("12:60"), None);
+        assert_eq!(TimeOfDay::parse("noon"), None);
+    }
+
+    #[test]
+    fn test_date() {
+        let date = Date::parse("2024-02-29").unwrap();
+        assert_eq!((date.year(), date.month(), date.day()), (2024, 2, 29));
+        assert_eq!(Date::parse("2023-02-29"), None);
+        assert_eq!(Date::parse("1900-02-29"), None);
+        assert!(Date::parse("2000-02-29").is_some());
+        assert_eq!(Date::parse("2024-04-31"), None);
+        assert_eq!(Date::parse("2024-13-01"), None);
+        assert_eq!(Date::parse("2024-01-00"), None);
+        assert_eq!(Date::parse("2024-01"), None);
+        assert_eq!(Date::parse("today"), None);
+    }
+
+    #[test]
+    fn test_daily_seed() {
+        let date = Date::new(2024, 6, 1).unwrap();
+        assert_eq!(
+            date.daily_seed(),
+            Date::parse("2024-06-01").unwrap().daily_seed()
+        );
+        assert_ne!(
+            date.daily_seed(),
+            Date::new(2024, 6, 2).unwrap().daily_seed()
+        );
+        assert_ne!(
+            date.daily_seed(),
+            Date::new(2025, 6, 1).unwrap().daily_seed()
+        );
+    }
+
+    #[test]
+    fn test_tick() {
+        let mut time = TimeOfDay::new(23, 59).unwrap();
+        time.tick(59);
+        assert_eq!((time.hours(), time.minutes()), (23, 59));
+        time.tick(1);
+        assert_eq!((time.hours(), time.minutes()), (0, 0));
+    }
+
+    #[test]
+    fn test_binary_clock() {
+        let board = TimeOfDay::new(21, 58)
+            .unwrap()
+            .into_board(&RenderContext::default());
+        let lit = |x, y| board.get_led(Coord::new(x, y).unwrap()) != BLACK;
+        // 2 = 0b0010
+        assert!(!lit(0, 0) && lit(0, 1) && !lit(0, 2));
+        // 1 = 0b0001
+        assert!(lit(1, 0) && !lit(1, 1));
+        // 8 = 0b1000
+        assert!(!lit(3, 0) && lit(3, 3));
+    }
+}
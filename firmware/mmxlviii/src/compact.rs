@@ -0,0 +1,72 @@
+use crate::{board::SIZE, crc::crc16, error::Error};
+
+/// Bytes taken by the tiles, packed two to a byte.
+const TILES_SIZE: usize = SIZE * SIZE / 2;
+
+/// Size of a packed board: the tiles, the score, then a CRC-16 of both.
+pub const PACKED_SIZE: usize = TILES_SIZE + 4 + 2;
+
+/// Pack a board's tiles two to a byte, then its score, then a CRC-16 of both.
+/// Fails with `InvalidTile` if a tile doesn't fit in four bits, i.e. it is
+/// larger than 32768 or a blocker.
+pub fn pack(tiles: &[u8; SIZE * SIZE], score: u32) -> Result<[u8; PACKED_SIZE], Error> {
+    let mut packed = [0; PACKED_SIZE];
+    for (byte, pair) in packed.iter_mut().zip(tiles.chunks(2)) {
+        if pair.iter().any(|&tile| tile > 0x0F) {
+            return Err(Error::InvalidTile);
+        }
+        *byte = pair[0] << 4 | pair[1];
+    }
+    packed[TILES_SIZE..TILES_SIZE + 4].copy_from_slice(&score.to_le_bytes());
+    let crc = crc16(&packed[..TILES_SIZE + 4]);
+    packed[TILES_SIZE + 4..].copy_from_slice(&crc.to_le_bytes());
+    Ok(packed)
+}
+
+/// Unpack a board's tiles and score, checking the CRC.
+pub fn unpack(packed: &[u8; PACKED_SIZE]) -> Result<([u8; SIZE * SIZE], u32), Error> {
+    let crc = u16::from_le_bytes([packed[TILES_SIZE + 4], packed[TILES_SIZE + 5]]);
+    if crc16(&packed[..TILES_SIZE + 4]) != crc {
+        return Err(Error::SaveCorrupt);
+    }
+    let mut tiles = [0; SIZE * SIZE];
+    for (pair, &byte) in tiles.chunks_mut(2).zip(packed.iter()) {
+        pair[0] = byte >> 4;
+        pair[1] = byte & 0x0F;
+    }
+    let mut score = [0; 4];
+    score.copy_from_slice(&packed[TILES_SIZE..TILES_SIZE + 4]);
+    Ok((tiles, u32::from_le_bytes(score)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_board::BLOCKER;
+
+    const TILES: [u8; SIZE * SIZE] = [11, 10, 9, 8, 1, 2, 3, 4, 0, 0, 0, 0, 15, 0, 0, 1];
+
+    #[test]
+    fn test_round_trip() {
+        let packed = pack(&TILES, 20_480).unwrap();
+        assert_eq!(packed[0], 0xBA);
+        assert_eq!(unpack(&packed), Ok((TILES, 20_480)));
+    }
+
+    #[test]
+    fn test_corrupt() {
+        let mut packed = pack(&TILES, 20_480).unwrap();
+        packed[3] ^= 0x01;
+        assert_eq!(unpack(&packed), Err(Error::SaveCorrupt));
+        assert_eq!(unpack(&[0xFF; PACKED_SIZE]), Err(Error::SaveCorrupt));
+    }
+
+    #[test]
+    fn test_unpackable_tile() {
+        let mut tiles = TILES;
+        tiles[5] = 16;
+        assert_eq!(pack(&tiles, 0), Err(Error::InvalidTile));
+        tiles[5] = BLOCKER;
+        assert_eq!(pack(&tiles, 0), Err(Error::InvalidTile));
+    }
+}
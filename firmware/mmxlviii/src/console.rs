@@ -0,0 +1,400 @@
+use heapless::{String, Vec};
+
+#[cfg(feature = "ai")]
+use crate::ai::Strategy;
+#[cfg(feature = "god-mode")]
+use crate::{board::Coord, game_board::MAX_TILE, tile::Tile};
+use crate::{
+    diagnostics::StatusLedPolicy,
+    game_board::{ScorePolicy, SpawnRate, UndoPolicy},
+    input::Shortcut,
+    numerals::NumeralStyle,
+    render::Orientation,
+};
+
+/// Maximum length of a line sent to the console.
+pub const LINE_LENGTH: usize = 32;
+
+/// Commands accepted over the debug console.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command<'a> {
+    /// Report the device's ID and friendly name.
+    Id,
+    /// Set the device's friendly name.
+    Name(&'a str),
+    /// Receive a provisioning blob, sent as raw bytes after the command.
+    Provision,
+    /// Dump the most recently rendered frames.
+    Frames,
+    /// Start or stop streaming rendered frames to the host.
+    Mirror(bool),
+    /// Start or stop broadcasting frames and the board state for spectators.
+    Spectate(bool),
+    /// Enable or disable the ghost-tile preview of held moves.
+    Preview(bool),
+    /// Opt in to or out of keeping usage counters.
+    Telemetry(bool),
+    /// Dump the usage counters.
+    Counters,
+    /// Dump the statistics of every game played.
+    Stats,
+    /// Print the current board and score.
+    Board,
+    /// Report a share code for the current board.
+    Share,
+    /// Dump the most recent moves and the tiles added after them.
+    Moves,
+    /// Set the time of day, written as `HH:MM`.
+    Time(&'a str),
+    /// Start the daily challenge of a date, written as `YYYY-MM-DD`.
+    Daily(&'a str),
+    /// Choose what the status LED indicates.
+    StatusLed(StatusLedPolicy),
+    /// Choose how numbers such as the score are drawn.
+    Numerals(NumeralStyle),
+    /// Choose what a short press of B does.
+    Shortcut(Shortcut),
+    /// Choose how undoing moves is limited, for this game and those after it.
+    Undo(UndoPolicy),
+    /// Choose how many tiles are added after each move, for this game and those after it.
+    Spawns(SpawnRate),
+    /// Choose how merges are scored, for this game and those after it.
+    Scoring(ScorePolicy),
+    /// Make this game and those after it a time attack of some number of
+    /// seconds, or untimed with `None`.
+    TimeAttack(Option<u32>),
+    /// Choose how the demo plays, starting it again if it's running.
+    #[cfg(feature = "ai")]
+    Demo(Strategy),
+    /// Choose which way up the board is, or `None` to detect it at boot.
+    Orient(Option<Orientation>),
+    /// Report stack usage.
+    Stack,
+    /// Report how long frames take to build.
+    FrameTime,
+    /// Start or stop playing random moves to soak test the firmware.
+    Soak(bool),
+    /// Step through brightness levels and patterns, reporting the current
+    /// drawn and the supply voltage of each.
+    Calibrate,
+    /// Start or stop the exhibition demo stored in flash.
+    Exhibit(bool),
+    /// Receive a demo script of some length, sent as raw bytes after the
+    /// command, and run it.
+    Script(usize),
+    /// Join a light show as the board at some position in a row of boards,
+    /// written as `unit units`, or leave it with `None`.
+    Show(Option<(u8, u8)>),
+    /// Bring a light show in step with the leader's frame, as relayed by the host.
+    Sync(u32),
+    /// Cheat: put a tile in any empty space.
+    #[cfg(feature = "god-mode")]
+    Spawn(Coord, Tile),
+    /// Cheat: turn the largest tile into a 2048.
+    #[cfg(feature = "god-mode")]
+    Win,
+    /// Cheat: let the AI play some moves.
+    #[cfg(feature = "god-mode")]
+    FastForward(u32),
+    /// Cheat: show an effect that's otherwise slow to reach.
+    #[cfg(feature = "god-mode")]
+    Effect(Effect),
+}
+
+/// Effects that can be shown on demand with `Command::Effect`.
+#[cfg(feature = "god-mode")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    /// The device ID pattern shown at boot.
+    Boot,
+    /// The clock shown once the game has been left idle.
+    Idle,
+    /// Tiles sliding, without the move being made.
+    Slide,
+}
+
+impl<'a> Command<'a> {
+    /// Parse a single line of console input.
+    pub fn parse(line: &'a str) -> Option<Command<'a>> {
+        let line = line.trim();
+        let (word, argument) = match line.find(' ') {
+            Some(index) => (&line[..index], line[index + 1..].trim()),
+            None => (line, ""),
+        };
+        match (word, argument) {
+            ("id", "") => Some(Command::Id),
+            ("name", name) if !name.is_empty() => Some(Command::Name(name)),
+            ("provision", "") => Some(Command::Provision),
+            ("frames", "") => Some(Command::Frames),
+            ("mirror", "on") => Some(Command::Mirror(true)),
+            ("mirror", "off") => Some(Command::Mirror(false)),
+            ("spectate", "on") => Some(Command::Spectate(true)),
+            ("spectate", "off") => Some(Command::Spectate(false)),
+            ("preview", "on") => Some(Command::Preview(true)),
+            ("preview", "off") => Some(Command::Preview(false)),
+            ("telemetry", "on") => Some(Command::Telemetry(true)),
+            ("telemetry", "off") => Some(Command::Telemetry(false)),
+            ("counters", "") => Some(Command::Counters),
+            ("stats", "") => Some(Command::Stats),
+            ("board", "") => Some(Command::Board),
+            ("share", "") => Some(Command::Share),
+            ("moves", "") => Some(Command::Moves),
+            ("time", time) if !time.is_empty() => Some(Command::Time(time)),
+            ("daily", date) if !date.is_empty() => Some(Command::Daily(date)),
+            ("stack", "") => Some(Command::Stack),
+            ("frametime", "") => Some(Command::FrameTime),
+            ("soak", "on") => Some(Command::Soak(true)),
+            ("soak", "off") => Some(Command::Soak(false)),
+            ("calibrate", "") => Some(Command::Calibrate),
+            ("exhibit", "on") => Some(Command::Exhibit(true)),
+            ("exhibit", "off") => Some(Command::Exhibit(false)),
+            ("script", length) => length
+                .parse()
+                .ok()
+                .filter(|&length| length > 0)
+                .map(Command::Script),
+            ("show", "off") => Some(Command::Show(None)),
+            ("show", arguments) => parse_show(arguments),
+            ("sync", frame) => frame.parse().ok().map(Command::Sync),
+            ("led", policy) => StatusLedPolicy::parse(policy).map(Command::StatusLed),
+            #[cfg(feature = "god-mode")]
+            ("spawn", arguments) => parse_spawn(arguments),
+            #[cfg(feature = "god-mode")]
+            ("win", "") => Some(Command::Win),
+            #[cfg(feature = "god-mode")]
+            ("ff", moves) => moves.parse().ok().map(Command::FastForward),
+            #[cfg(feature = "god-mode")]
+            ("effect", "boot") => Some(Command::Effect(Effect::Boot)),
+            #[cfg(feature = "god-mode")]
+            ("effect", "idle") => Some(Command::Effect(Effect::Idle)),
+            #[cfg(feature = "god-mode")]
+            ("effect", "slide") => Some(Command::Effect(Effect::Slide)),
+            ("numerals", style) => NumeralStyle::parse(style).map(Command::Numerals),
+            ("shortcut", shortcut) => Shortcut::parse(shortcut).map(Command::Shortcut),
+            ("undo", policy) => UndoPolicy::parse(policy).map(Command::Undo),
+            ("spawns", rate) => SpawnRate::parse(rate).map(Command::Spawns),
+            ("scoring", policy) => ScorePolicy::parse(policy).map(Command::Scoring),
+            ("timeattack", "off") => Some(Command::TimeAttack(None)),
+            ("timeattack", seconds) => seconds
+                .parse()
+                .ok()
+                .filter(|&seconds| seconds > 0)
+                .map(|seconds| Command::TimeAttack(Some(seconds))),
+            #[cfg(feature = "ai")]
+            ("demo", strategy) => Strategy::parse(strategy).map(Command::Demo),
+            ("orient", "auto") => Some(Command::Orient(None)),
+            ("orient", degrees) => {
+                Orientation::parse(degrees).map(|orientation| Command::Orient(Some(orientation)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parse the arguments of a show command, written as `unit units`, e.g. `1 3`.
+fn parse_show(arguments: &str) -> Option<Command<'_>> {
+    let mut words = arguments.split_whitespace();
+    let unit = words.next()?.parse().ok()?;
+    let units = words.next()?.parse().ok()?;
+    if words.next().is_some() {
+        return None;
+    }
+    Some(Command::Show(Some((unit, units))))
+}
+
+/// Parse the arguments of a spawn command, written as `x y tile`, e.g. `0 3 2048`.
+#[cfg(feature = "god-mode")]
+fn parse_spawn(arguments: &str) -> Option<Command<'_>> {
+    let mut words = arguments.split_whitespace();
+    let x = words.next()?.parse().ok()?;
+    let y = words.next()?.parse().ok()?;
+    let tile: u32 = words.next()?.parse().ok()?;
+    if words.next().is_some() || !tile.is_power_of_two() || tile < 2 {
+        return None;
+    }
+    let exponent = tile.trailing_zeros() as u8;
+    if exponent > MAX_TILE {
+        return None;
+    }
+    Some(Command::Spawn(Coord::new(x, y).ok()?, Tile::from(exponent)))
+}
+
+/// Accumulates bytes received from the host until a full line is available.
+#[derive(Debug, Default)]
+pub struct LineBuffer {
+    bytes: Vec<u8, LINE_LENGTH>,
+    overflowed: bool,
+}
+
+impl LineBuffer {
+    pub fn new() -> LineBuffer {
+        LineBuffer::default()
+    }
+
+    /// Add a received byte to the buffer.
+    /// Returns the completed line when a newline is received.
+    /// Lines that are too long or are not valid UTF-8 are discarded.
+    pub fn push(&mut self, byte: u8) -> Option<String<LINE_LENGTH>> {
+        match byte {
+            b'\r' => None,
+            b'\n' => {
+                let line = if self.overflowed {
+                    None
+                } else {
+                    core::str::from_utf8(&self.bytes).ok().map(String::from)
+                };
+                self.bytes.clear();
+                self.overflowed = false;
+                line
+            }
+            _ => {
+                if self.bytes.push(byte).is_err() {
+                    self.overflowed = true;
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Command::parse("id"), Some(Command::Id));
+        assert_eq!(Command::parse(" id \r"), Some(Command::Id));
+        assert_eq!(Command::parse("name bob"), Some(Command::Name("bob")));
+        assert_eq!(Command::parse("name"), None);
+        assert_eq!(Command::parse("provision"), Some(Command::Provision));
+        assert_eq!(Command::parse("frames"), Some(Command::Frames));
+        assert_eq!(Command::parse("mirror on"), Some(Command::Mirror(true)));
+        assert_eq!(Command::parse("mirror"), None);
+        assert_eq!(
+            Command::parse("spectate off"),
+            Some(Command::Spectate(false))
+        );
+        assert_eq!(Command::parse("spectate now"), None);
+        assert_eq!(Command::parse("preview off"), Some(Command::Preview(false)));
+        assert_eq!(
+            Command::parse("telemetry on"),
+            Some(Command::Telemetry(true))
+        );
+        assert_eq!(Command::parse("counters"), Some(Command::Counters));
+        assert_eq!(Command::parse("share"), Some(Command::Share));
+        assert_eq!(Command::parse("moves"), Some(Command::Moves));
+        assert_eq!(Command::parse("time 12:30"), Some(Command::Time("12:30")));
+        assert_eq!(
+            Command::parse("daily 2024-06-01"),
+            Some(Command::Daily("2024-06-01"))
+        );
+        assert_eq!(Command::parse("daily"), None);
+        assert_eq!(
+            Command::parse("led heartbeat"),
+            Some(Command::StatusLed(StatusLedPolicy::Heartbeat))
+        );
+        assert_eq!(Command::parse("led"), None);
+        assert_eq!(
+            Command::parse("numerals dots"),
+            Some(Command::Numerals(NumeralStyle::Dots))
+        );
+        assert_eq!(
+            Command::parse("shortcut theme"),
+            Some(Command::Shortcut(Shortcut::Theme))
+        );
+        assert_eq!(Command::parse("shortcut mute"), None);
+        assert_eq!(
+            Command::parse("undo penalty 50"),
+            Some(Command::Undo(UndoPolicy::Penalty(50)))
+        );
+        assert_eq!(
+            Command::parse("spawns escalating"),
+            Some(Command::Spawns(SpawnRate::Escalating))
+        );
+        assert_eq!(
+            Command::parse("scoring highest"),
+            Some(Command::Scoring(ScorePolicy::HighestTile))
+        );
+        assert_eq!(
+            Command::parse("timeattack 120"),
+            Some(Command::TimeAttack(Some(120)))
+        );
+        assert_eq!(
+            Command::parse("timeattack off"),
+            Some(Command::TimeAttack(None))
+        );
+        assert_eq!(Command::parse("timeattack 0"), None);
+        #[cfg(feature = "ai")]
+        assert_eq!(
+            Command::parse("demo expectimax"),
+            Some(Command::Demo(Strategy::Expectimax))
+        );
+        assert_eq!(Command::parse("orient auto"), Some(Command::Orient(None)));
+        assert_eq!(
+            Command::parse("orient 90"),
+            Some(Command::Orient(Some(Orientation::Clockwise90)))
+        );
+        assert_eq!(Command::parse("stack"), Some(Command::Stack));
+        assert_eq!(Command::parse("frametime"), Some(Command::FrameTime));
+        assert_eq!(Command::parse("stats"), Some(Command::Stats));
+        assert_eq!(Command::parse("soak on"), Some(Command::Soak(true)));
+        assert_eq!(Command::parse("calibrate"), Some(Command::Calibrate));
+        assert_eq!(Command::parse("board"), Some(Command::Board));
+        assert_eq!(Command::parse("exhibit on"), Some(Command::Exhibit(true)));
+        assert_eq!(Command::parse("script 42"), Some(Command::Script(42)));
+        assert_eq!(Command::parse("script 0"), None);
+        assert_eq!(
+            Command::parse("show 1 3"),
+            Some(Command::Show(Some((1, 3))))
+        );
+        assert_eq!(Command::parse("show off"), Some(Command::Show(None)));
+        assert_eq!(Command::parse("show 1"), None);
+        assert_eq!(Command::parse("sync 240"), Some(Command::Sync(240)));
+        assert_eq!(Command::parse("id 3"), None);
+        assert_eq!(Command::parse("foo"), None);
+    }
+
+    #[cfg(feature = "god-mode")]
+    #[test]
+    fn test_parse_cheats() {
+        assert_eq!(
+            Command::parse("spawn 0 3 2048"),
+            Some(Command::Spawn(Coord::new(0, 3).unwrap(), Tile::from(11)))
+        );
+        assert_eq!(Command::parse("spawn 0 4 2048"), None);
+        assert_eq!(Command::parse("spawn 0 3 6"), None);
+        assert_eq!(Command::parse("spawn 0 3"), None);
+        assert_eq!(Command::parse("win"), Some(Command::Win));
+        assert_eq!(Command::parse("ff 50"), Some(Command::FastForward(50)));
+        assert_eq!(Command::parse("ff"), None);
+        assert_eq!(
+            Command::parse("effect slide"),
+            Some(Command::Effect(Effect::Slide))
+        );
+    }
+
+    #[test]
+    fn test_line_buffer() {
+        let mut buffer = LineBuffer::new();
+        for &byte in b"name ".iter() {
+            assert_eq!(buffer.push(byte), None);
+        }
+        for &byte in b"bob\r".iter() {
+            assert_eq!(buffer.push(byte), None);
+        }
+        assert_eq!(buffer.push(b'\n').unwrap(), "name bob");
+        assert_eq!(buffer.push(b'\n').unwrap(), "");
+    }
+
+    #[test]
+    fn test_line_buffer_overflow() {
+        let mut buffer = LineBuffer::new();
+        for _ in 0..LINE_LENGTH + 1 {
+            buffer.push(b'a');
+        }
+        assert_eq!(buffer.push(b'\n'), None);
+        buffer.push(b'a');
+        assert_eq!(buffer.push(b'\n').unwrap(), "a");
+    }
+}
@@ -0,0 +1,84 @@
+use core::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+use smart_leds::hsv::{hsv2rgb, Hsv};
+
+use crate::{
+    board::{Board, Coord, IntoBoard, SIZE},
+    render::RenderContext,
+};
+
+/// Number of bytes in the MCU's unique device ID.
+pub const UID_SIZE: usize = 12;
+
+/// Short identifier for a unit, derived from the MCU's unique device ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceId(u16);
+
+impl DeviceId {
+    /// Derive a device ID from the MCU's 96-bit unique ID.
+    pub fn from_uid(uid: &[u8; UID_SIZE]) -> DeviceId {
+        // 32-bit FNV-1a, folded down to 16 bits
+        let hash = uid.iter().fold(0x811c_9dc5_u32, |hash, &byte| {
+            (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+        });
+        DeviceId((hash >> 16) as u16 ^ hash as u16)
+    }
+
+    /// Get the numeric value of the ID.
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    /// Get a hex digit of the ID, with the most significant digit first.
+    fn digit(&self, index: usize) -> u8 {
+        ((self.0 >> (4 * (SIZE - index - 1))) & 0xF) as u8
+    }
+}
+
+impl Display for DeviceId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:04X}", self.0)
+    }
+}
+
+impl IntoBoard for DeviceId {
+    /// Show each hex digit of the ID as a coloured row, most significant digit at the top.
+    fn into_board(&self, _context: &RenderContext) -> Board {
+        let mut board = Board::new();
+        for row in 0..SIZE {
+            let colour = hsv2rgb(Hsv {
+                hue: self.digit(row) * 16,
+                sat: 255,
+                val: 255,
+            });
+            for coord in Coord::row(SIZE - row - 1) {
+                board.set_led(coord, colour);
+            }
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_uid() {
+        let uid1 = [0; UID_SIZE];
+        let mut uid2 = [0; UID_SIZE];
+        uid2[11] = 1;
+        assert_eq!(DeviceId::from_uid(&uid1), DeviceId::from_uid(&uid1));
+        assert_ne!(DeviceId::from_uid(&uid1), DeviceId::from_uid(&uid2));
+    }
+
+    #[test]
+    fn test_digit() {
+        let id = DeviceId(0x1A2F);
+        assert_eq!(id.digit(0), 0x1);
+        assert_eq!(id.digit(1), 0xA);
+        assert_eq!(id.digit(2), 0x2);
+        assert_eq!(id.digit(3), 0xF);
+    }
+}
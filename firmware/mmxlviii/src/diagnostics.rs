@@ -0,0 +1,404 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// Number of frames between heartbeat blinks.
+pub const HEARTBEAT_FRAMES: u32 = 60;
+/// Number of frames the status LED stays lit for a blink or flash.
+pub const FLASH_FRAMES: u32 = 6;
+/// Number of frames the status LED spends on, then off, while showing a fault.
+pub const FAULT_FRAMES: u32 = 3;
+
+/// Number of times a failed LED write is tried again before the frame is dropped.
+pub const LED_WRITE_RETRIES: u32 = 2;
+/// Number of frames dropped in a row before the refresh rate is reduced.
+const DEGRADE_AFTER_DROPPED: u32 = 8;
+/// Number of frames written in a row before the full refresh rate is restored.
+const RECOVER_AFTER_WRITTEN: u32 = 60;
+/// While degraded, only one frame in this many is written.
+pub const DEGRADED_FRAME_DIVISOR: u32 = 4;
+
+/// Value written over unused stack, so the deepest use can be found later.
+pub const STACK_PAINT: u32 = 0xCCCC_CCCC;
+
+/// Fill a region of unused stack with the paint value.
+pub fn paint_stack(words: &mut [u32]) {
+    for word in words.iter_mut() {
+        *word = STACK_PAINT;
+    }
+}
+
+/// Count how many words at the bottom of a painted stack region have never been used.
+/// The stack grows downwards, so the first word to be overwritten is the last one.
+pub fn unused_stack_words(words: &[u32]) -> usize {
+    words
+        .iter()
+        .take_while(|&&word| word == STACK_PAINT)
+        .count()
+}
+
+/// Records the deepest stack pointer seen on entry to each of `N` tasks.
+///
+/// As all RTIC tasks share one stack, this shows how much a task has to work with
+/// when it is started, including anything it preempted.
+pub struct StackMonitor<const N: usize> {
+    lowest: [AtomicU32; N],
+}
+
+impl<const N: usize> StackMonitor<N> {
+    // Only used to initialise the array, where each copy is a separate atomic
+    #[allow(clippy::declare_interior_mutable_const)]
+    const UNSEEN: AtomicU32 = AtomicU32::new(u32::MAX);
+
+    pub const fn new() -> StackMonitor<N> {
+        StackMonitor {
+            lowest: [Self::UNSEEN; N],
+        }
+    }
+
+    /// Record the stack pointer on entry to a task.
+    pub fn record(&self, task: usize, stack_pointer: u32) {
+        if let Some(lowest) = self.lowest.get(task) {
+            lowest.fetch_min(stack_pointer, Ordering::Relaxed);
+        }
+    }
+
+    /// Get the deepest stack use seen on entry to a task, in bytes below `stack_top`.
+    /// Returns `None` if the task hasn't run yet.
+    pub fn depth(&self, task: usize, stack_top: u32) -> Option<u32> {
+        match self.lowest.get(task)?.load(Ordering::Relaxed) {
+            u32::MAX => None,
+            lowest => Some(stack_top.saturating_sub(lowest)),
+        }
+    }
+}
+
+impl<const N: usize> Default for StackMonitor<N> {
+    fn default() -> StackMonitor<N> {
+        StackMonitor::new()
+    }
+}
+
+/// Times how long frames take to build, in cycles, against a budget.
+///
+/// Frames are only sent to the LEDs once built, so a frame over budget
+/// leaves less time before the next one is due.
+#[derive(Debug)]
+pub struct RenderTimer {
+    budget: u32,
+    last: u32,
+    worst: u32,
+    over_budget: u32,
+}
+
+impl RenderTimer {
+    pub const fn new(budget: u32) -> RenderTimer {
+        RenderTimer {
+            budget,
+            last: 0,
+            worst: 0,
+            over_budget: 0,
+        }
+    }
+
+    /// Note how long a frame took to build, given the cycle counter before and
+    /// after, which may have wrapped in between.
+    /// Returns true if the frame was over budget.
+    pub fn record(&mut self, start: u32, end: u32) -> bool {
+        self.last = end.wrapping_sub(start);
+        self.worst = self.worst.max(self.last);
+        let over = self.last > self.budget;
+        if over {
+            self.over_budget = self.over_budget.saturating_add(1);
+        }
+        over
+    }
+
+    pub fn budget(&self) -> u32 {
+        self.budget
+    }
+
+    /// Get the cycles the most recent frame took.
+    pub fn last(&self) -> u32 {
+        self.last
+    }
+
+    /// Get the most cycles any frame has taken since boot.
+    pub fn worst(&self) -> u32 {
+        self.worst
+    }
+
+    /// Get the number of frames over budget since boot.
+    pub fn over_budget(&self) -> u32 {
+        self.over_budget
+    }
+}
+
+/// A change in how LED writes are being handled, see `LedWriteHealth`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedWriteEvent {
+    /// Frames kept failing to be written, so the refresh rate has been reduced.
+    Degraded,
+    /// Frames are being written again, so the full refresh rate is restored.
+    Recovered,
+}
+
+/// Tracks failed LED writes, so a faulty LED chain slows the display down
+/// rather than stopping the game.
+///
+/// Each frame is retried up to `LED_WRITE_RETRIES` times before it is
+/// dropped. After enough dropped frames in a row only one frame in
+/// `DEGRADED_FRAME_DIVISOR` is written, until writes succeed for a while.
+#[derive(Debug, Default)]
+pub struct LedWriteHealth {
+    failures: u32,
+    dropped_in_row: u32,
+    written_in_row: u32,
+    degraded: bool,
+}
+
+impl LedWriteHealth {
+    pub fn new() -> LedWriteHealth {
+        LedWriteHealth::default()
+    }
+
+    /// Get the number of failed writes since boot, including retries.
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Returns true while the refresh rate is reduced.
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// Returns true if a frame should be written, or false if it should be
+    /// skipped to reduce the refresh rate.
+    pub fn should_write(&self, frame: u32) -> bool {
+        !self.degraded || frame % DEGRADED_FRAME_DIVISOR == 0
+    }
+
+    /// Note that a write failed.
+    pub fn record_failure(&mut self) {
+        self.failures = self.failures.saturating_add(1);
+    }
+
+    /// Note whether a frame was written, after any retries.
+    /// Returns an event only when the refresh rate changes.
+    pub fn record_frame(&mut self, written: bool) -> Option<LedWriteEvent> {
+        if written {
+            self.dropped_in_row = 0;
+            self.written_in_row = self.written_in_row.saturating_add(1);
+            if self.degraded && self.written_in_row >= RECOVER_AFTER_WRITTEN {
+                self.degraded = false;
+                return Some(LedWriteEvent::Recovered);
+            }
+        } else {
+            self.written_in_row = 0;
+            self.dropped_in_row = self.dropped_in_row.saturating_add(1);
+            if !self.degraded && self.dropped_in_row >= DEGRADE_AFTER_DROPPED {
+                self.degraded = true;
+                return Some(LedWriteEvent::Degraded);
+            }
+        }
+        None
+    }
+}
+
+/// What the status LED indicates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StatusLedPolicy {
+    /// Blink once a second to show the firmware is running.
+    #[default]
+    Heartbeat,
+    /// Flash whenever a move is made.
+    MoveFlash,
+    /// Flash whenever data is saved to the EEPROM.
+    SaveActivity,
+    /// Always off.
+    Off,
+}
+
+impl StatusLedPolicy {
+    /// Parse a policy from its console name.
+    pub fn parse(name: &str) -> Option<StatusLedPolicy> {
+        match name {
+            "heartbeat" => Some(StatusLedPolicy::Heartbeat),
+            "move" => Some(StatusLedPolicy::MoveFlash),
+            "save" => Some(StatusLedPolicy::SaveActivity),
+            "off" => Some(StatusLedPolicy::Off),
+            _ => None,
+        }
+    }
+}
+
+/// Drives the status LED according to a policy.
+#[derive(Debug, Default)]
+pub struct StatusLed {
+    policy: StatusLedPolicy,
+    flash_frames: u32,
+    fault: bool,
+}
+
+impl StatusLed {
+    pub fn new(policy: StatusLedPolicy) -> StatusLed {
+        StatusLed {
+            policy,
+            flash_frames: 0,
+            fault: false,
+        }
+    }
+
+    pub fn policy(&self) -> StatusLedPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: StatusLedPolicy) {
+        self.policy = policy;
+        self.flash_frames = 0;
+    }
+
+    /// Note that a move was made.
+    pub fn on_move(&mut self) {
+        if self.policy == StatusLedPolicy::MoveFlash {
+            self.flash_frames = FLASH_FRAMES;
+        }
+    }
+
+    /// Note that data was saved.
+    pub fn on_save(&mut self) {
+        if self.policy == StatusLedPolicy::SaveActivity {
+            self.flash_frames = FLASH_FRAMES;
+        }
+    }
+
+    /// Show a fault with a fast blink, whatever the policy, until it is cleared.
+    pub fn set_fault(&mut self, fault: bool) {
+        self.fault = fault;
+    }
+
+    /// Advance by one frame, returning true if the LED should be lit.
+    pub fn update(&mut self, frame: u32) -> bool {
+        if self.fault {
+            return (frame / FAULT_FRAMES) % 2 == 0;
+        }
+        match self.policy {
+            StatusLedPolicy::Heartbeat => frame % HEARTBEAT_FRAMES < FLASH_FRAMES,
+            StatusLedPolicy::MoveFlash | StatusLedPolicy::SaveActivity => {
+                let lit = self.flash_frames > 0;
+                self.flash_frames = self.flash_frames.saturating_sub(1);
+                lit
+            }
+            StatusLedPolicy::Off => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_timer() {
+        let mut timer = RenderTimer::new(1000);
+        assert!(!timer.record(500, 1500));
+        assert!(timer.record(u32::MAX - 500, 1000));
+        assert!(!timer.record(0, 10));
+        assert_eq!(timer.last(), 10);
+        assert_eq!(timer.worst(), 1501);
+        assert_eq!(timer.over_budget(), 1);
+    }
+
+    #[test]
+    fn test_heartbeat() {
+        let mut led = StatusLed::new(StatusLedPolicy::Heartbeat);
+        assert!(led.update(0));
+        assert!(!led.update(FLASH_FRAMES));
+        assert!(led.update(HEARTBEAT_FRAMES));
+    }
+
+    #[test]
+    fn test_move_flash() {
+        let mut led = StatusLed::new(StatusLedPolicy::MoveFlash);
+        assert!(!led.update(0));
+        led.on_save();
+        assert!(!led.update(1));
+        led.on_move();
+        for frame in 0..FLASH_FRAMES {
+            assert!(led.update(frame));
+        }
+        assert!(!led.update(FLASH_FRAMES));
+    }
+
+    #[test]
+    fn test_off() {
+        let mut led = StatusLed::new(StatusLedPolicy::Off);
+        led.on_move();
+        led.on_save();
+        assert!(!led.update(0));
+    }
+
+    #[test]
+    fn test_fault_overrides_policy() {
+        let mut led = StatusLed::new(StatusLedPolicy::Off);
+        led.set_fault(true);
+        assert!(led.update(0));
+        assert!(!led.update(FAULT_FRAMES));
+        led.set_fault(false);
+        assert!(!led.update(0));
+    }
+
+    #[test]
+    fn test_led_write_health() {
+        let mut health = LedWriteHealth::new();
+        for _ in 0..DEGRADE_AFTER_DROPPED - 1 {
+            health.record_failure();
+            assert_eq!(health.record_frame(false), None);
+        }
+        // A written frame resets the count
+        assert_eq!(health.record_frame(true), None);
+        for _ in 0..DEGRADE_AFTER_DROPPED - 1 {
+            assert_eq!(health.record_frame(false), None);
+        }
+        assert_eq!(health.record_frame(false), Some(LedWriteEvent::Degraded));
+        assert!(health.should_write(0));
+        assert!(!health.should_write(1));
+        assert_eq!(health.failures(), DEGRADE_AFTER_DROPPED - 1);
+
+        for _ in 0..RECOVER_AFTER_WRITTEN - 1 {
+            assert_eq!(health.record_frame(true), None);
+        }
+        assert_eq!(health.record_frame(true), Some(LedWriteEvent::Recovered));
+        assert!(health.should_write(1));
+    }
+
+    #[test]
+    fn test_unused_stack_words() {
+        let mut stack = [0; 8];
+        paint_stack(&mut stack);
+        assert_eq!(unused_stack_words(&stack), 8);
+        stack[5] = 0x1234;
+        assert_eq!(unused_stack_words(&stack), 5);
+    }
+
+    #[test]
+    fn test_stack_monitor() {
+        let monitor = StackMonitor::<2>::new();
+        assert_eq!(monitor.depth(0, 0x2000_3000), None);
+        monitor.record(0, 0x2000_2F00);
+        monitor.record(0, 0x2000_2F80);
+        assert_eq!(monitor.depth(0, 0x2000_3000), Some(0x100));
+        assert_eq!(monitor.depth(1, 0x2000_3000), None);
+        monitor.record(2, 0);
+        assert_eq!(monitor.depth(2, 0x2000_3000), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            StatusLedPolicy::parse("save"),
+            Some(StatusLedPolicy::SaveActivity)
+        );
+        assert_eq!(StatusLedPolicy::parse("blink"), None);
+    }
+}
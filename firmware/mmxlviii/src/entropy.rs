@@ -0,0 +1,167 @@
+use wyhash::{wyhash, wyrng};
+
+use crate::error::Error;
+
+/// Number of identical raw samples in a row that fails the repetition count test.
+pub const REPETITION_CUTOFF: u8 = 16;
+/// Number of debiased bits in each adaptive proportion test window.
+pub const PROPORTION_WINDOW: u16 = 64;
+/// Maximum number of ones, or of zeros, allowed in a single window.
+pub const PROPORTION_CUTOFF: u16 = 52;
+/// Number of debiased bits collected before a seed is available.
+pub const SEED_BITS: u16 = 256;
+
+/// Collects noise from the least significant bits of ADC samples and conditions it into seeds.
+///
+/// Raw bits are debiased with a von Neumann extractor and mixed into the pool with wyhash.
+/// Health tests run on every sample, so a stuck or heavily biased source is detected
+/// rather than silently producing predictable seeds.
+#[derive(Debug, Default)]
+pub struct EntropyPool {
+    state: u64,
+    accumulator: u64,
+    bits: u16,
+    /// First bit of a pair waiting for the von Neumann extractor.
+    pending: Option<bool>,
+    last_sample: Option<u16>,
+    repetitions: u8,
+    window_bits: u16,
+    window_ones: u16,
+}
+
+impl EntropyPool {
+    pub fn new() -> EntropyPool {
+        EntropyPool::default()
+    }
+
+    /// Add a raw ADC sample to the pool.
+    /// Returns an error if the source fails a health test, after which the pool shouldn't be used.
+    pub fn push(&mut self, sample: u16) -> Result<(), Error> {
+        // Repetition count test: a stuck source repeats the same reading
+        if self.last_sample == Some(sample) {
+            self.repetitions += 1;
+            if self.repetitions >= REPETITION_CUTOFF {
+                return Err(Error::EntropyHealth);
+            }
+        } else {
+            self.last_sample = Some(sample);
+            self.repetitions = 1;
+        }
+
+        let bit = sample & 1 == 1;
+        let first = match self.pending.take() {
+            Some(first) => first,
+            None => {
+                self.pending = Some(bit);
+                return Ok(());
+            }
+        };
+        // Von Neumann extractor: 01 and 10 give a bit, 00 and 11 are discarded
+        if first == bit {
+            return Ok(());
+        }
+        self.add_bit(first)
+    }
+
+    /// Add a debiased bit to the pool.
+    fn add_bit(&mut self, bit: bool) -> Result<(), Error> {
+        // Adaptive proportion test: a biased source gives too many ones or zeros
+        self.window_bits += 1;
+        self.window_ones += u16::from(bit);
+        if self.window_bits == PROPORTION_WINDOW {
+            let ones = self.window_ones;
+            self.window_bits = 0;
+            self.window_ones = 0;
+            if ones > PROPORTION_CUTOFF || PROPORTION_WINDOW - ones > PROPORTION_CUTOFF {
+                return Err(Error::EntropyHealth);
+            }
+        }
+
+        self.accumulator = self.accumulator << 1 | u64::from(bit);
+        self.bits = self.bits.saturating_add(1);
+        if self.bits % 64 == 0 {
+            self.state = wyhash(&self.accumulator.to_le_bytes(), self.state);
+        }
+        Ok(())
+    }
+
+    /// Returns true once enough bits have been collected for a seed.
+    pub fn is_ready(&self) -> bool {
+        self.bits >= SEED_BITS
+    }
+
+    /// Get a seed from the pool, or `None` if not enough bits have been collected yet.
+    pub fn seed(&self) -> Option<u64> {
+        if self.is_ready() {
+            Some(wyhash(&self.accumulator.to_le_bytes(), self.state))
+        } else {
+            None
+        }
+    }
+}
+
+/// A stream of seeds derived from a single conditioned seed, one for each new game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedSequence {
+    state: u64,
+}
+
+impl SeedSequence {
+    pub fn new(seed: u64) -> SeedSequence {
+        SeedSequence { state: seed }
+    }
+
+    /// Get the next seed in the sequence.
+    pub fn next_seed(&mut self) -> u64 {
+        wyrng(&mut self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fill a pool from a deterministic but well mixed source.
+    fn fill_pool(pool: &mut EntropyPool, mut state: u64) {
+        while !pool.is_ready() {
+            let sample = (wyrng(&mut state) & 0xFFF) as u16;
+            pool.push(sample).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_seed_after_enough_bits() {
+        let mut pool = EntropyPool::new();
+        assert_eq!(pool.seed(), None);
+        fill_pool(&mut pool, 1);
+        assert!(pool.seed().is_some());
+
+        let mut other = EntropyPool::new();
+        fill_pool(&mut other, 2);
+        assert_ne!(pool.seed(), other.seed());
+    }
+
+    #[test]
+    fn test_stuck_source() {
+        let mut pool = EntropyPool::new();
+        let result = (0..REPETITION_CUTOFF).try_for_each(|_| pool.push(1234));
+        assert_eq!(result, Err(Error::EntropyHealth));
+    }
+
+    #[test]
+    fn test_biased_source() {
+        // Alternating samples pass the repetition test but give a constant debiased bit
+        let mut pool = EntropyPool::new();
+        let result = (0..1000).try_for_each(|i| pool.push(if i % 2 == 0 { 2 } else { 1 }));
+        assert_eq!(result, Err(Error::EntropyHealth));
+        assert!(!pool.is_ready());
+    }
+
+    #[test]
+    fn test_seed_sequence() {
+        let mut sequence = SeedSequence::new(42);
+        let first = sequence.next_seed();
+        assert_ne!(first, sequence.next_seed());
+        assert_eq!(SeedSequence::new(42).next_seed(), first);
+    }
+}
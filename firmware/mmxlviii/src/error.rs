@@ -0,0 +1,50 @@
+use core::fmt::{Display, Formatter};
+
+/// Errors that can occur when using this crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A coordinate or index was outside of the board.
+    InvalidCoord,
+    /// A panel index was outside of the chain of panels.
+    InvalidPanel,
+    /// Saved data could not be decoded or failed its checksum.
+    SaveCorrupt,
+    /// Saved data decoded, but isn't something that could have been saved.
+    SaveImplausible,
+    /// Saved data was written by an incompatible version.
+    UnsupportedVersion,
+    /// Data was too large to fit in the space reserved for it.
+    StorageFull,
+    /// Reading from or writing to storage failed.
+    Storage,
+    /// A name was longer than the space available to store it.
+    NameTooLong,
+    /// The entropy source failed its health tests.
+    EntropyHealth,
+    /// A demo script had an unknown or incomplete instruction.
+    InvalidScript,
+    /// A tile was empty or larger than the largest tile.
+    InvalidTile,
+    /// A tile was put in a space that already has one.
+    SpaceOccupied,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let description = match self {
+            Error::InvalidCoord => "coordinate is outside of the board",
+            Error::InvalidPanel => "panel is outside of the chain",
+            Error::SaveCorrupt => "saved data is corrupt",
+            Error::SaveImplausible => "saved data could not have come from a real game",
+            Error::UnsupportedVersion => "saved data is from an unsupported version",
+            Error::StorageFull => "data is too large for its storage",
+            Error::Storage => "storage could not be accessed",
+            Error::NameTooLong => "name is too long",
+            Error::EntropyHealth => "entropy source failed its health tests",
+            Error::InvalidScript => "script has an invalid instruction",
+            Error::InvalidTile => "tile is outside of the range of tiles",
+            Error::SpaceOccupied => "space already has a tile",
+        };
+        f.write_str(description)
+    }
+}
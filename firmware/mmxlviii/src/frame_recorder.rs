@@ -0,0 +1,72 @@
+use crate::board::Board;
+
+/// Keeps the most recently rendered frames so they can be dumped for debugging.
+pub struct FrameRecorder<const N: usize> {
+    frames: [Board; N],
+    count: u32,
+}
+
+impl<const N: usize> FrameRecorder<N> {
+    pub fn new() -> FrameRecorder<N> {
+        FrameRecorder {
+            frames: [Board::new(); N],
+            count: 0,
+        }
+    }
+
+    /// Record a frame, replacing the oldest frame if the recorder is full.
+    pub fn record(&mut self, frame: Board) {
+        self.frames[self.count as usize % N] = frame;
+        self.count = self.count.wrapping_add(1);
+    }
+
+    /// Get the recorded frames and their frame numbers, oldest first.
+    pub fn frames(&self) -> impl Iterator<Item = (u32, &Board)> + '_ {
+        let start = self.count.saturating_sub(N as u32);
+        (start..self.count).map(move |number| (number, &self.frames[number as usize % N]))
+    }
+}
+
+impl<const N: usize> Default for FrameRecorder<N> {
+    fn default() -> FrameRecorder<N> {
+        FrameRecorder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::Vec;
+    use smart_leds::colors::RED;
+
+    use super::*;
+    use crate::board::Coord;
+
+    fn numbered_frame(number: usize) -> Board {
+        let mut board = Board::new();
+        board.set_led(Coord::from_index(number).unwrap(), RED);
+        board
+    }
+
+    #[test]
+    fn test_partially_full() {
+        let mut recorder = FrameRecorder::<4>::new();
+        assert_eq!(recorder.frames().count(), 0);
+        recorder.record(numbered_frame(0));
+        recorder.record(numbered_frame(1));
+        let numbers: Vec<u32, 4> = recorder.frames().map(|(number, _)| number).collect();
+        assert_eq!(numbers, [0, 1]);
+    }
+
+    #[test]
+    fn test_wrap_around() {
+        let mut recorder = FrameRecorder::<4>::new();
+        for number in 0..6 {
+            recorder.record(numbered_frame(number));
+        }
+        let numbers: Vec<u32, 4> = recorder.frames().map(|(number, _)| number).collect();
+        assert_eq!(numbers, [2, 3, 4, 5]);
+        for (number, frame) in recorder.frames() {
+            assert_eq!(*frame, numbered_frame(number as usize));
+        }
+    }
+}
@@ -1,31 +1,217 @@
-use core::fmt::Debug;
+use core::{
+    array,
+    fmt::{self, Debug, Display, Formatter},
+    iter, mem,
+    num::NonZeroU32,
+};
 
 use heapless::Vec;
 use postcard::{from_bytes, to_slice};
-use rand::RngCore;
+use rand::{RngCore, SeedableRng};
 use serde::{Deserialize, Serialize};
-use smart_leds::{
-    colors::{BLACK, DIM_GRAY, WHITE},
-    hsv::{hsv2rgb, Hsv},
-    RGB8,
-};
 use wyhash::WyRng;
 
-use crate::board::{Board, Coord, Direction, IntoBoard, SIZE};
+use crate::{
+    achievements::{Achievements, MERGE_CHAIN_LENGTH},
+    board::{Board, Coord, Direction, GridCoord, IntoBoard, SIZE},
+    board_builder::GameBoardBuilder,
+    compact,
+    error::Error,
+    game_stats::GameStats,
+    merge_rule::MergeRule,
+    numerals,
+    render::RenderContext,
+    rng_state::{RngState, MAX_DRAWS},
+    tile::Tile,
+};
 
 /// Size of the board serialized in bytes, rounded up to the next 16 bytes.
 pub const BYTES_SIZE: usize = 32;
 
+/// Number of moves that can be undone.
+pub const UNDO_DEPTH: usize = 4;
+
+/// Number of recent moves kept in the move log.
+pub const LOG_LENGTH: usize = 32;
+
+/// New tiles are a 4 one time in this many unless configured otherwise.
+pub const DEFAULT_FOUR_ODDS: u8 = 10;
+
+/// Largest tile, 2^31, the largest whose value still fits in the score.
+pub const MAX_TILE: u8 = 31;
+
+/// A blocker, which fills a space without being a tile. It never moves, and
+/// no tile merges with it, so tiles stop against it as at the edge.
+pub const BLOCKER: u8 = u8::MAX;
+
+/// Value of each empty tile when suggesting a move, relative to one step
+/// of a row or column going against its order.
+const HINT_EMPTY_WEIGHT: i32 = 4;
+
+/// Tiles of an N by N board, one row after another from the bottom.
+type Tiles<const N: usize> = [[u8; N]; N];
+
 #[derive(Debug, PartialEq)]
-enum TileMoveResult {
+enum TileMoveResult<const N: usize> {
     NoMove,
-    Free(Coord),
-    Merge(Coord),
+    Free(GridCoord<N>),
+    /// Where the tile merges, and the tile it makes.
+    Merge(GridCoord<N>, u8),
+}
+
+/// Where a tile went during a move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TileSlide<const N: usize = SIZE> {
+    pub from: GridCoord<N>,
+    pub to: GridCoord<N>,
+    /// The tile before the move, so a merging tile keeps its colour until it lands.
+    pub value: Tile,
+    /// The tile made if the tile merged into the tile at `to`.
+    pub merged: Option<Tile>,
+}
+
+impl<const N: usize> TileSlide<N> {
+    /// Get the tile left at `to` once this tile lands.
+    pub fn result(&self) -> Tile {
+        self.merged.unwrap_or(self.value)
+    }
+}
+
+/// Every tile on the board during a move, including those that stayed put.
+pub type Slides = Vec<TileSlide, { SIZE * SIZE }>;
+
+/// What happened to every tile during a move.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MoveOutcome {
+    slides: Slides,
+}
+
+impl MoveOutcome {
+    /// Returns true if any tiles moved.
+    pub fn moved(&self) -> bool {
+        self.slides.iter().any(|slide| slide.from != slide.to)
+    }
+
+    /// Get where every tile went, including those that stayed put.
+    pub fn slides(&self) -> &[TileSlide] {
+        &self.slides
+    }
+
+    /// Get the tiles that merged. Each merged into the tile at its destination.
+    pub fn merges(&self) -> impl Iterator<Item = &TileSlide> + '_ {
+        self.slides.iter().filter(|slide| slide.merged.is_some())
+    }
+}
+
+/// Serializes tiles as one flat tuple, so a 4x4 board is laid out the same as a 16 byte array.
+mod tiles {
+    use core::{fmt, marker::PhantomData};
+
+    use serde::{
+        de::{self, SeqAccess, Visitor},
+        ser::SerializeTuple,
+        Deserializer, Serializer,
+    };
+
+    use super::Tiles;
+
+    pub fn serialize<S, const N: usize>(tiles: &Tiles<N>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(N * N)?;
+        for tile in tiles.iter().flatten() {
+            tuple.serialize_element(tile)?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D, const N: usize>(deserializer: D) -> Result<Tiles<N>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct TilesVisitor<const N: usize>(PhantomData<Tiles<N>>);
+
+        impl<'de, const N: usize> Visitor<'de> for TilesVisitor<N> {
+            type Value = Tiles<N>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{} tiles", N * N)
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Tiles<N>, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut tiles = [[0; N]; N];
+                for (index, tile) in tiles.iter_mut().flatten().enumerate() {
+                    *tile = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(index, &self))?;
+                }
+                Ok(tiles)
+            }
+        }
+
+        deserializer.deserialize_tuple(N * N, TilesVisitor(PhantomData))
+    }
+}
+
+/// The board's random number generator, which isn't saved with the board.
+///
+/// If it was seeded, the seed and the numbers drawn since are counted, so
+/// it can be saved separately as an `RngState` and restored later, as long as
+/// no more than `MAX_DRAWS` have been drawn.
+#[derive(Clone)]
+struct MyRng<R> {
+    rng: R,
+    seed: u64,
+    draws: u32,
+    seeded: bool,
+}
+
+impl<R> MyRng<R> {
+    fn new(rng: R) -> MyRng<R> {
+        MyRng {
+            rng,
+            seed: 0,
+            draws: 0,
+            seeded: false,
+        }
+    }
+
+    fn state(&self) -> Option<RngState> {
+        (self.seeded && self.draws <= MAX_DRAWS).then_some(RngState {
+            seed: self.seed,
+            draws: self.draws,
+        })
+    }
 }
 
-struct MyRng(WyRng);
+impl<R: RngCore> MyRng<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.draws = self.draws.wrapping_add(1);
+        self.rng.next_u32()
+    }
+}
+
+impl<R: RngCore + SeedableRng> MyRng<R> {
+    /// Seed a generator and draw from it until it's in the given state.
+    fn restore(state: RngState) -> MyRng<R> {
+        let mut rng = MyRng {
+            rng: R::seed_from_u64(state.seed),
+            seed: state.seed,
+            draws: 0,
+            seeded: true,
+        };
+        for _ in 0..state.draws {
+            rng.next_u32();
+        }
+        rng
+    }
+}
 
-impl Serialize for MyRng {
+impl<R> Serialize for MyRng<R> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
@@ -34,22 +220,519 @@ impl Serialize for MyRng {
     }
 }
 
-impl<'de> Deserialize<'de> for MyRng {
-    fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
+impl<'de, R: Default> Deserialize<'de> for MyRng<R> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        Ok(MyRng(WyRng::default()))
+        // Consume the placeholder written by serialize, so following fields line up
+        Option::<()>::deserialize(deserializer)?;
+        Ok(MyRng::new(R::default()))
     }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct GameBoard {
-    tiles: [u8; SIZE * SIZE],
-    rng: MyRng,
+/// The board as it was before a move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Snapshot<const N: usize> {
+    #[serde(with = "tiles")]
+    tiles: Tiles<N>,
     score: u32,
 }
 
+impl<const N: usize> Default for Snapshot<N> {
+    fn default() -> Snapshot<N> {
+        Snapshot {
+            tiles: [[0; N]; N],
+            score: 0,
+        }
+    }
+}
+
+/// The boards before the most recent moves, overwriting the oldest once full.
+///
+/// Only the most recent board is serialized, so saves stay small but the last
+/// move can still be undone after a power cycle.
+///
+/// Undoing a move keeps the board it was undone from in the place of the board
+/// it went back to, so it can be redone exactly, with every tile added after it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct History<const N: usize = SIZE> {
+    snapshots: [Snapshot<N>; UNDO_DEPTH],
+    /// Index of the oldest snapshot.
+    start: usize,
+    len: usize,
+    /// Number of boards after the last `len` that were undone from.
+    redoable: usize,
+}
+
+impl<const N: usize> History<N> {
+    /// Get the number of moves that can be undone.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn push(&mut self, snapshot: Snapshot<N>) {
+        // A new move takes the place of any that were undone
+        self.redoable = 0;
+        if self.len == UNDO_DEPTH {
+            self.start = (self.start + 1) % UNDO_DEPTH;
+            self.len -= 1;
+        }
+        self.snapshots[(self.start + self.len) % UNDO_DEPTH] = snapshot;
+        self.len += 1;
+    }
+
+    /// Go back a move from the `current` board, returning the board before it.
+    fn undo(&mut self, current: Snapshot<N>) -> Option<Snapshot<N>> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let before = mem::replace(
+            &mut self.snapshots[(self.start + self.len) % UNDO_DEPTH],
+            current,
+        );
+        self.redoable += 1;
+        Some(before)
+    }
+
+    /// Take back the latest undo from the `current` board, returning the
+    /// board it was undone from.
+    fn redo(&mut self, current: Snapshot<N>) -> Option<Snapshot<N>> {
+        if self.redoable == 0 {
+            return None;
+        }
+        let after = mem::replace(
+            &mut self.snapshots[(self.start + self.len) % UNDO_DEPTH],
+            current,
+        );
+        self.len += 1;
+        self.redoable -= 1;
+        Some(after)
+    }
+
+    /// Take points off the score of every board, e.g. so a penalty for
+    /// undoing isn't given back by undoing again.
+    fn deduct(&mut self, points: u32) {
+        for snapshot in self.snapshots.iter_mut() {
+            snapshot.score = snapshot.score.saturating_sub(points);
+        }
+    }
+
+    /// Move the tiles of every board, e.g. to follow the board being turned.
+    fn remap(&mut self, to: impl Fn(GridCoord<N>) -> GridCoord<N>) {
+        for snapshot in self.snapshots.iter_mut() {
+            snapshot.tiles = remap_tiles(&snapshot.tiles, &to);
+        }
+    }
+
+    fn latest(&self) -> Option<&Snapshot<N>> {
+        match self.len {
+            0 => None,
+            len => Some(&self.snapshots[(self.start + len - 1) % UNDO_DEPTH]),
+        }
+    }
+}
+
+impl<const N: usize> Default for History<N> {
+    fn default() -> History<N> {
+        History {
+            snapshots: [Snapshot::default(); UNDO_DEPTH],
+            start: 0,
+            len: 0,
+            redoable: 0,
+        }
+    }
+}
+
+impl<const N: usize> Serialize for History<N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.latest().serialize(serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for History<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let mut history = History::default();
+        if let Some(snapshot) = Option::<Snapshot<N>>::deserialize(deserializer)? {
+            history.push(snapshot);
+        }
+        Ok(history)
+    }
+}
+
+/// A move as it was played, along with the tile added after it.
+///
+/// Packed into three bytes, so a long log stays small.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoggedMove<const N: usize = SIZE> {
+    direction: Direction,
+    /// Board index of the added tile.
+    spawn_index: u8,
+    /// Value of the added tile, or 0 if none was added.
+    spawn_value: u8,
+}
+
+impl<const N: usize> LoggedMove<N> {
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Get where a tile was added after the move, and its value.
+    /// Returns `None` if no tile was added.
+    pub fn spawned(&self) -> Option<(GridCoord<N>, Tile)> {
+        match self.spawn_value {
+            0 => None,
+            value => GridCoord::from_index(usize::from(self.spawn_index))
+                .ok()
+                .map(|coord| (coord, Tile::from(value))),
+        }
+    }
+
+    /// Get the events that played the move, for applying to another board.
+    pub fn events(&self) -> impl Iterator<Item = Event<N>> {
+        let spawned = self.spawned();
+        iter::once(Event::MoveApplied(self.direction))
+            .chain(spawned.map(|(coord, value)| Event::TileSpawned(coord, value)))
+    }
+
+    /// Get the move written as the direction then the added tile, valued as
+    /// played by `rule`, e.g. `Left, 4 at 1,3`.
+    pub fn display<'a, M: MergeRule>(&'a self, rule: &'a M) -> impl Display + 'a {
+        DisplayLoggedMove { logged: self, rule }
+    }
+}
+
+struct DisplayLoggedMove<'a, const N: usize, M> {
+    logged: &'a LoggedMove<N>,
+    rule: &'a M,
+}
+
+impl<const N: usize, M: MergeRule> Display for DisplayLoggedMove<'_, N, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.logged.direction)?;
+        match self.logged.spawned() {
+            Some((coord, tile)) => {
+                let value = tile.value_in(self.rule);
+                write!(f, ", {} at {},{}", value, coord.x(), coord.y())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// The most recent moves of a game, oldest first, dropping the oldest once full.
+///
+/// Kept for replaying or exporting a game when a board looks impossible. It is
+/// not saved, so it only covers moves since the device was powered on.
+///
+/// Undone moves stay at the back so they can be redone, until the next move
+/// replaces them. They aren't counted as part of the log.
+#[derive(Clone, Debug)]
+pub struct MoveLog<const N: usize = SIZE> {
+    moves: [LoggedMove<N>; LOG_LENGTH],
+    /// Index of the oldest move. Kept as bytes, like the rest, to save RAM.
+    start: u8,
+    /// Number of moves held, including those undone.
+    held: u8,
+    /// Number of moves at the back that were undone.
+    undone: u8,
+}
+
+impl<const N: usize> MoveLog<N> {
+    pub fn len(&self) -> usize {
+        usize::from(self.held - self.undone)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the logged moves, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &LoggedMove<N>> + '_ {
+        (0..self.len()).map(move |index| &self.moves[self.position(index)])
+    }
+
+    /// Get how many undone moves can be redone.
+    pub fn redoable(&self) -> usize {
+        usize::from(self.undone)
+    }
+
+    /// Get where the move `index` places from the oldest is kept.
+    fn position(&self, index: usize) -> usize {
+        (usize::from(self.start) + index) % LOG_LENGTH
+    }
+
+    fn record_move(&mut self, direction: Direction) {
+        // A new move takes the place of any that were undone
+        self.held -= self.undone;
+        self.undone = 0;
+        if usize::from(self.held) == LOG_LENGTH {
+            self.start = self.position(1) as u8;
+            self.held -= 1;
+        }
+        self.moves[self.position(self.held.into())] = LoggedMove {
+            direction,
+            spawn_index: 0,
+            spawn_value: 0,
+        };
+        self.held += 1;
+    }
+
+    /// Note the tile added after the latest move.
+    /// Tiles added before the first move, or a second time after a move, aren't logged.
+    fn record_spawn(&mut self, coord: GridCoord<N>, value: u8) {
+        if self.is_empty() {
+            return;
+        }
+        let latest = &mut self.moves[self.position(self.len() - 1)];
+        if latest.spawn_value == 0 {
+            latest.spawn_index = coord.board_index() as u8;
+            latest.spawn_value = value;
+        }
+    }
+
+    fn undo(&mut self) {
+        if !self.is_empty() {
+            self.undone += 1;
+        }
+    }
+
+    /// Take back the latest undo.
+    fn redo(&mut self) {
+        if self.undone != 0 {
+            self.undone -= 1;
+        }
+    }
+
+    fn clear(&mut self) {
+        self.start = 0;
+        self.held = 0;
+        self.undone = 0;
+    }
+}
+
+impl<const N: usize> Default for MoveLog<N> {
+    fn default() -> MoveLog<N> {
+        MoveLog {
+            moves: [LoggedMove {
+                direction: Direction::Up,
+                spawn_index: 0,
+                spawn_value: 0,
+            }; LOG_LENGTH],
+            start: 0,
+            held: 0,
+            undone: 0,
+        }
+    }
+}
+
+/// A change to a game. Every change made in play goes through
+/// `GameBoard::apply`, so the same events applied to the same board always
+/// give the same game, whether played, replayed or received from another board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event<const N: usize = SIZE> {
+    /// Every tile slid as far as it could in a direction, merging on the way.
+    MoveApplied(Direction),
+    /// A tile was added to an empty space.
+    TileSpawned(GridCoord<N>, Tile),
+    /// The board was emptied for a new game.
+    GameReset,
+}
+
+/// The rules the game is being played with, chosen when a new game starts.
+/// Each merges tiles by its own rule, see `MergeRule`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Variant {
+    #[default]
+    Classic,
+    /// 1s and 2s combine into 3s, and only equal tiles of 3 or more merge.
+    Threes,
+}
+
+/// What happens when two of the largest tile, `MAX_TILE`, meet in an endless game.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EndlessPolicy {
+    /// They don't merge, as if they were different tiles.
+    #[default]
+    Refuse,
+    /// They merge into a single largest tile, scoring as one.
+    Saturate,
+}
+
+/// How undoing moves is limited, so games can be played casually or by purists.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UndoPolicy {
+    /// Moves can be undone freely, taking back only what they scored.
+    #[default]
+    Free,
+    /// Undoing a move also takes this percentage of what it scored off the
+    /// score, up to 100.
+    Penalty(u8),
+    /// Only this many moves can be undone each game.
+    Limited(u8),
+}
+
+impl UndoPolicy {
+    /// Parse a policy from its name and any amount, e.g. `"penalty 50"`.
+    pub fn parse(name: &str) -> Option<UndoPolicy> {
+        match name.split_once(' ') {
+            None if name == "free" => Some(UndoPolicy::Free),
+            Some(("penalty", percent)) => percent.parse().ok().map(UndoPolicy::Penalty),
+            Some(("limit", limit)) => limit.parse().ok().map(UndoPolicy::Limited),
+            _ => None,
+        }
+    }
+}
+
+/// Points scored for each extra tile added after a move, with `SpawnRate::Escalating`.
+const ESCALATION_POINTS: u32 = 10_000;
+/// Most tiles added after a move, with `SpawnRate::Escalating`.
+const MAX_SPAWNS: u8 = 3;
+
+/// How many tiles are added after each move, so experienced players can make
+/// the game harder.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpawnRate {
+    /// One tile, as in the classic game.
+    #[default]
+    Single,
+    /// Two tiles after every move.
+    Double,
+    /// One tile, and one more for every 10,000 points scored, up to three.
+    Escalating,
+}
+
+impl SpawnRate {
+    /// Parse a rate from its console name.
+    pub fn parse(name: &str) -> Option<SpawnRate> {
+        match name {
+            "single" => Some(SpawnRate::Single),
+            "double" => Some(SpawnRate::Double),
+            "escalating" => Some(SpawnRate::Escalating),
+            _ => None,
+        }
+    }
+
+    /// Get how many tiles are added after a move in a game with some score.
+    pub fn spawns(&self, score: u32) -> u8 {
+        match self {
+            SpawnRate::Single => 1,
+            SpawnRate::Double => 2,
+            SpawnRate::Escalating => (1 + score / ESCALATION_POINTS).min(MAX_SPAWNS.into()) as u8,
+        }
+    }
+}
+
+/// How merges add to the score, so games can be played for different goals
+/// while tiles move the same way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScorePolicy {
+    /// Each merge scores the value of the tile it makes, as in the classic game.
+    #[default]
+    Classic,
+    /// Each merge scores one point, however large the tile.
+    Merges,
+    /// The score is the value of the largest tile made, for a race to the highest tile.
+    HighestTile,
+}
+
+impl ScorePolicy {
+    /// Parse a policy from its console name.
+    pub fn parse(name: &str) -> Option<ScorePolicy> {
+        match name {
+            "classic" => Some(ScorePolicy::Classic),
+            "merges" => Some(ScorePolicy::Merges),
+            "highest" => Some(ScorePolicy::HighestTile),
+            _ => None,
+        }
+    }
+
+    /// Get the score after a merge that made a tile of some value.
+    pub fn score_merge(&self, score: u32, value: u32) -> u32 {
+        match self {
+            ScorePolicy::Classic => score.saturating_add(value),
+            ScorePolicy::Merges => score.saturating_add(1),
+            ScorePolicy::HighestTile => score.max(value),
+        }
+    }
+}
+
+/// A game of 2048 on an N by N board, 4x4 unless otherwise specified.
+///
+/// New tiles are placed using `R`, a `WyRng` unless another generator is
+/// given, e.g. a fixed one for tests. Tiles merge following `M`, a `Variant`
+/// chosen when the game starts unless a fixed rule is given.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "", deserialize = "R: Default, M: Default"))]
+pub struct GameBoard<const N: usize = SIZE, R = WyRng, M = Variant> {
+    #[serde(with = "tiles")]
+    tiles: Tiles<N>,
+    rng: MyRng<R>,
+    score: u32,
+    /// Saved separately, so the board's own format is unchanged.
+    #[serde(skip)]
+    history: History<N>,
+    /// Number of moves made this game, also saved separately.
+    #[serde(skip)]
+    moves: u32,
+    /// Largest tile made by a merge this game, or 0 if none, also saved separately.
+    #[serde(skip)]
+    best_merge: u8,
+    /// Kept across games, and saved separately from any game.
+    #[serde(skip)]
+    stats: GameStats,
+    /// New tiles are a 4 one time in this many, or never if zero. Also saved separately.
+    #[serde(skip)]
+    four_odds: u8,
+    /// What happens once tiles can't get any larger, also saved separately.
+    #[serde(skip)]
+    endless: EndlessPolicy,
+    /// How undoing is limited, also saved separately.
+    #[serde(skip)]
+    undo_policy: UndoPolicy,
+    /// Number of moves undone this game, also saved separately.
+    #[serde(skip)]
+    undos: u8,
+    /// How many tiles are added after a move, also saved separately.
+    #[serde(skip)]
+    spawn_rate: SpawnRate,
+    /// How merges are scored, also saved separately.
+    #[serde(skip)]
+    scoring: ScorePolicy,
+    /// Ticks each game of a time attack lasts, or `None` for an untimed game.
+    /// Not saved, as a countdown can't carry on across a power cycle.
+    #[serde(skip)]
+    time_limit: Option<NonZeroU32>,
+    /// Ticks counted so far this game, not saved either.
+    #[serde(skip)]
+    ticks: u32,
+    /// Not saved at all, see `MoveLog`.
+    #[serde(skip)]
+    log: MoveLog<N>,
+    /// Board index and value of the tile added most recently, since the
+    /// last move, with a value of 0 if none. Kept as two bytes to save RAM,
+    /// and not saved, as it is only for showing the tile appear.
+    #[serde(skip)]
+    last_spawn: (u8, u8),
+    /// Moves in a row that merged, for `Achievements::MERGE_CHAIN`. Not saved.
+    #[serde(skip)]
+    merge_streak: u8,
+    /// Saved with the UI state, as its variant.
+    #[serde(skip)]
+    rule: M,
+}
+
 impl GameBoard {
     /// Create an empty board.
     pub fn empty() -> GameBoard {
@@ -61,17 +744,243 @@ impl GameBoard {
         GameBoard::with_tiles([value; SIZE * SIZE])
     }
 
-    /// Create a board containing the specified tiles
+    /// Create a board containing the specified tiles, given as bare
+    /// exponents as they are stored or sent.
     pub fn with_tiles(tiles: [u8; SIZE * SIZE]) -> GameBoard {
+        let mut rows = [[0; SIZE]; SIZE];
+        for (index, &tile) in tiles.iter().enumerate() {
+            rows[index / SIZE][index % SIZE] = tile;
+        }
+        GameBoard::from_rows(rows)
+    }
+
+    /// Rebuild a game from its tiles, score and move count, e.g. when restoring
+    /// or receiving one. Fails with `InvalidTile` if a tile is out of range,
+    /// or `SaveImplausible` if the tiles can't account for the score.
+    pub fn from_state(
+        tiles: [u8; SIZE * SIZE],
+        score: u32,
+        move_count: u32,
+    ) -> Result<GameBoard, Error> {
+        let mut board = GameBoardBuilder::new()
+            .tiles(tiles.map(Tile::from))
+            .score(score)
+            .build()?;
+        board.set_move_count(move_count);
+        board.check_plausible(false)?;
+        Ok(board)
+    }
+
+    /// Encode just the tiles and score, packed into a single EEPROM page.
+    /// Fails with `InvalidTile` if a tile is larger than 32768 or a blocker.
+    pub fn to_compact_bytes(&self) -> Result<[u8; compact::PACKED_SIZE], Error> {
+        compact::pack(&self.tile_array(), self.score)
+    }
+
+    /// Decode a board encoded by `to_compact_bytes`, rejecting any that are
+    /// corrupt or couldn't have come from a real game.
+    pub fn from_compact_bytes(bytes: &[u8; compact::PACKED_SIZE]) -> Result<GameBoard, Error> {
+        let (tiles, score) = compact::unpack(bytes)?;
+        GameBoard::from_state(tiles, score, 0)
+    }
+
+    pub fn new_game() -> GameBoard {
+        let mut board = GameBoard::empty();
+        board.set_random();
+        board.set_random();
+        board
+    }
+
+    /// Start a new game with the random number generator seeded, so games differ between boots.
+    /// The same seed always gives the same tiles, for replays and daily challenges.
+    pub fn new_game_with_seed(seed: u64) -> GameBoard {
+        GameBoard::seeded(seed)
+    }
+
+    /// Start a new game of a variant, as `new_game_with_seed`.
+    pub fn new_variant_game(variant: Variant, seed: u64) -> GameBoard {
+        let mut board = GameBoard::new_game_with_seed(seed);
+        board.set_rule(variant);
+        board
+    }
+
+    /// Play a game again from its seed and moves, as `new_game_with_seed` and
+    /// then `make_move` and `set_random` for each move would have.
+    /// Yields the starting board, then the board after each move.
+    pub fn replay<I>(seed: u64, moves: I) -> Replay<I::IntoIter>
+    where
+        I: IntoIterator<Item = Direction>,
+    {
+        Replay {
+            board: GameBoard::new_game_with_seed(seed),
+            moves: moves.into_iter(),
+            started: false,
+        }
+    }
+}
+
+/// A game being played again, see `GameBoard::replay`.
+pub struct Replay<I> {
+    board: GameBoard,
+    moves: I,
+    /// True once the starting board has been yielded.
+    started: bool,
+}
+
+impl<I: Iterator<Item = Direction>> Iterator for Replay<I> {
+    type Item = GameBoard;
+
+    fn next(&mut self) -> Option<GameBoard> {
+        if self.started {
+            let direction = self.moves.next()?;
+            // Moves that change nothing don't add a tile, just as in play
+            if self.board.make_move(direction).moved() {
+                self.board.set_random();
+            }
+        }
+        self.started = true;
+        Some(self.board.clone())
+    }
+}
+
+impl<R: RngCore + Clone, M: MergeRule> GameBoard<SIZE, R, M> {
+    /// Get the board tiles.
+    #[deprecated(note = "use `tiles` instead")]
+    pub fn get_board(&self) -> [u8; SIZE * SIZE] {
+        self.tile_array()
+    }
+
+    /// Get the board tiles in board index order, for encoding.
+    pub(crate) fn tile_array(&self) -> [u8; SIZE * SIZE] {
+        let mut tiles = [0; SIZE * SIZE];
+        for (tile, &value) in tiles.iter_mut().zip(self.tiles.iter().flatten()) {
+            *tile = value;
+        }
+        tiles
+    }
+
+    /// Moves all tiles as far as possible in the specified direction.
+    /// Returns where every tile went and which merged, for animating the move.
+    pub fn make_move(&mut self, direction: Direction) -> MoveOutcome {
+        let mut slides = Slides::new();
+        // There can't be more tiles than spaces on the board
+        self.slide_with(direction, |slide| slides.push(slide).unwrap());
+        MoveOutcome { slides }
+    }
+}
+
+impl<const N: usize, R: RngCore + Clone + Default, M: MergeRule> GameBoard<N, R, M> {
+    /// Create a board of any size from its rows, starting from the bottom.
+    pub fn from_rows(tiles: [[u8; N]; N]) -> GameBoard<N, R, M> {
+        GameBoard::from_rows_with_rng(tiles, R::default())
+    }
+}
+
+impl<const N: usize, R: RngCore + Clone + Default> GameBoard<N, R> {
+    /// Decode a board, rejecting any that couldn't have come from a real
+    /// game, as corrupt data can still decode.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let board = from_bytes::<GameBoard<N, R>>(bytes).map_err(|_| Error::SaveCorrupt)?;
+        board.check_plausible(false)?;
+        Ok(board)
+    }
+
+    /// Check the board could have come from a real game: every tile is in
+    /// range, and the score is one the tiles could account for. Boards that
+    /// started with unscored tiles, such as puzzles, may score less.
+    /// Only the classic game's scores are checked, as only its scores are known.
+    pub fn check_plausible(&self, unscored_tiles: bool) -> Result<(), Error> {
+        let tiles = self
+            .tiles
+            .iter()
+            .flatten()
+            .filter(|&&tile| tile > 0 && tile != BLOCKER);
+        if tiles.clone().any(|&tile| tile > MAX_TILE) {
+            return Err(Error::SaveImplausible);
+        }
+        // Only the classic rule scored in the classic way is checked
+        if self.rule != Variant::Classic || self.scoring != ScorePolicy::Classic {
+            return Ok(());
+        }
+
+        // A 2^k tile scores between (k - 2) * 2^k, if built from 4s, and
+        // (k - 1) * 2^k, if built from 2s
+        let (least, most) = tiles.fold((0, 0), |(least, most), &tile| {
+            (
+                least + (u64::from(tile.saturating_sub(2)) << tile),
+                most + (u64::from(tile - 1) << tile),
+            )
+        });
+        let score = u64::from(self.score);
+        // Merges at the largest tile score without making the tiles any larger
+        let saturating = self.endless == EndlessPolicy::Saturate;
+        // Penalties for undoing take points the tiles still account for
+        let penalised = matches!(self.undo_policy, UndoPolicy::Penalty(_)) && self.undos > 0;
+        if (score > most && !saturating) || (score < least && !unscored_tiles && !penalised) {
+            return Err(Error::SaveImplausible);
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize, R: RngCore + Clone + SeedableRng, M: MergeRule> GameBoard<N, R, M> {
+    /// Start a new game of any size, with the random number generator seeded.
+    pub fn seeded(seed: u64) -> GameBoard<N, R, M> {
+        let mut board = GameBoard::from_rows_with_rng([[0; N]; N], R::seed_from_u64(seed));
+        board.reseed(seed);
+        board.set_random();
+        board.set_random();
+        board
+    }
+
+    /// Reseed the random number generator, e.g. after loading a saved game
+    /// without its `RngState`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = MyRng::restore(RngState { seed, draws: 0 });
+    }
+
+    /// Put the random number generator back in a state saved by `rng_state`,
+    /// so the game goes on adding the same tiles as before it was saved.
+    /// Fails with `SaveImplausible` if more than `MAX_DRAWS` numbers were
+    /// drawn, as `rng_state` never gives, leaving the generator unchanged.
+    pub fn restore_rng(&mut self, state: RngState) -> Result<(), Error> {
+        if state.draws > MAX_DRAWS {
+            return Err(Error::SaveImplausible);
+        }
+        self.rng = MyRng::restore(state);
+        Ok(())
+    }
+}
+
+impl<const N: usize, R: RngCore + Clone, M: MergeRule> GameBoard<N, R, M> {
+    /// Create a board from its rows, placing new tiles with the given random number generator.
+    pub fn from_rows_with_rng(tiles: [[u8; N]; N], rng: R) -> GameBoard<N, R, M> {
         GameBoard {
             tiles,
-            rng: MyRng(WyRng::default()),
+            rng: MyRng::new(rng),
             score: 0,
+            history: History::default(),
+            moves: 0,
+            best_merge: 0,
+            stats: GameStats::default(),
+            four_odds: DEFAULT_FOUR_ODDS,
+            endless: EndlessPolicy::default(),
+            undo_policy: UndoPolicy::default(),
+            undos: 0,
+            spawn_rate: SpawnRate::default(),
+            scoring: ScorePolicy::default(),
+            time_limit: None,
+            ticks: 0,
+            log: MoveLog::default(),
+            last_spawn: (0, 0),
+            merge_streak: 0,
+            rule: M::default(),
         }
     }
 
-    pub fn new_game() -> GameBoard {
-        let mut board = GameBoard::empty();
+    /// Start a new game, placing tiles with the given random number generator.
+    pub fn new_game_with_rng(rng: R) -> GameBoard<N, R, M> {
+        let mut board = GameBoard::from_rows_with_rng([[0; N]; N], rng);
         board.set_random();
         board.set_random();
         board
@@ -79,68 +988,189 @@ impl GameBoard {
 
     /// Clears all tiles from the board.
     pub fn clear(&mut self) {
-        self.tiles = [0; SIZE * SIZE];
-        self.score = 0;
+        self.apply(Event::GameReset);
+    }
+
+    /// Turn the board a quarter turn clockwise, along with the boards it can
+    /// be undone to. The score and move log are unchanged, so moves in the log
+    /// no longer match the board.
+    pub fn rotate_cw(&mut self) {
+        self.remap(|coord| coord.rotate_cw());
     }
 
-    /// Get the maximum value of any tile on the board.
-    pub fn max_tile(&self) -> u8 {
-        *self
+    /// Turn the board a quarter turn anticlockwise, see `rotate_cw`.
+    pub fn rotate_ccw(&mut self) {
+        self.remap(|coord| coord.rotate_ccw());
+    }
+
+    /// Mirror the board left to right, see `rotate_cw`.
+    pub fn mirror(&mut self) {
+        self.remap(|coord| coord.mirror());
+    }
+
+    fn remap(&mut self, to: impl Fn(GridCoord<N>) -> GridCoord<N>) {
+        self.tiles = remap_tiles(&self.tiles, &to);
+        self.history.remap(to);
+    }
+
+    /// Get the tiles as the smallest of the board's eight rotations and
+    /// mirror images, compared row by row from row 0. Boards that are the
+    /// same but for turning or flipping have the same canonical form, so it
+    /// can key positions that play alike, e.g. in a search's table of
+    /// positions already seen.
+    pub fn canonical_form(&self) -> [[u8; N]; N] {
+        let mut canonical = self.tiles;
+        let mut turned = self.tiles;
+        for _ in 0..4 {
+            turned = remap_tiles(&turned, |coord| coord.rotate_cw());
+            let mirrored = remap_tiles(&turned, |coord| coord.mirror());
+            canonical = canonical.min(turned).min(mirrored);
+        }
+        canonical
+    }
+
+    /// Get the largest tile on the board, or `Tile::EMPTY` if there are
+    /// none. Blockers aren't tiles.
+    pub fn max_tile(&self) -> Tile {
+        let max = self
             .tiles
             .iter()
+            .flatten()
+            .copied()
+            .filter(|&tile| tile != BLOCKER)
             .max()
-            .expect("there were no tiles on the board")
+            .unwrap_or(0);
+        Tile::from(max)
     }
 
     /// Returns true only if all tiles are filled (non-zero)
     pub fn is_full(&self) -> bool {
-        self.tiles.iter().all(|&tile| tile != 0)
+        self.tiles.iter().flatten().all(|&tile| tile != 0)
+    }
+
+    /// Returns true if the board is full and no move would change it, or a
+    /// time attack has run out of time.
+    pub fn is_game_over(&self) -> bool {
+        self.is_time_up() || (self.is_full() && self.available_moves().is_empty())
+    }
+
+    /// Get the directions a move would change the board in, in the order of
+    /// `Direction::ALL`. A single direction left means the game is nearly over.
+    pub fn available_moves(&self) -> Vec<Direction, 4> {
+        Direction::ALL
+            .iter()
+            .copied()
+            .filter(|&direction| self.can_move(direction))
+            .collect()
+    }
+
+    /// Returns true if a move would change the board, without making it or
+    /// copying the board. A move changes the board if any tile has an empty
+    /// space or a tile it merges with next to it in that direction.
+    pub fn can_move(&self, direction: Direction) -> bool {
+        if self.is_time_up() {
+            return false;
+        }
+        GridCoord::all().any(|coord| {
+            let tile = self.get_tile(coord);
+            match coord.neighbour(direction) {
+                Some(next) if tile != 0 && tile != BLOCKER => {
+                    let target = self.get_tile(next);
+                    target == 0 || self.merge(tile, target).is_some()
+                }
+                _ => false,
+            }
+        })
+    }
+
+    /// Iterate over every tile and where it is, in board index order.
+    /// Empty spaces are included, as `Tile::EMPTY`.
+    pub fn tiles(&self) -> impl Iterator<Item = (GridCoord<N>, Tile)> + '_ {
+        GridCoord::all().map(move |coord| (coord, self.tile(coord)))
+    }
+
+    /// Get a tile on the board.
+    pub fn tile(&self, coord: GridCoord<N>) -> Tile {
+        Tile::from(self.get_tile(coord))
     }
 
     /// Get the value of a tile on the board.
-    fn get_tile(&self, coord: Coord) -> u8 {
-        self.tiles[coord.board_index()]
+    fn get_tile(&self, coord: GridCoord<N>) -> u8 {
+        self.tiles[coord.y()][coord.x()]
     }
 
     /// Set a tile on the board to some value.
-    fn set_tile(&mut self, coord: Coord, value: u8) {
-        self.tiles[coord.board_index()] = value;
+    fn set_tile(&mut self, coord: GridCoord<N>, value: u8) {
+        self.tiles[coord.y()][coord.x()] = value;
     }
 
     /// Set a tile on the board to empty.
-    fn clear_tile(&mut self, coord: Coord) {
+    fn clear_tile(&mut self, coord: GridCoord<N>) {
         self.set_tile(coord, 0)
     }
 
+    /// Put a tile in an empty space, e.g. to set up a puzzle. Unlike a new
+    /// tile, it isn't recorded in the log or statistics.
+    /// Fails with `InvalidTile` if the tile is empty, a blocker or above
+    /// `MAX_TILE`, or `SpaceOccupied` if the space already has a tile.
+    pub fn place_tile(&mut self, coord: GridCoord<N>, tile: Tile) -> Result<(), Error> {
+        if tile.is_empty() || tile.exponent() > MAX_TILE {
+            return Err(Error::InvalidTile);
+        }
+        if self.get_tile(coord) != 0 {
+            return Err(Error::SpaceOccupied);
+        }
+        self.set_tile(coord, tile.exponent());
+        Ok(())
+    }
+
+    /// Put a blocker in an empty space, e.g. to set up a puzzle or a game
+    /// with obstacles. Fails with `SpaceOccupied` if the space isn't empty.
+    pub fn place_blocker(&mut self, coord: GridCoord<N>) -> Result<(), Error> {
+        if self.get_tile(coord) != 0 {
+            return Err(Error::SpaceOccupied);
+        }
+        self.set_tile(coord, BLOCKER);
+        Ok(())
+    }
+
+    /// Iterate over where the blockers are, in board index order.
+    pub fn blockers(&self) -> impl Iterator<Item = GridCoord<N>> + '_ {
+        GridCoord::all().filter(move |&coord| self.get_tile(coord) == BLOCKER)
+    }
+
+    /// Put any tile anywhere, replacing what was there, to reach states that
+    /// are slow to play to.
+    #[cfg(feature = "god-mode")]
+    pub fn replace_tile(&mut self, coord: GridCoord<N>, tile: Tile) {
+        self.set_tile(coord, tile.exponent());
+    }
+
     /// Get the game's score.
     pub fn get_score(&self) -> u32 {
         self.score
     }
 
+    /// Set the score outright, as when setting up a board, see `GameBoardBuilder`.
+    pub(crate) fn set_score(&mut self, score: u32) {
+        self.score = score;
+    }
+
     /// Get the locations of all empty tiles.
-    fn vacant_tiles(&self) -> impl Iterator<Item = Coord> + '_ {
-        self.tiles
-            .iter()
-            .enumerate()
-            .filter(|&(_index, &value)| value == 0)
-            .map(|(index, _value)| {
-                Coord::from_index(index).expect("index was invalid for creating Coord")
-            })
+    fn vacant_tiles(&self) -> impl Iterator<Item = GridCoord<N>> + '_ {
+        GridCoord::all().filter(move |&coord| self.get_tile(coord) == 0)
     }
 
     /// Get the location of a random empty tile.
     /// Returns `None` if no empty tiles are present.
-    fn random_vacant_tile(&mut self) -> Option<Coord> {
-        let mut vacant_tiles = Vec::<Coord, 16>::new();
-        let num_vacant = self.vacant_tiles().fold(0, |count, coord| {
-            vacant_tiles
-                .push(coord)
-                .expect("more than 16 tiles were vacant");
-            count + 1
-        });
+    ///
+    /// The empty tiles are counted and then one is picked by its index, so
+    /// nothing is collected along the way and any random number is in range.
+    fn random_vacant_tile(&mut self) -> Option<GridCoord<N>> {
+        let num_vacant = self.vacant_tiles().count();
         if num_vacant > 0 {
-            let index = (self.rng.0.next_u32() as usize) % num_vacant;
-            Some(vacant_tiles[index])
+            let index = (self.rng.next_u32() as usize) % num_vacant;
+            self.vacant_tiles().nth(index)
         } else {
             None
         }
@@ -150,50 +1180,82 @@ impl GameBoard {
     /// If no empty tile is found, then no changes are made and `false` is returned.
     pub fn set_random(&mut self) -> bool {
         if let Some(tile) = self.random_vacant_tile() {
-            let value = if self.rng.0.next_u32() % 10 == 0 {
-                2
+            let four_odds = u32::from(self.four_odds);
+            let value = if four_odds != 0 && self.rng.next_u32() % four_odds == 0 {
+                Tile::from_exponent(2)
             } else {
-                1
+                Tile::from_exponent(1)
             };
-            self.set_tile(tile, value);
-            true
+            self.apply(Event::TileSpawned(tile, value))
         } else {
             false
         }
     }
 
-    /// Get the board tiles.
-    /// FIXME: This is temporary, make some nice pretty print instead
-    pub fn get_board(&self) -> [u8; SIZE * SIZE] {
-        self.tiles
+    /// Set a random empty tile to a given tile, rather than a 2 or a 4.
+    /// If no empty tile is found, then no changes are made and `false` is returned.
+    pub fn place_random_tile(&mut self, value: Tile) -> bool {
+        match self.random_vacant_tile() {
+            Some(tile) => self.apply(Event::TileSpawned(tile, value)),
+            None => false,
+        }
+    }
+
+    /// Add the tiles due after a move, as set by the spawn rate, stopping
+    /// early if the board fills. Returns how many were added.
+    /// Only the first is kept in the move log.
+    pub fn spawn_tiles(&mut self) -> u8 {
+        let due = self.spawn_rate.spawns(self.score);
+        (0..due).take_while(|_| self.set_random()).count() as u8
     }
 
     /// Return two arrays specifying the order to attempt to move tiles.
-    fn get_traversal_order(&self, direction: Direction) -> ([usize; SIZE], [usize; SIZE]) {
+    fn get_traversal_order(&self, direction: Direction) -> ([usize; N], [usize; N]) {
+        let forwards: [usize; N] = array::from_fn(|index| index);
+        let backwards: [usize; N] = array::from_fn(|index| N - index - 1);
         let x_traversal_order = match direction {
-            Direction::Right => [3, 2, 1, 0],
-            _ => [0, 1, 2, 3],
+            Direction::Right => backwards,
+            _ => forwards,
         };
         let y_traversal_order = match direction {
-            Direction::Up => [3, 2, 1, 0],
-            _ => [0, 1, 2, 3],
+            Direction::Up => backwards,
+            _ => forwards,
         };
         (x_traversal_order, y_traversal_order)
     }
 
+    /// Get the tile made when one tile slides into another, or `None` if they
+    /// don't merge, including when it would be larger than `MAX_TILE` and the
+    /// game isn't endless.
+    fn merge(&self, moving: u8, target: u8) -> Option<u8> {
+        if moving == BLOCKER || target == BLOCKER {
+            return None;
+        }
+        match self.rule.merge(moving, target)? {
+            merged if merged <= MAX_TILE => Some(merged),
+            _ if self.endless == EndlessPolicy::Saturate => Some(MAX_TILE),
+            _ => None,
+        }
+    }
+
     /// Find the farthest position in the specified direction that the tile can move to
-    fn find_tile_move(&self, tile_coord: Coord, direction: Direction) -> TileMoveResult {
+    fn find_tile_move(&self, tile_coord: GridCoord<N>, direction: Direction) -> TileMoveResult<N> {
+        if self.get_tile(tile_coord) == BLOCKER {
+            return TileMoveResult::NoMove;
+        }
         let mut prev = tile_coord;
         loop {
             match prev.neighbour(direction) {
                 None => break, // Edge of board has been reached
                 Some(next) => {
-                    if self.get_tile(next) == self.get_tile(tile_coord) {
-                        // Next tile is same as tile that we're moving, so merge
-                        return TileMoveResult::Merge(next);
-                    } else if self.get_tile(next) != 0 {
-                        // Next tile is occupied but not mergable.
-                        break;
+                    if self.get_tile(next) != 0 {
+                        let merged = self.merge(self.get_tile(tile_coord), self.get_tile(next));
+                        // Next tile is occupied, so merge with it if the rule allows
+                        return match merged {
+                            Some(merged) => TileMoveResult::Merge(next, merged),
+                            None if tile_coord == prev => TileMoveResult::NoMove,
+                            None => TileMoveResult::Free(prev),
+                        };
                     }
                     prev = next;
                 }
@@ -208,97 +1270,488 @@ impl GameBoard {
         }
     }
 
-    /// Moves all tiles as far as possible in the specified direction.
-    /// Returns true if any tiles were moved.
-    pub fn make_move(&mut self, direction: Direction) -> bool {
+    /// Moves all tiles as far as possible in the specified direction, passing
+    /// where each tile went to `on_slide`. Returns true if any tiles were moved.
+    ///
+    /// This works for any size of board, see `make_move` for 4x4 boards.
+    pub fn slide_with<F>(&mut self, direction: Direction, on_slide: F) -> bool
+    where
+        F: FnMut(TileSlide<N>),
+    {
+        self.apply_with(Event::MoveApplied(direction), on_slide)
+    }
+
+    /// Change the game by an event. Returns false if the event changed
+    /// nothing, such as a move no tile could make or a tile added to a space
+    /// that isn't empty.
+    pub fn apply(&mut self, event: Event<N>) -> bool {
+        self.apply_with(event, |_| {})
+    }
+
+    /// Change the game by an event, passing where each tile went to
+    /// `on_slide` if it was a move. See `apply`.
+    pub fn apply_with<F>(&mut self, event: Event<N>, on_slide: F) -> bool
+    where
+        F: FnMut(TileSlide<N>),
+    {
+        match event {
+            // Once time is up the game is over
+            Event::MoveApplied(_) if self.is_time_up() => false,
+            Event::MoveApplied(direction) => {
+                let moved = self.apply_move(direction, on_slide);
+                if moved {
+                    self.log.record_move(direction);
+                }
+                moved
+            }
+            Event::TileSpawned(coord, tile) => {
+                let value = tile.exponent();
+                if value == 0 || self.get_tile(coord) != 0 {
+                    return false;
+                }
+                self.set_tile(coord, value);
+                self.log.record_spawn(coord, value);
+                self.last_spawn = (coord.board_index() as u8, value);
+                self.stats.record_spawn(value);
+                // Only a new tile can fill the board, so only it can end the game
+                if self.is_game_over() {
+                    self.stats.record_game_over();
+                }
+                true
+            }
+            Event::GameReset => {
+                self.tiles = [[0; N]; N];
+                self.score = 0;
+                self.history = History::default();
+                self.moves = 0;
+                self.best_merge = 0;
+                self.undos = 0;
+                self.ticks = 0;
+                self.log.clear();
+                self.last_spawn = (0, 0);
+                self.merge_streak = 0;
+                true
+            }
+        }
+    }
+
+    fn apply_move<F>(&mut self, direction: Direction, mut on_slide: F) -> bool
+    where
+        F: FnMut(TileSlide<N>),
+    {
         let (x_traversals, y_traversals) = self.get_traversal_order(direction);
+        let snapshot = Snapshot {
+            tiles: self.tiles,
+            score: self.score,
+        };
         let mut moved = false;
+        let mut largest_merge = None;
 
         for &x in x_traversals.iter() {
             for &y in y_traversals.iter() {
-                let coord = Coord::new(x, y).unwrap();
+                let coord = GridCoord::new(x, y).unwrap();
                 let value = self.get_tile(coord);
 
-                if value == 0 {
-                    continue;
-                }
+                if value == 0 {
+                    continue;
+                }
+
+                let (to, merged) = match self.find_tile_move(coord, direction) {
+                    TileMoveResult::NoMove => (coord, None),
+                    TileMoveResult::Free(new_coord) => {
+                        self.set_tile(new_coord, value);
+                        self.clear_tile(coord);
+                        (new_coord, None)
+                    }
+                    TileMoveResult::Merge(new_coord, merged) => {
+                        self.set_tile(new_coord, merged);
+                        self.clear_tile(coord);
+                        self.score = self
+                            .scoring
+                            .score_merge(self.score, self.rule.value(merged));
+                        self.best_merge = self.best_merge.max(merged);
+                        self.stats.record_merge(merged);
+                        largest_merge = largest_merge.max(Some(merged));
+                        (new_coord, Some(merged))
+                    }
+                };
+                moved |= to != coord;
+                on_slide(TileSlide {
+                    from: coord,
+                    to,
+                    value: Tile::from(value),
+                    merged: merged.map(Tile::from),
+                });
+            }
+        }
+
+        if moved {
+            self.history.push(snapshot);
+            self.moves = self.moves.saturating_add(1);
+            self.stats.record_move();
+            self.last_spawn = (0, 0);
+            self.unlock_achievements(largest_merge);
+        }
+        moved
+    }
+
+    /// Unlock whichever achievements a move has earned, given the largest
+    /// tile it made by a merge.
+    fn unlock_achievements(&mut self, largest_merge: Option<u8>) {
+        self.merge_streak = match largest_merge {
+            Some(_) => self.merge_streak.saturating_add(1),
+            None => 0,
+        };
+        let value = largest_merge.map_or(0, |merged| self.rule.value(merged));
+        let achievements = &mut self.stats.achievements;
+        if self.merge_streak >= MERGE_CHAIN_LENGTH {
+            achievements.insert(Achievements::MERGE_CHAIN);
+        }
+        if value >= 512 {
+            achievements.insert(Achievements::FIRST_512);
+        }
+        if value >= 2048 {
+            achievements.insert(Achievements::FIRST_2048);
+            if self.undos == 0 {
+                achievements.insert(Achievements::NO_UNDO_WIN);
+            }
+        }
+    }
+
+    /// Put the board back as it was before the last move, including the
+    /// score, less any penalty of the undo policy.
+    /// Returns false if there is no move to undo, or the policy allows no more.
+    pub fn undo(&mut self) -> bool {
+        if let UndoPolicy::Limited(limit) = self.undo_policy {
+            if self.undos >= limit {
+                return false;
+            }
+        }
+        let current = Snapshot {
+            tiles: self.tiles,
+            score: self.score,
+        };
+        match self.history.undo(current) {
+            Some(snapshot) => {
+                let penalty = match self.undo_policy {
+                    UndoPolicy::Penalty(percent) => {
+                        let scored = u64::from(self.score.saturating_sub(snapshot.score));
+                        (scored * u64::from(percent.min(100)) / 100) as u32
+                    }
+                    _ => 0,
+                };
+                self.history.deduct(penalty);
+                self.tiles = snapshot.tiles;
+                self.score = snapshot.score.saturating_sub(penalty);
+                self.moves = self.moves.saturating_sub(1);
+                self.undos = self.undos.saturating_add(1);
+                self.log.undo();
+                self.last_spawn = (0, 0);
+                self.merge_streak = 0;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Put back the board the most recent undo went back from, with every
+    /// tile added after the move. Any new move since the undo means there's
+    /// nothing to redo, so at most `UNDO_DEPTH` moves can be redone.
+    /// The move was counted in the statistics when it was first made, so it
+    /// isn't counted again. Returns false if there is no move to redo.
+    pub fn redo(&mut self) -> bool {
+        if self.is_time_up() {
+            return false;
+        }
+        let current = Snapshot {
+            tiles: self.tiles,
+            score: self.score,
+        };
+        let after = match self.history.redo(current) {
+            Some(after) => after,
+            None => return false,
+        };
+        self.log.redo();
+        self.tiles = after.tiles;
+        self.score = after.score;
+        self.moves = self.moves.saturating_add(1);
+        self.last_spawn = (0, 0);
+        true
+    }
+
+    /// Get the moves that can be undone.
+    pub fn history(&self) -> &History<N> {
+        &self.history
+    }
+
+    /// Replace the moves that can be undone, e.g. after loading a saved game.
+    pub fn set_history(&mut self, history: History<N>) {
+        self.history = history;
+    }
+
+    /// Get where the most recent tile was added and its value, so it can be
+    /// shown appearing. Returns `None` once a move or undo has been made since.
+    pub fn last_spawn(&self) -> Option<(GridCoord<N>, Tile)> {
+        match self.last_spawn {
+            (_, 0) => None,
+            (index, value) => GridCoord::from_index(usize::from(index))
+                .ok()
+                .map(|coord| (coord, Tile::from(value))),
+        }
+    }
+
+    /// Get the most recent moves of this game, with the tiles added after them.
+    pub fn move_log(&self) -> &MoveLog<N> {
+        &self.log
+    }
+
+    /// Get the number of moves made this game.
+    pub fn get_move_count(&self) -> u32 {
+        self.moves
+    }
+
+    /// Set the number of moves made this game, e.g. after loading a saved game.
+    pub fn set_move_count(&mut self, moves: u32) {
+        self.moves = moves;
+    }
+
+    /// Get the number of moves undone this game.
+    pub fn get_undo_count(&self) -> u8 {
+        self.undos
+    }
+
+    /// Set the number of moves undone this game, e.g. after loading a saved game.
+    pub fn set_undo_count(&mut self, undos: u8) {
+        self.undos = undos;
+    }
+
+    /// Get the largest tile made by a single merge this game, or `Tile::EMPTY`
+    /// if nothing has merged. Undoing a move doesn't take back its merges.
+    pub fn best_merge(&self) -> Tile {
+        Tile::from(self.best_merge)
+    }
+
+    /// Set the largest tile made by a merge this game, e.g. after loading a saved game.
+    pub fn set_best_merge(&mut self, best_merge: Tile) {
+        self.best_merge = best_merge.exponent();
+    }
+
+    /// Get the statistics of every game played on this board, including this one.
+    pub fn stats(&self) -> &GameStats {
+        &self.stats
+    }
+
+    /// Replace the statistics, e.g. to carry them on to a new board or after
+    /// loading them from storage.
+    pub fn set_stats(&mut self, stats: GameStats) {
+        self.stats = stats;
+    }
+
+    /// Get how often new tiles are a 4, as one time in this many.
+    /// Zero means new tiles are always a 2.
+    pub fn four_odds(&self) -> u8 {
+        self.four_odds
+    }
+
+    /// Set how often new tiles are a 4, e.g. more often for a harder game.
+    pub fn set_four_odds(&mut self, four_odds: u8) {
+        self.four_odds = four_odds;
+    }
+
+    /// Get the rule tiles merge by.
+    pub fn rule(&self) -> &M {
+        &self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: M) {
+        self.rule = rule;
+    }
+
+    /// Get what happens when two of the largest tile meet.
+    pub fn endless(&self) -> EndlessPolicy {
+        self.endless
+    }
+
+    pub fn set_endless(&mut self, policy: EndlessPolicy) {
+        self.endless = policy;
+    }
+
+    /// Get the random number generator new tiles are placed with, e.g. to
+    /// look ahead at games that could follow with a copy of it.
+    pub fn rng(&self) -> &R {
+        &self.rng.rng
+    }
+
+    /// Get the state of the random number generator, to save apart from the
+    /// board. Returns `None` if it was given rather than seeded, or has drawn
+    /// more than `MAX_DRAWS` numbers, as then it can't be restored.
+    pub fn rng_state(&self) -> Option<RngState> {
+        self.rng.state()
+    }
+
+    /// Get how undoing moves is limited.
+    pub fn undo_policy(&self) -> UndoPolicy {
+        self.undo_policy
+    }
+
+    pub fn set_undo_policy(&mut self, policy: UndoPolicy) {
+        self.undo_policy = policy;
+    }
+
+    /// Get how many tiles are added after each move.
+    pub fn spawn_rate(&self) -> SpawnRate {
+        self.spawn_rate
+    }
+
+    pub fn set_spawn_rate(&mut self, rate: SpawnRate) {
+        self.spawn_rate = rate;
+    }
+
+    /// Get how merges are scored.
+    pub fn score_policy(&self) -> ScorePolicy {
+        self.scoring
+    }
+
+    pub fn set_score_policy(&mut self, policy: ScorePolicy) {
+        self.scoring = policy;
+    }
+
+    /// Get how many ticks each game of a time attack lasts, or `None` if
+    /// games aren't timed.
+    pub fn time_limit(&self) -> Option<u32> {
+        self.time_limit.map(NonZeroU32::get)
+    }
+
+    /// Make this game and those after it a time attack lasting some number
+    /// of ticks, counting from now, or untimed with `None` or a limit of 0.
+    pub fn set_time_limit(&mut self, limit: Option<u32>) {
+        self.time_limit = limit.and_then(NonZeroU32::new);
+        self.ticks = 0;
+    }
+
+    /// Get how many ticks are left in a time attack, or `None` if the game isn't timed.
+    pub fn ticks_left(&self) -> Option<u32> {
+        self.time_limit()
+            .map(|limit| limit.saturating_sub(self.ticks))
+    }
+
+    /// Returns true if a time attack has run out of time, ending the game.
+    pub fn is_time_up(&self) -> bool {
+        self.ticks_left() == Some(0)
+    }
+
+    /// Count down one tick of a time attack, e.g. one frame of the display.
+    /// Returns true if this tick ran out the time.
+    pub fn tick(&mut self) -> bool {
+        match self.ticks_left() {
+            Some(left) if left > 0 => {
+                self.ticks += 1;
+                if left == 1 {
+                    self.stats.record_game_over();
+                }
+                left == 1
+            }
+            _ => false,
+        }
+    }
+
+    /// Get the board as it would be after a move, without changing this board.
+    /// No new tile is added and the random number generator isn't used, so
+    /// the tiles added after peeking are the same as without.
+    /// Returns `None` if no tiles would move.
+    pub fn peek_move(&self, direction: Direction) -> Option<GameBoard<N, R, M>> {
+        let mut next = self.clone();
+        if next.slide_with(direction, |_| {}) {
+            Some(next)
+        } else {
+            None
+        }
+    }
+
+    #[deprecated(note = "use `peek_move` instead")]
+    pub fn preview(&self, direction: Direction) -> Option<GameBoard<N, R, M>> {
+        self.peek_move(direction)
+    }
+
+    /// Suggest a move, judged only by how many tiles it leaves empty and how
+    /// well the rows and columns keep their tiles in order.
+    /// Returns `None` if no move is possible.
+    pub fn best_move(&self) -> Option<Direction> {
+        Direction::ALL
+            .iter()
+            .filter_map(|&direction| {
+                self.peek_move(direction)
+                    .map(|next| (direction, next.hint_value()))
+            })
+            .max_by_key(|&(_, value)| value)
+            .map(|(direction, _)| direction)
+    }
+
+    /// Score the board for `best_move`. Higher is better.
+    fn hint_value(&self) -> i32 {
+        let empty = self
+            .tiles
+            .iter()
+            .flatten()
+            .filter(|&&tile| tile == 0)
+            .count() as i32;
+        let disorder: i32 = (0..N)
+            .map(|i| {
+                line_disorder((0..N).map(|j| self.tiles[i][j]))
+                    + line_disorder((0..N).map(|j| self.tiles[j][i]))
+            })
+            .sum();
+        empty * HINT_EMPTY_WEIGHT - disorder
+    }
+
+    pub fn to_bytes(&self) -> Result<[u8; BYTES_SIZE], Error> {
+        let mut bytes = [0; BYTES_SIZE];
+        to_slice(self, &mut bytes).map_err(|_| Error::StorageFull)?;
+        Ok(bytes)
+    }
+}
+
+/// Move every tile to where `to` says it goes, which must be somewhere different for each.
+fn remap_tiles<const N: usize>(
+    tiles: &Tiles<N>,
+    to: impl Fn(GridCoord<N>) -> GridCoord<N>,
+) -> Tiles<N> {
+    let mut remapped = [[0; N]; N];
+    for coord in GridCoord::all() {
+        let new_coord = to(coord);
+        remapped[new_coord.y()][new_coord.x()] = tiles[coord.y()][coord.x()];
+    }
+    remapped
+}
 
-                match self.find_tile_move(coord, direction) {
-                    TileMoveResult::NoMove => {}
-                    TileMoveResult::Free(new_coord) => {
-                        self.set_tile(new_coord, value);
-                        self.clear_tile(coord);
-                        moved = true;
-                    }
-                    TileMoveResult::Merge(new_coord) => {
-                        self.set_tile(new_coord, value + 1);
-                        self.clear_tile(coord);
-                        self.score += u32::pow(2, (value + 1).into());
-                        moved = true;
-                    }
-                }
+/// Measure how far a line of tiles is from only rising or only falling, as
+/// the smaller of its total rise and total fall. Lines in order keep large
+/// tiles together, ready to merge.
+fn line_disorder(line: impl Iterator<Item = u8>) -> i32 {
+    let (mut rise, mut fall) = (0, 0);
+    let mut previous = None;
+    for tile in line {
+        if let Some(previous) = previous {
+            match i32::from(tile) - i32::from(previous) {
+                step if step > 0 => rise += step,
+                step => fall -= step,
             }
         }
-
-        return moved;
+        previous = Some(tile);
     }
+    rise.min(fall)
+}
 
-    pub fn to_bytes(&self) -> [u8; BYTES_SIZE] {
-        let mut bytes = [0; BYTES_SIZE];
-        to_slice(self, &mut bytes).unwrap();
-        bytes
-    }
-
-    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        from_bytes::<GameBoard>(&bytes).ok()
-    }
-}
-
-fn colour_with_hue(hue: u8) -> RGB8 {
-    hsv2rgb(Hsv {
-        hue,
-        sat: 255,
-        val: 255,
-    })
-}
-
-/// Map blank tiles to be off
-/// Map 2 to 1024 tiles to rainbow colours
-/// Map 2048 to 8192 tiles to decreasing shades of white
-/// Map tiles greater than 8192 to the same gray as 8192
-fn get_tile_colour(value: u8) -> RGB8 {
-    match value {
-        0 => BLACK,              // Empty tile
-        1 => colour_with_hue(0), // 2
-        2 => colour_with_hue(15),
-        3 => colour_with_hue(45),
-        4 => colour_with_hue(75),
-        5 => colour_with_hue(95),
-        6 => colour_with_hue(130),
-        7 => colour_with_hue(175),
-        8 => colour_with_hue(195),
-        9 => colour_with_hue(230),
-        10 => colour_with_hue(250),
-        11 => WHITE, // 2048
-        12 => DIM_GRAY,
-        _ => RGB8 {
-            r: 0x20,
-            g: 0x20,
-            b: 0x20,
-        },
-    }
-}
-
-impl PartialEq for GameBoard {
+impl<const N: usize, R, M> PartialEq for GameBoard<N, R, M> {
     fn eq(&self, other: &Self) -> bool {
         self.tiles == other.tiles && self.score == other.score
     }
 }
 
-impl Eq for GameBoard {}
+impl<const N: usize, R, M> Eq for GameBoard<N, R, M> {}
 
-impl Debug for GameBoard {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+impl<const N: usize, R, M> Debug for GameBoard<N, R, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("GameBoard")
             .field("tiles", &self.tiles)
             .field("score", &self.score)
@@ -306,14 +1759,48 @@ impl Debug for GameBoard {
     }
 }
 
+/// Written as a grid of tile values, top row first, with `.` for empty
+/// spaces and `#` for blockers, then the score, e.g.
+///
+/// ```text
+///    .    4    .    .
+///    .    .    .    .
+///    .    .    .    .
+///    2    8   32 2048
+/// score 20
+/// ```
+impl<const N: usize, R, M: MergeRule> Display for GameBoard<N, R, M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let largest = self
+            .tiles
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|&tile| tile != BLOCKER)
+            .max()
+            .unwrap_or(0);
+        // Wide enough for the largest tile, with a space before it
+        let width = 1 + numerals::decimal_digits(self.rule.value(largest)).len();
+        for row in self.tiles.iter().rev() {
+            for &tile in row.iter() {
+                match tile {
+                    0 => write!(f, "{:>width$}", ".", width = width)?,
+                    BLOCKER => write!(f, "{:>width$}", "#", width = width)?,
+                    _ => write!(f, "{:>width$}", self.rule.value(tile), width = width)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        write!(f, "score {}", self.score)
+    }
+}
+
 impl IntoBoard for GameBoard {
-    /// Return a board where 2s are red and 4s are blue.
-    fn into_board(&self) -> Board {
+    /// Return a board where each tile is coloured according to the palette.
+    fn into_board(&self, context: &RenderContext) -> Board {
         let mut board = Board::new();
-        for index in 0..(SIZE * SIZE) {
-            let coord = Coord::from_index(index).unwrap();
-            let colour = get_tile_colour(self.tiles[index]);
-            board.set_led(coord, colour);
+        for coord in Coord::all() {
+            board.set_led(coord, context.palette.tile_colour(self.tile(coord)));
         }
         board
     }
@@ -321,7 +1808,44 @@ impl IntoBoard for GameBoard {
 
 #[cfg(test)]
 mod tests {
+    use rand::rngs::mock::StepRng;
+
     use super::*;
+    use crate::merge_rule::{Fibonacci, Threes};
+
+    #[test]
+    fn test_display() {
+        use core::fmt::Write;
+
+        let mut board = GameBoard::with_tiles([1, 3, 5, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0]);
+        board.score = 20;
+        let mut text: heapless::String<128> = heapless::String::new();
+        write!(text, "{}", board).unwrap();
+        let expected = concat!(
+            "    .    4    .    .\n",
+            "    .    .    .    .\n",
+            "    .    .    .    .\n",
+            "    2    8   32 2048\n",
+            "score 20",
+        );
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn test_tiles() {
+        let mut tiles = [0; SIZE * SIZE];
+        tiles[6] = 3;
+        let board = GameBoard::with_tiles(tiles);
+        assert_eq!(board.tiles().count(), SIZE * SIZE);
+        let (coord, tile) = board.tiles().find(|(_, tile)| !tile.is_empty()).unwrap();
+        assert_eq!(
+            (coord, tile),
+            (Coord::from_index(6).unwrap(), Tile::from(3))
+        );
+        assert!(board
+            .tiles()
+            .all(|(coord, tile)| tile == Tile::from(tiles[coord.board_index()])));
+    }
 
     #[test]
     fn test_get_board_index() {
@@ -333,7 +1857,7 @@ mod tests {
     #[test]
     fn test_empty_instantiation() {
         let board = GameBoard::empty();
-        assert!(board.tiles.iter().all(|&tile| tile == 0));
+        assert!(board.tiles().all(|(_, tile)| tile.is_empty()));
         assert_eq!(board.get_score(), 0);
     }
 
@@ -342,15 +1866,15 @@ mod tests {
         let mut board = GameBoard::full_of(1);
         board.score = 100;
         board.clear();
-        assert!(board.tiles.iter().all(|&tile| tile == 0));
+        assert!(board.tiles().all(|(_, tile)| tile.is_empty()));
         assert_eq!(board.get_score(), 0);
     }
 
     #[test]
     fn test_max_tile() {
         let mut board = GameBoard::empty();
-        board.tiles[7] = 11;
-        assert_eq!(board.max_tile(), 11)
+        board.set_tile(Coord::from_index(7).unwrap(), 11);
+        assert_eq!(board.max_tile(), Tile::from(11))
     }
 
     #[test]
@@ -361,12 +1885,32 @@ mod tests {
         assert!(!board.is_full());
     }
 
+    #[test]
+    fn test_is_game_over() {
+        assert!(!GameBoard::empty().is_game_over());
+        assert!(!GameBoard::full_of(1).is_game_over());
+
+        let mut tiles = [0; SIZE * SIZE];
+        for (index, tile) in tiles.iter_mut().enumerate() {
+            *tile = (index % 2 + 2 * ((index / SIZE) % 2)) as u8 + 1;
+        }
+        // Board looks like
+        // | 3 4 3 4 |
+        // | 1 2 1 2 |
+        // | 3 4 3 4 |
+        // | 1 2 1 2 |
+        let board = GameBoard::with_tiles(tiles);
+        assert!(board.is_game_over());
+        assert_eq!(board.tile_array(), tiles);
+    }
+
     #[test]
     fn test_get_tile() {
         let coord = Coord::new(2, 3).unwrap();
         let mut board = GameBoard::empty();
         board.set_tile(coord, 5);
-        assert_eq!(board.get_tile(coord), 5)
+        assert_eq!(board.get_tile(coord), 5);
+        assert_eq!(board.tile(coord).value_in(board.rule()), 32);
     }
 
     #[test]
@@ -374,7 +1918,61 @@ mod tests {
         let coord = Coord::new(2, 3).unwrap();
         let mut board = GameBoard::empty();
         board.set_tile(coord, 5);
-        assert_eq!(board.tiles[coord.board_index()], 5)
+        assert_eq!(board.get_tile(coord), 5)
+    }
+
+    #[test]
+    fn test_place_tile() {
+        let coord = Coord::new(2, 3).unwrap();
+        let mut board = GameBoard::empty();
+        assert_eq!(
+            board.place_tile(coord, Tile::from(0)),
+            Err(Error::InvalidTile)
+        );
+        assert_eq!(
+            board.place_tile(coord, Tile::from(MAX_TILE + 1)),
+            Err(Error::InvalidTile)
+        );
+        assert_eq!(board.place_tile(coord, Tile::from(MAX_TILE)), Ok(()));
+        assert_eq!(
+            board.place_tile(coord, Tile::from(1)),
+            Err(Error::SpaceOccupied)
+        );
+        assert_eq!(board.get_tile(coord), MAX_TILE);
+        assert_eq!(board.stats(), &GameStats::new());
+    }
+
+    #[test]
+    fn test_blockers() {
+        let blocker = Coord::new(2, 0).unwrap();
+        let mut board = GameBoard::with_tiles([1, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(board.place_blocker(blocker), Ok(()));
+        assert_eq!(board.place_blocker(blocker), Err(Error::SpaceOccupied));
+        assert_eq!(board.blockers().collect::<Vec<_, 16>>(), [blocker]);
+        assert_eq!(board.max_tile(), Tile::from(1));
+        assert_eq!(board.check_plausible(true), Ok(()));
+
+        // Tiles stop against a blocker rather than merging with it or passing it
+        assert!(!board.can_move(Direction::Down));
+        board.make_move(Direction::Right);
+        let bottom_row: Vec<u8, 4> = board.tiles().take(4).map(|(_, tile)| tile.into()).collect();
+        assert_eq!(bottom_row, [0, 2, BLOCKER, 1]);
+        assert_eq!(board.get_score(), 4);
+        board.make_move(Direction::Left);
+        board.make_move(Direction::Up);
+        assert_eq!(board.blockers().next(), Some(blocker));
+
+        use core::fmt::Write;
+        let mut text: heapless::String<64> = heapless::String::new();
+        write!(text, "{}", board).unwrap();
+        let expected = concat!(
+            " 4 . . 2\n",
+            " . . . .\n",
+            " . . . .\n",
+            " . . # .\n",
+            "score 4",
+        );
+        assert_eq!(text, expected);
     }
 
     #[test]
@@ -382,7 +1980,7 @@ mod tests {
         let coord = Coord::new(2, 3).unwrap();
         let mut board = GameBoard::full_of(1);
         board.clear_tile(coord);
-        assert_eq!(board.tiles[coord.board_index()], 0)
+        assert_eq!(board.get_tile(coord), 0)
     }
 
     #[test]
@@ -427,6 +2025,13 @@ mod tests {
         let vacant_tile = Coord::new(3, 0).unwrap();
         board.set_tile(vacant_tile, 0);
         assert_eq!(board.random_vacant_tile().unwrap(), vacant_tile);
+
+        // The largest random number picks the last empty tile, of two
+        let mut rows = [[1; SIZE]; SIZE];
+        rows[0][0] = 0;
+        rows[3][3] = 0;
+        let mut board = GameBoard::<SIZE, _>::from_rows_with_rng(rows, StepRng::new(u64::MAX, 0));
+        assert_eq!(board.random_vacant_tile(), Some(Coord::new(3, 3).unwrap()));
     }
 
     #[test]
@@ -439,7 +2044,25 @@ mod tests {
     fn test_set_random() {
         let mut board = GameBoard::empty();
         board.set_random();
-        assert!(board.max_tile() != 0)
+        assert!(board.max_tile() != Tile::EMPTY)
+    }
+
+    #[test]
+    fn test_last_spawn() {
+        let mut board = GameBoard::with_tiles([0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(board.last_spawn(), None);
+        let coord = Coord::new(2, 2).unwrap();
+        assert!(board.apply(Event::TileSpawned(coord, Tile::from(2))));
+        assert_eq!(board.last_spawn(), Some((coord, Tile::from(2))));
+
+        assert!(board.make_move(Direction::Left).moved());
+        assert_eq!(board.last_spawn(), None);
+        board.set_random();
+        let (spawned, value) = board.last_spawn().unwrap();
+        assert_eq!(board.tile(spawned), value);
+
+        assert!(board.undo());
+        assert_eq!(board.last_spawn(), None);
     }
 
     #[test]
@@ -470,7 +2093,7 @@ mod tests {
         );
         assert_eq!(
             board.find_tile_move(start_coord, Direction::Right),
-            TileMoveResult::Merge(Coord::new(3, 0).unwrap())
+            TileMoveResult::Merge(Coord::new(3, 0).unwrap(), 2)
         );
     }
 
@@ -478,7 +2101,7 @@ mod tests {
     fn test_make_move() {
         let mut board = GameBoard::empty();
         board.set_tile(Coord::new(0, 0).unwrap(), 1);
-        assert!(board.make_move(Direction::Up));
+        assert!(board.make_move(Direction::Up).moved());
 
         let mut expected_board = GameBoard::empty();
         expected_board.set_tile(Coord::new(0, 3).unwrap(), 1);
@@ -486,7 +2109,7 @@ mod tests {
         assert_eq!(board, expected_board);
 
         board.set_tile(Coord::new(2, 3).unwrap(), 1);
-        assert!(board.make_move(Direction::Right));
+        assert!(board.make_move(Direction::Right).moved());
 
         expected_board.clear();
         expected_board.set_tile(Coord::new(3, 3).unwrap(), 2);
@@ -494,56 +2117,857 @@ mod tests {
 
         assert_eq!(board, expected_board);
 
-        assert!(!board.make_move(Direction::Right));
+        assert!(!board.make_move(Direction::Right).moved());
 
         assert_eq!(board, expected_board);
     }
 
+    #[test]
+    fn test_move_outcome() {
+        let mut board = GameBoard::empty();
+        board.set_tile(Coord::new(0, 0).unwrap(), 1);
+        board.set_tile(Coord::new(2, 0).unwrap(), 1);
+        board.set_tile(Coord::new(3, 3).unwrap(), 2);
+        let outcome = board.make_move(Direction::Right);
+        let slides = outcome.slides();
+
+        assert!(outcome.moved());
+        assert_eq!(slides.len(), 3);
+        assert!(slides.contains(&TileSlide {
+            from: Coord::new(2, 0).unwrap(),
+            to: Coord::new(3, 0).unwrap(),
+            value: Tile::from(1),
+            merged: None,
+        }));
+        let merge = TileSlide {
+            from: Coord::new(0, 0).unwrap(),
+            to: Coord::new(3, 0).unwrap(),
+            value: Tile::from(1),
+            merged: Some(Tile::from(2)),
+        };
+        assert!(slides.contains(&merge));
+        assert!(slides.contains(&TileSlide {
+            from: Coord::new(3, 3).unwrap(),
+            to: Coord::new(3, 3).unwrap(),
+            value: Tile::from(2),
+            merged: None,
+        }));
+        assert!(outcome.merges().eq([merge].iter()));
+        assert_eq!(merge.result(), Tile::from(2));
+        assert_eq!(board.get_tile(Coord::new(3, 0).unwrap()), 2);
+
+        // Nothing can move any further right
+        let outcome = board.make_move(Direction::Right);
+        assert!(!outcome.moved());
+        assert_eq!(outcome.merges().count(), 0);
+    }
+
+    #[test]
+    fn test_undo() {
+        let mut board = GameBoard::with_tiles([1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let start = board.clone();
+        assert!(!board.undo());
+
+        assert!(board.make_move(Direction::Left).moved());
+        let merged = board.clone();
+        // Moves that don't change the board can't be undone
+        assert!(!board.make_move(Direction::Left).moved());
+        assert!(board.make_move(Direction::Up).moved());
+
+        assert_eq!(board.get_move_count(), 2);
+        assert!(board.undo());
+        assert_eq!(board, merged);
+        assert!(board.undo());
+        assert_eq!(board, start);
+        assert!(!board.undo());
+        assert_eq!(board.get_move_count(), 0);
+    }
+
+    #[test]
+    fn test_redo() {
+        let mut board = GameBoard::new_game_with_seed(2304);
+        assert!(!board.redo());
+        let start = board.clone();
+        let mut played = 0;
+        for &direction in Direction::ALL.iter().cycle().take(8) {
+            if board.make_move(direction).moved() {
+                board.set_random();
+                played += 1;
+            }
+        }
+        let end = board.clone();
+
+        for _ in 0..played.min(UNDO_DEPTH) {
+            assert!(board.undo());
+        }
+        assert_eq!(board.move_log().redoable(), played.min(UNDO_DEPTH));
+        while board.redo() {}
+        assert_eq!(board.tiles, end.tiles);
+        assert_eq!(board.get_score(), end.get_score());
+        assert_eq!(board.get_move_count(), end.get_move_count());
+        assert_eq!(board.move_log().len(), played);
+
+        // A new move means the undone move can't be redone
+        assert!(board.undo());
+        let direction = Direction::ALL
+            .iter()
+            .copied()
+            .find(|&direction| board.can_move(direction))
+            .unwrap();
+        assert!(board.make_move(direction).moved());
+        assert_eq!(board.move_log().redoable(), 0);
+        assert!(!board.redo());
+        assert_ne!(board, start);
+    }
+
+    #[test]
+    fn test_redo_every_spawn() {
+        let mut board = GameBoard::new_game_with_seed(2304);
+        board.set_spawn_rate(SpawnRate::Double);
+        let direction = Direction::ALL
+            .iter()
+            .copied()
+            .find(|&direction| board.can_move(direction))
+            .unwrap();
+        assert!(board.make_move(direction).moved());
+        assert_eq!(board.spawn_tiles(), 2);
+        let moved = board.clone();
+
+        // Both tiles added after the move come back, not just the first
+        assert!(board.undo());
+        assert!(board.redo());
+        assert_eq!(board, moved);
+        assert_eq!(board.get_move_count(), moved.get_move_count());
+        assert!(board.undo());
+        assert_ne!(board, moved);
+    }
+
+    #[test]
+    fn test_rng_state() {
+        let mut board = GameBoard::new_game_with_seed(2305);
+        let start = board.rng_state().unwrap();
+        assert_eq!(start.seed, 2305);
+        assert!(board.set_random());
+        assert!(board.rng_state().unwrap().draws > start.draws);
+
+        let mut restored = GameBoard::new_game_with_seed(1);
+        assert_eq!(restored.restore_rng(start), Ok(()));
+        assert_eq!(restored.rng_state(), Some(start));
+
+        // Restoring replays every draw, so too many are refused
+        let too_many = RngState {
+            seed: 1,
+            draws: MAX_DRAWS + 1,
+        };
+        assert_eq!(restored.restore_rng(too_many), Err(Error::SaveImplausible));
+        assert_eq!(restored.rng_state(), Some(start));
+        restored.rng.draws = MAX_DRAWS + 1;
+        assert_eq!(restored.rng_state(), None);
+
+        // Only a seeded generator can be restored
+        let board = GameBoard::<SIZE, _>::new_game_with_rng(StepRng::new(1, 0));
+        assert_eq!(board.rng_state(), None);
+    }
+
+    #[test]
+    fn test_available_moves() {
+        let board = GameBoard::with_tiles([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(board.available_moves(), [Direction::Up, Direction::Right]);
+
+        // Only the bottom row can merge, and only sideways
+        let board = GameBoard::with_tiles([1, 1, 3, 4, 2, 3, 4, 5, 3, 4, 5, 6, 4, 5, 6, 7]);
+        assert_eq!(board.available_moves(), [Direction::Right, Direction::Left]);
+        assert!(!board.is_game_over());
+
+        let mut board = GameBoard::empty();
+        assert!(board.available_moves().is_empty());
+        assert!(!board.is_game_over());
+        board = GameBoard::with_tiles([1, 2, 1, 2, 2, 1, 2, 1, 1, 2, 1, 2, 2, 1, 2, 1]);
+        assert!(board.available_moves().is_empty());
+        assert!(board.is_game_over());
+    }
+
+    #[test]
+    fn test_can_move() {
+        let mut tiles = [0; SIZE * SIZE];
+        tiles[0] = MAX_TILE;
+        tiles[1] = MAX_TILE;
+        let mut board = GameBoard::with_tiles(tiles);
+        // The largest tiles only merge in an endless game
+        assert!(!board.can_move(Direction::Left));
+        assert!(board.can_move(Direction::Up));
+        board.set_endless(EndlessPolicy::Saturate);
+        assert!(board.can_move(Direction::Left));
+
+        // Always the same answer as trying the move
+        let mut board = GameBoard::new_game_with_seed(9);
+        for &direction in Direction::ALL.iter().cycle().take(200) {
+            let can_move = board.can_move(direction);
+            assert_eq!(can_move, board.peek_move(direction).is_some());
+            if board.make_move(direction).moved() {
+                board.set_random();
+            }
+        }
+    }
+
+    #[test]
+    fn test_transforms() {
+        let tiles = [1, 1, 2, 0, 0, 3, 0, 0, 0, 0, 0, 4, 5, 0, 0, 0];
+        let board = GameBoard::with_tiles(tiles);
+        let mut turned = board.clone();
+        turned.rotate_cw();
+        assert_ne!(turned, board);
+        turned.rotate_ccw();
+        assert_eq!(turned, board);
+        turned.mirror();
+        let mirrored = GameBoard::with_tiles([0, 2, 1, 1, 0, 0, 3, 0, 4, 0, 0, 0, 0, 0, 0, 5]);
+        assert_eq!(turned, mirrored);
+
+        // Moving then turning is the same as turning then moving the turned way
+        for &direction in Direction::ALL.iter() {
+            let mut moved_first = board.clone();
+            moved_first.make_move(direction);
+            moved_first.rotate_cw();
+            moved_first.mirror();
+            let mut turned_first = board.clone();
+            turned_first.rotate_cw();
+            turned_first.mirror();
+            turned_first.make_move(direction.rotate_cw().mirror());
+            assert_eq!(moved_first, turned_first);
+
+            // Undoing goes back to the turned board
+            assert!(turned_first.undo());
+            moved_first.undo();
+            assert_eq!(moved_first, turned_first);
+        }
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let board = GameBoard::with_tiles([0, 0, 3, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let canonical = board.canonical_form();
+        assert_eq!(canonical, [[0; 4], [0; 4], [0, 0, 0, 2], [0, 0, 3, 1]]);
+
+        let mut transformed = board.clone();
+        for _ in 0..4 {
+            transformed.rotate_cw();
+            assert_eq!(transformed.canonical_form(), canonical);
+            transformed.mirror();
+            assert_eq!(transformed.canonical_form(), canonical);
+            transformed.mirror();
+        }
+
+        // Swapping two tiles isn't a symmetry
+        let swapped = GameBoard::with_tiles([0, 0, 1, 3, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_ne!(swapped.canonical_form(), canonical);
+    }
+
+    #[test]
+    fn test_undo_policy() {
+        let tiles = [1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let mut board = GameBoard::with_tiles(tiles);
+        board.set_undo_policy(UndoPolicy::Penalty(50));
+        assert!(board.make_move(Direction::Left).moved());
+        assert!(board.make_move(Direction::Left).moved());
+        assert_eq!(board.get_score(), 8 + 8);
+        // Half of the 8 scored is lost, and stays lost when undoing further back
+        assert!(board.undo());
+        assert_eq!(board.get_score(), 8 - 4);
+        assert!(board.undo());
+        assert_eq!(board.get_score(), 0);
+        assert_eq!(board.get_undo_count(), 2);
+        assert_eq!(board.check_plausible(false), Ok(()));
+
+        let mut board = GameBoard::with_tiles(tiles);
+        board.set_undo_policy(UndoPolicy::Limited(1));
+        assert!(board.make_move(Direction::Left).moved());
+        assert!(board.make_move(Direction::Left).moved());
+        assert!(board.undo());
+        assert!(!board.undo());
+        assert_eq!(board.get_score(), 8);
+        // The limit is for each game
+        board.clear();
+        assert_eq!(board.get_undo_count(), 0);
+
+        assert_eq!(UndoPolicy::parse("free"), Some(UndoPolicy::Free));
+        assert_eq!(
+            UndoPolicy::parse("penalty 25"),
+            Some(UndoPolicy::Penalty(25))
+        );
+        assert_eq!(UndoPolicy::parse("limit 3"), Some(UndoPolicy::Limited(3)));
+        assert_eq!(UndoPolicy::parse("limit"), None);
+        assert_eq!(UndoPolicy::parse("free 3"), None);
+    }
+
+    #[test]
+    fn test_move_count() {
+        let mut board = GameBoard::with_tiles([1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(board.make_move(Direction::Left).moved());
+        // Moves that don't change the board aren't counted
+        assert!(!board.make_move(Direction::Left).moved());
+        assert_eq!(board.get_move_count(), 1);
+
+        // The count is saved alongside the board by `Save`, not with the board itself
+        let parsed: GameBoard = GameBoard::from_bytes(&board.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed.get_move_count(), 0);
+
+        board.clear();
+        assert_eq!(board.get_move_count(), 0);
+    }
+
+    #[test]
+    fn test_undo_depth() {
+        let mut board = GameBoard::with_tiles([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        for &direction in [
+            Direction::Right,
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+        ]
+        .iter()
+        .cycle()
+        .take(UNDO_DEPTH + 2)
+        {
+            assert!(board.make_move(direction).moved());
+        }
+        assert_eq!(board.history().len(), UNDO_DEPTH);
+        for _ in 0..UNDO_DEPTH {
+            assert!(board.undo());
+        }
+        assert!(!board.undo());
+        // The oldest moves were forgotten
+        assert_eq!(board.get_tile(Coord::new(3, 3).unwrap()), 1);
+    }
+
+    #[test]
+    fn test_history_serialisation() {
+        let mut board = GameBoard::with_tiles([1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        board.make_move(Direction::Left);
+        board.make_move(Direction::Up);
+
+        let mut bytes = [0; BYTES_SIZE];
+        to_slice(board.history(), &mut bytes).unwrap();
+        let history: History = from_bytes(&bytes).unwrap();
+        assert_eq!(history.len(), 1);
+
+        let mut loaded = board.clone();
+        loaded.set_history(history);
+        assert!(loaded.undo());
+        assert!(board.undo());
+        assert_eq!(loaded, board);
+        assert!(!loaded.undo());
+
+        // A blank history is a single zero byte, as found in the padding of older saves
+        let empty: History = from_bytes(&[0]).unwrap();
+        assert!(empty.is_empty());
+    }
+
     #[test]
     fn test_make_move_full_board() {
         let mut board = GameBoard::full_of(1);
 
-        assert!(board.make_move(Direction::Down));
+        assert!(board.make_move(Direction::Down).moved());
         assert_eq!(
-            board.tiles,
+            board.tile_array(),
             [2, 2, 2, 2, 2, 2, 2, 2, 0, 0, 0, 0, 0, 0, 0, 0]
         );
         assert_eq!(board.score, 32);
 
-        assert!(board.make_move(Direction::Up));
+        assert!(board.make_move(Direction::Up).moved());
         assert_eq!(
-            board.tiles,
+            board.tile_array(),
             [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 3, 3, 3]
         );
         assert_eq!(board.score, 64);
 
-        assert!(board.make_move(Direction::Left));
+        assert!(board.make_move(Direction::Left).moved());
         assert_eq!(
-            board.tiles,
+            board.tile_array(),
             [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 4, 0, 0]
         );
         assert_eq!(board.score, 96);
 
-        assert!(board.make_move(Direction::Right));
+        assert!(board.make_move(Direction::Right).moved());
         assert_eq!(
-            board.tiles,
+            board.tile_array(),
             [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5]
         );
         assert_eq!(board.score, 128);
 
-        assert!(!board.make_move(Direction::Up));
+        assert!(!board.make_move(Direction::Up).moved());
         assert_eq!(
-            board.tiles,
+            board.tile_array(),
             [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5]
         );
         assert_eq!(board.score, 128);
     }
 
     #[test]
-    fn test_get_colour() {
-        for i in 0..(SIZE * SIZE) {
-            get_tile_colour(i as u8);
+    fn test_other_sizes() {
+        let mut board = GameBoard::<3>::from_rows([[1, 1, 1], [0, 0, 0], [0, 0, 2]]);
+        assert!(board.slide_with(Direction::Left, |_| {}));
+        assert_eq!(board.tiles, [[2, 1, 0], [0, 0, 0], [2, 0, 0]]);
+        assert!(board.slide_with(Direction::Up, |_| {}));
+        assert_eq!(board.tiles, [[0, 0, 0], [0, 0, 0], [3, 1, 0]]);
+        assert_eq!(board.get_score(), 12);
+        assert!(board.undo());
+        assert_eq!(board.tiles, [[2, 1, 0], [0, 0, 0], [2, 0, 0]]);
+
+        let board = GameBoard::<5>::seeded(1);
+        assert_eq!(board.vacant_tiles().count(), 5 * 5 - 2);
+        assert_eq!(
+            GameBoard::<5>::from_bytes(&board.to_bytes().unwrap()),
+            Ok(board)
+        );
+    }
+
+    #[test]
+    fn test_serialised_layout() {
+        // Tiles are stored as a flat array, bottom row first
+        let board = GameBoard::with_tiles([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0]);
+        let bytes = board.to_bytes().unwrap();
+        assert_eq!(bytes[..SIZE * SIZE], board.tile_array());
+    }
+
+    #[test]
+    fn test_new_game_with_seed() {
+        let board1 = GameBoard::new_game_with_seed(1);
+        let board2 = GameBoard::new_game_with_seed(1);
+        assert_eq!(board1, board2);
+        assert_eq!(board1.vacant_tiles().count(), SIZE * SIZE - 2);
+
+        // Different seeds should give different games, at least some of the time
+        assert!((2..10).any(|seed| GameBoard::new_game_with_seed(seed) != board1));
+    }
+
+    #[test]
+    fn test_injected_rng() {
+        // Always picking the first vacant tile and a 2
+        let rng = StepRng::new(1, 0);
+        let board = GameBoard::<SIZE, _>::new_game_with_rng(rng);
+        assert_eq!(
+            board.tile_array(),
+            [0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        let mut small = GameBoard::<3, _>::from_rows_with_rng([[0; 3]; 3], StepRng::new(0, 0));
+        assert!(small.set_random());
+        assert_eq!(small.max_tile(), Tile::from(2));
+    }
+
+    #[test]
+    fn test_move_log() {
+        let mut board = GameBoard::new_game_with_seed(5);
+        assert!(board.move_log().is_empty());
+        let mut played = 0;
+        for &direction in Direction::ALL.iter().cycle().take(2 * LOG_LENGTH) {
+            if board.make_move(direction).moved() {
+                board.set_random();
+                played += 1;
+            }
+        }
+        assert_eq!(board.move_log().len(), played.min(LOG_LENGTH));
+
+        // Replaying the last move from the board before it gives the same board
+        let last = *board.move_log().iter().last().unwrap();
+        let mut replayed = board.clone();
+        assert!(board.undo());
+        assert_eq!(board.move_log().len(), played.min(LOG_LENGTH) - 1);
+        assert!(board.make_move(last.direction()).moved());
+        let (coord, value) = last.spawned().unwrap();
+        assert_eq!(replayed.tile(coord), value);
+        replayed.clear();
+        assert!(replayed.move_log().is_empty());
+    }
+
+    #[test]
+    fn test_replay() {
+        let moves = [
+            Direction::Left,
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ];
+        let mut played = GameBoard::new_game_with_seed(42);
+        let mut replay = GameBoard::replay(42, moves.iter().copied());
+        assert_eq!(replay.next(), Some(played.clone()));
+        for (&direction, replayed) in moves.iter().zip(&mut replay) {
+            if played.make_move(direction).moved() {
+                played.set_random();
+            }
+            assert_eq!(replayed, played);
+        }
+        assert_eq!(replay.next(), None);
+
+        // A game can be replayed from its own log
+        let log = played.move_log().iter().map(|logged| logged.direction());
+        assert_eq!(GameBoard::replay(42, log).last(), Some(played));
+    }
+
+    #[test]
+    fn test_logged_move_display() {
+        use core::fmt::Write;
+
+        let mut rows = [[0; SIZE]; SIZE];
+        rows[0][1] = 1;
+        let mut board = GameBoard::<SIZE, _>::from_rows_with_rng(rows, StepRng::new(1, 0));
+        board.make_move(Direction::Left);
+        let mut line = heapless::String::<32>::new();
+        let logged = *board.move_log().iter().next().unwrap();
+        write!(line, "{}", logged.display(board.rule())).unwrap();
+        assert_eq!(line, "Left");
+
+        // Always the second vacant tile, and a 2
+        board.set_random();
+        line.clear();
+        let logged = *board.move_log().iter().next().unwrap();
+        write!(line, "{}", logged.display(board.rule())).unwrap();
+        assert_eq!(line, "Left, 2 at 2,0");
+
+        // The same tile is a 1 in Threes
+        line.clear();
+        write!(line, "{}", logged.display(&Threes)).unwrap();
+        assert_eq!(line, "Left, 1 at 2,0");
+    }
+
+    #[test]
+    fn test_fibonacci() {
+        let mut board = GameBoard::<SIZE, WyRng, Fibonacci>::from_rows([
+            [1, 2, 0, 0],
+            [2, 0, 2, 0],
+            [1, 0, 1, 0],
+            [0; 4],
+        ]);
+        // 1 and 2 make a 3 and 1 and 1 make a 2, but 2 and 2 don't merge
+        assert!(board.make_move(Direction::Left).moved());
+        assert_eq!(board.tiles[..3], [[3, 0, 0, 0], [2, 2, 0, 0], [2, 0, 0, 0]]);
+        assert_eq!(board.get_score(), 3 + 2);
+        // 2 and 3 make a 5, but 2 and 5 don't merge
+        assert!(board.make_move(Direction::Down).moved());
+        assert_eq!(board.tiles[..3], [[4, 2, 0, 0], [2, 0, 0, 0], [0; 4]]);
+        assert_eq!(board.get_score(), 3 + 2 + 5);
+
+        // Tiles are written as their values
+        use core::fmt::Write;
+        let mut text: heapless::String<64> = heapless::String::new();
+        write!(text, "{}", board).unwrap();
+        assert!(text.ends_with(" 2 . . .\n 5 2 . .\nscore 10"));
+    }
+
+    #[test]
+    fn test_best_merge() {
+        let mut board = GameBoard::with_tiles([3, 3, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(board.best_merge(), Tile::EMPTY);
+        board.make_move(Direction::Left);
+        assert_eq!(board.best_merge(), Tile::from(4));
+        // Smaller merges later on don't count
+        board.set_tile(Coord::new(0, 1).unwrap(), 1);
+        board.set_tile(Coord::new(0, 2).unwrap(), 1);
+        board.make_move(Direction::Up);
+        assert_eq!(board.best_merge(), Tile::from(4));
+        assert!(board.undo());
+        assert_eq!(board.best_merge(), Tile::from(4));
+        board.clear();
+        assert_eq!(board.best_merge(), Tile::EMPTY);
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut board = GameBoard::with_tiles([3, 3, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        board.make_move(Direction::Left);
+        board.set_random();
+        assert!(!board.make_move(Direction::Left).moved());
+        assert!(board.undo());
+        board.clear();
+        assert_eq!(
+            *board.stats(),
+            GameStats {
+                moves: 1,
+                merges: 2,
+                spawns: 1,
+                highest_tile: 4,
+                games_completed: 0,
+                achievements: Achievements::empty(),
+            }
+        );
+
+        // Filling the board so no move is left completes a game
+        let mut board = GameBoard::with_tiles([1, 2, 1, 2, 2, 1, 2, 1, 1, 2, 1, 2, 2, 1, 2, 0]);
+        board.set_stats(GameStats::new());
+        assert!(board.apply(Event::TileSpawned(Coord::new(3, 3).unwrap(), Tile::from(5))));
+        assert_eq!(board.stats().games_completed, 1);
+        assert_eq!(board.stats().highest_tile, 5);
+    }
+
+    #[test]
+    fn test_achievements() {
+        let mut board = GameBoard::with_tiles([8, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        board.make_move(Direction::Left);
+        assert_eq!(board.stats().achievements, Achievements::FIRST_512);
+
+        // Merging on enough moves in a row makes a chain, broken by any move without one
+        board.tiles = [[0, 1, 0, 0], [0; 4], [0; 4], [0; 4]];
+        board.make_move(Direction::Left);
+        for _ in 1..MERGE_CHAIN_LENGTH {
+            board.tiles = [[1, 1, 0, 0], [0; 4], [0; 4], [0; 4]];
+            board.make_move(Direction::Left);
+        }
+        assert!(!board
+            .stats()
+            .achievements
+            .contains(Achievements::MERGE_CHAIN));
+        board.tiles = [[1, 1, 0, 0], [0; 4], [0; 4], [0; 4]];
+        board.make_move(Direction::Left);
+        assert!(board
+            .stats()
+            .achievements
+            .contains(Achievements::MERGE_CHAIN));
+
+        // A 2048 made after an undo isn't a win without one
+        let mut board = GameBoard::with_tiles([10, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        board.make_move(Direction::Up);
+        assert!(board.undo());
+        board.make_move(Direction::Left);
+        let achievements = board.stats().achievements;
+        assert!(achievements.contains(Achievements::FIRST_2048));
+        assert!(!achievements.contains(Achievements::NO_UNDO_WIN));
+
+        let mut board = GameBoard::with_tiles([10, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        board.make_move(Direction::Left);
+        assert!(board
+            .stats()
+            .achievements
+            .contains(Achievements::NO_UNDO_WIN));
+    }
+
+    #[test]
+    fn test_threes() {
+        let mut board = GameBoard::new_variant_game(Variant::Threes, 0);
+        board.tiles = [[1, 2, 1, 1], [3, 3, 2, 2], [0; 4], [0; 4]];
+        // A 1 and a 2 make a 3 and two 3s make a 6, but 1s and 2s don't pair up
+        assert!(board.make_move(Direction::Left).moved());
+        assert_eq!(board.tiles[..2], [[3, 1, 1, 0], [4, 2, 2, 0]]);
+        assert_eq!(board.get_score(), 3 + 6);
+        assert_eq!(board.check_plausible(false), Ok(()));
+    }
+
+    #[test]
+    fn test_events() {
+        let mut played = GameBoard::new_game_with_seed(5);
+        let mut replayed = played.clone();
+        for &direction in Direction::ALL.iter().cycle().take(12) {
+            if played.make_move(direction).moved() {
+                played.set_random();
+            }
+        }
+        // The log is enough to play the same game again
+        let events = played.move_log().iter().flat_map(LoggedMove::events);
+        for event in events {
+            assert!(replayed.apply(event));
+        }
+        assert_eq!(replayed, played);
+        assert!(replayed.move_log().iter().eq(played.move_log().iter()));
+        assert_eq!(replayed.get_move_count(), played.get_move_count());
+
+        // Tiles can only be added to empty spaces
+        let (coord, _) = played.tiles().find(|(_, tile)| !tile.is_empty()).unwrap();
+        assert!(!played.apply(Event::TileSpawned(coord, Tile::from(1))));
+        assert!(played.apply(Event::GameReset));
+        assert_eq!(played, GameBoard::empty());
+        assert!(played.move_log().is_empty());
+    }
+
+    #[test]
+    fn test_score_policy() {
+        assert_eq!(ScorePolicy::parse("merges"), Some(ScorePolicy::Merges));
+        assert_eq!(ScorePolicy::parse("points"), None);
+
+        let tiles = [1, 1, 2, 2, 3, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let score = |policy| {
+            let mut board = GameBoard::with_tiles(tiles);
+            board.set_score_policy(policy);
+            board.make_move(Direction::Left);
+            board.get_score()
+        };
+        // Merges make a 4, an 8 and a 16
+        assert_eq!(score(ScorePolicy::Classic), 4 + 8 + 16);
+        assert_eq!(score(ScorePolicy::Merges), 3);
+        assert_eq!(score(ScorePolicy::HighestTile), 16);
+    }
+
+    #[test]
+    fn test_time_attack() {
+        let mut board = GameBoard::with_tiles([1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(!board.tick());
+        assert_eq!(board.ticks_left(), None);
+
+        board.set_time_limit(Some(2));
+        assert!(!board.tick());
+        assert_eq!(board.ticks_left(), Some(1));
+        assert!(!board.is_game_over());
+        assert!(board.tick());
+        assert!(board.is_time_up());
+        assert!(board.is_game_over());
+        assert_eq!(board.stats().games_completed, 1);
+        assert!(!board.tick());
+
+        // No moves can be made once time is up, until the next game
+        assert!(!board.can_move(Direction::Left));
+        assert!(!board.make_move(Direction::Left).moved());
+        board.clear();
+        assert_eq!(board.ticks_left(), Some(2));
+        assert_eq!(board.time_limit(), Some(2));
+    }
+
+    #[test]
+    fn test_spawn_rate() {
+        assert_eq!(SpawnRate::parse("double"), Some(SpawnRate::Double));
+        assert_eq!(SpawnRate::parse("triple"), None);
+        assert_eq!(SpawnRate::Escalating.spawns(9_999), 1);
+        assert_eq!(SpawnRate::Escalating.spawns(10_000), 2);
+        assert_eq!(SpawnRate::Escalating.spawns(u32::MAX), MAX_SPAWNS);
+
+        let mut board = GameBoard::empty();
+        assert_eq!(board.spawn_tiles(), 1);
+        board.set_spawn_rate(SpawnRate::Double);
+        assert_eq!(board.spawn_tiles(), 2);
+        assert_eq!(board.vacant_tiles().count(), SIZE * SIZE - 3);
+
+        // Tiles stop being added once the board is full
+        let mut tiles = [1; SIZE * SIZE];
+        tiles[0] = 0;
+        let mut board = GameBoard::with_tiles(tiles);
+        board.set_spawn_rate(SpawnRate::Double);
+        assert_eq!(board.spawn_tiles(), 1);
+    }
+
+    #[test]
+    fn test_endless() {
+        let mut tiles = [0; 16];
+        tiles[0] = MAX_TILE;
+        tiles[1] = MAX_TILE;
+        let mut board = GameBoard::with_tiles(tiles);
+        // Refused, so the tiles stay put
+        assert!(!board.make_move(Direction::Left).moved());
+        assert_eq!(board.get_score(), 0);
+
+        board.set_endless(EndlessPolicy::Saturate);
+        let outcome = board.make_move(Direction::Left);
+        assert_eq!(
+            outcome.merges().next().unwrap().result(),
+            Tile::from(MAX_TILE)
+        );
+        assert_eq!(board.max_tile(), Tile::from(MAX_TILE));
+        assert_eq!(board.get_score(), 1 << MAX_TILE);
+
+        // The score saturates rather than wrapping
+        let mut board = GameBoard::with_tiles(tiles);
+        board.set_endless(EndlessPolicy::Saturate);
+        board.score = u32::MAX - 1;
+        board.make_move(Direction::Left);
+        assert_eq!(board.get_score(), u32::MAX);
+    }
+
+    #[test]
+    fn test_four_odds() {
+        // Each tile takes one draw for its position then one for its value, the second even
+        let mut board =
+            GameBoard::<SIZE, _>::from_rows_with_rng([[0; SIZE]; SIZE], StepRng::new(1, 1));
+        assert_eq!(board.four_odds(), DEFAULT_FOUR_ODDS);
+        board.set_four_odds(2);
+        board.set_random();
+        assert_eq!(board.max_tile(), Tile::from(2));
+
+        board.clear();
+        board.set_four_odds(0);
+        board.set_random();
+        assert_eq!(board.max_tile(), Tile::from(1));
+    }
+
+    #[test]
+    fn test_peek_move() {
+        let mut board = GameBoard::empty();
+        board.set_tile(Coord::new(0, 0).unwrap(), 1);
+        board.set_tile(Coord::new(3, 0).unwrap(), 1);
+        let original = board.clone();
+
+        let peeked = board.peek_move(Direction::Left).unwrap();
+        assert_eq!(board, original);
+        assert_eq!(peeked.get_tile(Coord::new(0, 0).unwrap()), 2);
+        assert_eq!(peeked.vacant_tiles().count(), SIZE * SIZE - 1);
+        assert!(peeked.peek_move(Direction::Left).is_none());
+
+        // Peeking doesn't change which tiles are added next
+        let mut played = GameBoard::new_game_with_seed(4);
+        let mut peeking = played.clone();
+        for &direction in Direction::ALL.iter().cycle().take(20) {
+            for &peek in Direction::ALL.iter() {
+                peeking.peek_move(peek);
+            }
+            played.make_move(direction);
+            played.set_random();
+            peeking.make_move(direction);
+            peeking.set_random();
+            assert_eq!(peeking, played);
+        }
+    }
+
+    #[test]
+    fn test_best_move() {
+        // Left and right both slide the row, but only right leaves it in order
+        let board = GameBoard::with_tiles([1, 0, 2, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(board.best_move(), Some(Direction::Right));
+
+        // Merging leaves more tiles empty
+        let board = GameBoard::with_tiles([2, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let direction = board.best_move().unwrap();
+        assert!(direction == Direction::Up || direction == Direction::Down);
+
+        let stuck = GameBoard::with_tiles([1, 2, 1, 2, 2, 1, 2, 1, 1, 2, 1, 2, 2, 1, 2, 1]);
+        assert_eq!(stuck.best_move(), None);
+    }
+
+    #[test]
+    fn test_compact_bytes() {
+        let mut board = GameBoard::new_game_with_seed(2);
+        for &direction in Direction::ALL.iter().cycle().take(20) {
+            if board.make_move(direction).moved() {
+                board.set_random();
+            }
         }
+        let bytes = board.to_compact_bytes().unwrap();
+        assert!(bytes.len() < BYTES_SIZE);
+        let parsed = GameBoard::from_compact_bytes(&bytes).unwrap();
+        assert_eq!(parsed.tile_array(), board.tile_array());
+        assert_eq!(parsed.get_score(), board.get_score());
+
+        // A 2048 with no score is rejected, even with a good checksum
+        let mut tiles = [0; SIZE * SIZE];
+        tiles[0] = 11;
+        let unscored = GameBoard::with_tiles(tiles).to_compact_bytes().unwrap();
+        assert_eq!(
+            GameBoard::from_compact_bytes(&unscored),
+            Err(Error::SaveImplausible)
+        );
+    }
+
+    #[test]
+    fn test_from_state() {
+        let mut tiles = [0; SIZE * SIZE];
+        tiles[0] = 3;
+        tiles[5] = 1;
+        let board = GameBoard::from_state(tiles, 8, 2).unwrap();
+        assert_eq!(board.tile_array(), tiles);
+        assert_eq!(board.get_score(), 8);
+        assert_eq!(board.get_move_count(), 2);
+
+        assert_eq!(
+            GameBoard::from_state(tiles, 1_000, 2),
+            Err(Error::SaveImplausible)
+        );
+        tiles[15] = MAX_TILE + 1;
+        assert_eq!(GameBoard::from_state(tiles, 8, 2), Err(Error::InvalidTile));
     }
 
     #[test]
@@ -568,7 +2992,7 @@ mod tests {
     }
 
     fn do_serialisation_test_on_board(board: &GameBoard) {
-        let bytes = board.to_bytes();
+        let bytes = board.to_bytes().unwrap();
         let parsed_board = GameBoard::from_bytes(&bytes).unwrap();
         assert_eq!(*board, parsed_board);
     }
@@ -582,22 +3006,22 @@ mod tests {
         });
 
         (1..5).for_each(|_| {
-            [
-                Direction::Up,
-                Direction::Right,
-                Direction::Down,
-                Direction::Left,
-            ]
-            .iter()
-            .for_each(|&direction| {
+            Direction::ALL.iter().for_each(|&direction| {
                 board.make_move(direction);
                 board.set_random();
                 do_serialisation_test_on_board(&board);
             });
         });
 
+        // A 32768 takes at least 13 * 32768 points to make
         board.set_tile(Coord::new(2, 2).unwrap(), 15);
-        board.score = 1000000;
+        board.score = 450_000;
         do_serialisation_test_on_board(&board);
+
+        board.score = 1_000_000;
+        assert_eq!(
+            GameBoard::<SIZE>::from_bytes(&board.to_bytes().unwrap()),
+            Err(Error::SaveImplausible)
+        );
     }
 }
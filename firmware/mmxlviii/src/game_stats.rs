@@ -0,0 +1,81 @@
+use postcard::{from_bytes, to_slice};
+use serde::{Deserialize, Serialize};
+
+use crate::{achievements::Achievements, error::Error};
+
+/// Size of the statistics serialized in bytes, rounded up to the next 16 bytes.
+pub const BYTES_SIZE: usize = 32;
+
+/// Statistics of play across every game, kept by `GameBoard` as the game changes.
+///
+/// They are carried from one game to the next rather than reset with the
+/// board, and saved on their own, so they last across power cycles.
+/// Undoing a move doesn't take back what it counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameStats {
+    pub moves: u32,
+    pub merges: u32,
+    pub spawns: u32,
+    /// Largest tile ever made or added, or 0 if there hasn't been one.
+    pub highest_tile: u8,
+    /// Number of games played until no move was left.
+    pub games_completed: u32,
+    /// Last, so statistics saved before there were achievements load with none.
+    pub achievements: Achievements,
+}
+
+impl GameStats {
+    pub fn new() -> GameStats {
+        GameStats::default()
+    }
+
+    pub(crate) fn record_move(&mut self) {
+        self.moves = self.moves.saturating_add(1);
+    }
+
+    pub(crate) fn record_merge(&mut self, tile: u8) {
+        self.merges = self.merges.saturating_add(1);
+        self.highest_tile = self.highest_tile.max(tile);
+    }
+
+    pub(crate) fn record_spawn(&mut self, tile: u8) {
+        self.spawns = self.spawns.saturating_add(1);
+        self.highest_tile = self.highest_tile.max(tile);
+    }
+
+    pub(crate) fn record_game_over(&mut self) {
+        self.games_completed = self.games_completed.saturating_add(1);
+    }
+
+    pub fn to_bytes(&self) -> Result<[u8; BYTES_SIZE], Error> {
+        let mut bytes = [0; BYTES_SIZE];
+        to_slice(self, &mut bytes).map_err(|_| Error::StorageFull)?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        from_bytes::<GameStats>(bytes).map_err(|_| Error::SaveCorrupt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes() {
+        let stats = GameStats {
+            moves: u32::MAX,
+            merges: u32::MAX,
+            spawns: u32::MAX,
+            highest_tile: u8::MAX,
+            games_completed: u32::MAX,
+            achievements: Achievements::from_bits(u16::MAX),
+        };
+        assert_eq!(GameStats::from_bytes(&stats.to_bytes().unwrap()), Ok(stats));
+        assert_eq!(
+            GameStats::from_bytes(&[0; BYTES_SIZE]),
+            Ok(GameStats::new())
+        );
+    }
+}
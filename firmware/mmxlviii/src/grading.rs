@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ai")]
+use crate::ai::preferred_move;
+use crate::{board::Direction, game_board::GameBoard};
+
+/// Tracks how often the player's moves matched the AI's preferred move.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveGrade {
+    matched: u32,
+    total: u32,
+}
+
+impl MoveGrade {
+    pub fn new() -> MoveGrade {
+        MoveGrade::default()
+    }
+
+    /// Grade a move, given the board before the move was made.
+    /// Without the AI, moves aren't graded.
+    #[cfg_attr(not(feature = "ai"), allow(unused_variables))]
+    pub fn record(&mut self, board: &GameBoard, direction: Direction) {
+        #[cfg(feature = "ai")]
+        {
+            if preferred_move(board) == Some(direction) {
+                self.matched += 1;
+            }
+            self.total += 1;
+        }
+    }
+
+    /// Get the percentage of moves that matched the AI.
+    /// A game with no moves is considered perfect.
+    pub fn accuracy(&self) -> u32 {
+        (100 * self.matched).checked_div(self.total).unwrap_or(100)
+    }
+}
+
+#[cfg(all(test, feature = "ai"))]
+mod tests {
+    use super::*;
+    use crate::board::SIZE;
+
+    #[test]
+    fn test_accuracy() {
+        let mut tiles = [0; SIZE * SIZE];
+        tiles[0] = 3;
+        tiles[SIZE] = 3;
+        let board = GameBoard::with_tiles(tiles);
+        let best = preferred_move(&board).unwrap();
+
+        let mut grade = MoveGrade::new();
+        assert_eq!(grade.accuracy(), 100);
+        grade.record(&board, best);
+        assert_eq!(grade.accuracy(), 100);
+        grade.record(&board, best.rotate_cw());
+        assert_eq!(grade.accuracy(), 50);
+        grade.record(&board, best.rotate_ccw());
+        assert_eq!(grade.accuracy(), 33);
+    }
+}
@@ -0,0 +1,423 @@
+use heapless::Vec;
+use postcard::{from_bytes, to_slice};
+use serde::{Deserialize, Serialize};
+use smart_leds::{colors::GOLD, RGB8};
+
+use crate::{
+    board::{Board, Coord, IntoBoard},
+    error::Error,
+    game_board::{GameBoard, Variant, DEFAULT_FOUR_ODDS},
+    render::RenderContext,
+    score_board::ScoreBoard,
+    tile::Tile,
+};
+
+/// Size of the high score serialized in bytes, rounded up to the next 16 bytes.
+pub const BYTES_SIZE: usize = 16;
+
+/// Number of categories a high score table keeps bests for.
+pub const TABLE_SIZE: usize = 4;
+/// Size of a high score table serialized in bytes, rounded up to the next 16 bytes.
+pub const TABLE_BYTES_SIZE: usize = 48;
+
+/// Colour of the best score, so it can't be mistaken for the current one.
+const HIGH_SCORE_COLOUR: RGB8 = GOLD;
+/// Row left free by `ScoreBoard`, used for the best tile.
+const BEST_TILE_ROW: usize = 1;
+
+/// The best score and best tile across all games, persisted across power cycles.
+///
+/// The two are tracked separately, so the best tile may come from a game
+/// other than the one with the best score.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScore {
+    best_score: u32,
+    best_tile: Tile,
+}
+
+impl HighScore {
+    pub fn new() -> HighScore {
+        HighScore::default()
+    }
+
+    pub fn best_score(&self) -> u32 {
+        self.best_score
+    }
+
+    pub fn best_tile(&self) -> Tile {
+        self.best_tile
+    }
+
+    /// Update the bests with a game's progress.
+    /// Returns true if either was beaten.
+    pub fn record(&mut self, board: &GameBoard) -> bool {
+        let mut improved = false;
+        if board.get_score() > self.best_score {
+            self.best_score = board.get_score();
+            improved = true;
+        }
+        if board.max_tile() > self.best_tile {
+            self.best_tile = board.max_tile();
+            improved = true;
+        }
+        improved
+    }
+
+    pub fn to_bytes(&self) -> Result<[u8; BYTES_SIZE], Error> {
+        let mut bytes = [0; BYTES_SIZE];
+        to_slice(self, &mut bytes).map_err(|_| Error::StorageFull)?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        from_bytes::<HighScore>(bytes).map_err(|_| Error::SaveCorrupt)
+    }
+}
+
+/// The rules a game was played with, so games played with different rules
+/// don't compete for the same high score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreCategory {
+    pub variant: Variant,
+    /// The difficulty, as how often a new tile is a 4, see `GameBoard::four_odds`.
+    pub four_odds: u8,
+    /// Whether the game was a time attack, see `GameBoard::time_limit`. Time
+    /// attacks of every length share a category, as the table has no room
+    /// left for the limit.
+    pub timed: bool,
+}
+
+impl ScoreCategory {
+    /// Get the category of a game being played.
+    pub fn of(variant: Variant, board: &GameBoard) -> ScoreCategory {
+        ScoreCategory {
+            variant,
+            four_odds: board.four_odds(),
+            timed: board.time_limit().is_some(),
+        }
+    }
+}
+
+impl Default for ScoreCategory {
+    fn default() -> ScoreCategory {
+        ScoreCategory {
+            variant: Variant::default(),
+            four_odds: DEFAULT_FOUR_ODDS,
+            timed: false,
+        }
+    }
+}
+
+/// A category as kept by firmware from before time attacks had their own,
+/// only read by `HighScoreTable::from_untimed_bytes`.
+#[derive(Deserialize)]
+struct UntimedCategory {
+    variant: Variant,
+    four_odds: u8,
+}
+
+/// The bests for each category of game that has been played, persisted
+/// across power cycles.
+///
+/// Categories are kept in the order they were first played. Once the table
+/// is full, a new category replaces the one with the lowest best score.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    entries: Vec<(ScoreCategory, HighScore), TABLE_SIZE>,
+}
+
+impl HighScoreTable {
+    pub fn new() -> HighScoreTable {
+        HighScoreTable::default()
+    }
+
+    /// Create a table from the single high score kept by older firmware,
+    /// which was only ever set by the default category.
+    pub fn from_legacy(high_score: HighScore) -> HighScoreTable {
+        let mut table = HighScoreTable::new();
+        if high_score != HighScore::default() {
+            let _ = table.entries.push((ScoreCategory::default(), high_score));
+        }
+        table
+    }
+
+    /// Get the number of categories with a high score.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get the bests for a category, which are zero if it hasn't been played.
+    pub fn get(&self, category: ScoreCategory) -> HighScore {
+        self.position(category)
+            .map(|index| self.entries[index].1)
+            .unwrap_or_default()
+    }
+
+    /// Get the position of a category in the table, for choosing which to show.
+    pub fn position(&self, category: ScoreCategory) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|&(other, _)| other == category)
+    }
+
+    /// Get the bests at a position in the table, wrapping around at the end,
+    /// so a selection can be stepped through without checking its bounds.
+    pub fn nth(&self, index: usize) -> Option<(ScoreCategory, HighScore)> {
+        match self.entries.len() {
+            0 => None,
+            len => Some(self.entries[index % len]),
+        }
+    }
+
+    /// Update the bests for a category with a game's progress.
+    /// Returns true if either was beaten.
+    pub fn record(&mut self, category: ScoreCategory, board: &GameBoard) -> bool {
+        if let Some(index) = self.position(category) {
+            return self.entries[index].1.record(board);
+        }
+        let mut high_score = HighScore::new();
+        if !high_score.record(board) {
+            return false;
+        }
+        if self.entries.is_full() {
+            let worst = (0..self.entries.len())
+                .min_by_key(|&index| self.entries[index].1.best_score())
+                .unwrap();
+            self.entries[worst] = (category, high_score);
+        } else {
+            let _ = self.entries.push((category, high_score));
+        }
+        true
+    }
+
+    pub fn to_bytes(&self) -> Result<[u8; TABLE_BYTES_SIZE], Error> {
+        let mut bytes = [0; TABLE_BYTES_SIZE];
+        to_slice(self, &mut bytes).map_err(|_| Error::StorageFull)?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        from_bytes::<HighScoreTable>(bytes).map_err(|_| Error::SaveCorrupt)
+    }
+
+    /// Decode a table stored by older firmware, whose categories had no
+    /// time attack flag. Every game it kept was untimed.
+    ///
+    /// A table already in the current format, which encodes back to exactly
+    /// the same bytes, is read as it is, so a migration can safely be repeated.
+    pub fn from_untimed_bytes(bytes: &[u8; TABLE_BYTES_SIZE]) -> Result<Self, Error> {
+        if let Ok(table) = HighScoreTable::from_bytes(bytes) {
+            if table.to_bytes().as_ref() == Ok(bytes) {
+                return Ok(table);
+            }
+        }
+        let entries = from_bytes::<Vec<(UntimedCategory, HighScore), TABLE_SIZE>>(bytes)
+            .map_err(|_| Error::SaveCorrupt)?;
+        let entries = entries
+            .into_iter()
+            .map(|(category, high_score)| {
+                let category = ScoreCategory {
+                    variant: category.variant,
+                    four_odds: category.four_odds,
+                    timed: false,
+                };
+                (category, high_score)
+            })
+            .collect();
+        Ok(HighScoreTable { entries })
+    }
+}
+
+impl IntoBoard for HighScore {
+    /// Show the best score in the same digits as `ScoreBoard`, with the best
+    /// tile's colour along the row between them.
+    fn into_board(&self, context: &RenderContext) -> Board {
+        let digits = ScoreBoard::from_score(self.best_score).into_board(context);
+        let mut board = Board::new();
+        for (coord, _) in Board::new().diff(&digits) {
+            board.set_led(coord, HIGH_SCORE_COLOUR);
+        }
+        if !self.best_tile.is_empty() {
+            for coord in Coord::row(BEST_TILE_ROW) {
+                board.set_led(coord, context.palette.tile_colour(self.best_tile));
+            }
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Direction;
+
+    #[test]
+    fn test_record() {
+        let mut high_score = HighScore::new();
+        let mut board = GameBoard::with_tiles([5, 5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(high_score.record(&board));
+        assert_eq!(high_score.best_tile(), Tile::from(5));
+        assert!(!high_score.record(&board));
+
+        board.make_move(Direction::Left);
+        assert!(high_score.record(&board));
+        assert_eq!(high_score.best_score(), 64);
+        assert_eq!(high_score.best_tile(), Tile::from(6));
+
+        // A worse game doesn't lower either best
+        assert!(!high_score.record(&GameBoard::with_tiles([1; 16])));
+        assert_eq!(high_score.best_score(), 64);
+        assert_eq!(high_score.best_tile(), Tile::from(6));
+    }
+
+    #[test]
+    fn test_serialisation() {
+        let mut high_score = HighScore::new();
+        high_score.record(&GameBoard::with_tiles([15; 16]));
+        let bytes = high_score.to_bytes().unwrap();
+        assert_eq!(HighScore::from_bytes(&bytes), Ok(high_score));
+    }
+
+    #[test]
+    fn test_worst_case_size() {
+        let high_score = HighScore {
+            best_score: u32::MAX,
+            best_tile: Tile::from(u8::MAX),
+        };
+        assert!(high_score.to_bytes().is_ok());
+    }
+
+    fn harder() -> ScoreCategory {
+        ScoreCategory {
+            four_odds: 2,
+            ..ScoreCategory::default()
+        }
+    }
+
+    #[test]
+    fn test_categories_are_separate() {
+        let mut table = HighScoreTable::new();
+        assert!(table.record(ScoreCategory::default(), &GameBoard::with_tiles([7; 16])));
+        assert!(table.record(harder(), &GameBoard::with_tiles([3; 16])));
+        assert_eq!(
+            table.get(ScoreCategory::default()).best_tile(),
+            Tile::from(7)
+        );
+        assert_eq!(table.get(harder()).best_tile(), Tile::from(3));
+        assert_eq!(table.position(harder()), Some(1));
+        assert_eq!(table.nth(3).map(|(category, _)| category), Some(harder()));
+
+        // An empty board sets no best, so doesn't take up a category
+        let unplayed = ScoreCategory {
+            four_odds: 0,
+            ..ScoreCategory::default()
+        };
+        assert!(!table.record(unplayed, &GameBoard::empty()));
+        assert_eq!(table.len(), 2);
+    }
+
+    #[test]
+    fn test_time_attack_category() {
+        let mut board = GameBoard::with_tiles([7; 16]);
+        let untimed = ScoreCategory::of(Variant::Classic, &board);
+        board.set_time_limit(Some(60 * 60));
+        let timed = ScoreCategory::of(Variant::Classic, &board);
+        assert!(timed.timed);
+        assert_ne!(timed, untimed);
+
+        let mut table = HighScoreTable::new();
+        assert!(table.record(timed, &board));
+        assert_eq!(table.get(untimed), HighScore::new());
+        assert!(table.record(untimed, &GameBoard::with_tiles([3; 16])));
+        assert_eq!(table.get(timed).best_tile(), Tile::from(7));
+    }
+
+    #[test]
+    fn test_untimed_bytes() {
+        // A table of two categories as older firmware stored it: the length,
+        // then each category's variant and four odds, then its bests
+        let mut bytes = [0; TABLE_BYTES_SIZE];
+        bytes[..9].copy_from_slice(&[2, 0, 10, 64, 6, 1, 2, 4, 2]);
+        let table = HighScoreTable::from_untimed_bytes(&bytes).unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get(ScoreCategory::default()).best_score(), 64);
+        let threes = ScoreCategory {
+            variant: Variant::Threes,
+            four_odds: 2,
+            timed: false,
+        };
+        assert_eq!(table.get(threes).best_tile(), Tile::from(2));
+
+        // Migrating again leaves the table as it is
+        let bytes = table.to_bytes().unwrap();
+        assert_eq!(HighScoreTable::from_untimed_bytes(&bytes), Ok(table));
+    }
+
+    #[test]
+    fn test_full_table() {
+        let mut table = HighScoreTable::new();
+        for four_odds in 0..TABLE_SIZE as u8 {
+            let mut board = GameBoard::with_tiles([1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            for _ in 0..four_odds {
+                board.make_move(Direction::Left);
+                board.make_move(Direction::Right);
+            }
+            let category = ScoreCategory {
+                four_odds,
+                ..ScoreCategory::default()
+            };
+            table.record(category, &board);
+        }
+        // The category with the lowest best score makes way
+        let newest = ScoreCategory {
+            four_odds: 100,
+            ..ScoreCategory::default()
+        };
+        assert!(table.record(newest, &GameBoard::with_tiles([9; 16])));
+        assert_eq!(table.len(), TABLE_SIZE);
+        assert_eq!(table.position(newest), Some(0));
+    }
+
+    #[test]
+    fn test_table_serialisation() {
+        let mut table = HighScoreTable::from_legacy(HighScore {
+            best_score: u32::MAX,
+            best_tile: Tile::from(u8::MAX),
+        });
+        for four_odds in 1..TABLE_SIZE as u8 {
+            let category = ScoreCategory {
+                four_odds,
+                ..ScoreCategory::default()
+            };
+            table.entries.push((category, table.entries[0].1)).unwrap();
+        }
+        let bytes = table.to_bytes().unwrap();
+        assert_eq!(HighScoreTable::from_bytes(&bytes), Ok(table));
+        assert!(HighScoreTable::from_legacy(HighScore::new()).is_empty());
+    }
+
+    #[test]
+    fn test_best_tile_row() {
+        let context = RenderContext::default();
+        let mut high_score = HighScore::new();
+        // Nothing is shown for the best tile until a game has been played
+        let coord = Coord::new(0, BEST_TILE_ROW).unwrap();
+        assert_eq!(
+            high_score.into_board(&context).get_led(coord),
+            Board::new().get_led(coord)
+        );
+
+        high_score.record(&GameBoard::with_tiles([7; 16]));
+        let board = high_score.into_board(&context);
+        for coord in Coord::row(BEST_TILE_ROW) {
+            assert_eq!(
+                board.get_led(coord),
+                context.palette.tile_colour(Tile::from(7))
+            );
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+use crate::board::Direction;
+
+/// Maps joystick directions to game directions, allowing for rotated joysticks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ButtonMap {
+    up: Direction,
+    down: Direction,
+    left: Direction,
+    right: Direction,
+}
+
+impl ButtonMap {
+    pub fn new(up: Direction, down: Direction, left: Direction, right: Direction) -> ButtonMap {
+        ButtonMap {
+            up,
+            down,
+            left,
+            right,
+        }
+    }
+
+    /// Get the game direction for a joystick direction.
+    pub fn map(&self, direction: Direction) -> Direction {
+        match direction {
+            Direction::Up => self.up,
+            Direction::Down => self.down,
+            Direction::Left => self.left,
+            Direction::Right => self.right,
+        }
+    }
+}
+
+/// What a short press of B does, chosen in the settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Shortcut {
+    /// Undo the last move.
+    #[default]
+    Undo,
+    /// Switch to the next theme.
+    Theme,
+    /// Show the score, or go back to the game if it's already shown.
+    Score,
+}
+
+impl Shortcut {
+    /// Parse a shortcut from its console name.
+    pub fn parse(name: &str) -> Option<Shortcut> {
+        match name {
+            "undo" => Some(Shortcut::Undo),
+            "theme" => Some(Shortcut::Theme),
+            "score" => Some(Shortcut::Score),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ButtonMap {
+    fn default() -> ButtonMap {
+        ButtonMap::new(
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map() {
+        let identity = ButtonMap::default();
+        assert_eq!(identity.map(Direction::Left), Direction::Left);
+
+        let rotated = ButtonMap::new(
+            Direction::Right,
+            Direction::Left,
+            Direction::Up,
+            Direction::Down,
+        );
+        assert_eq!(rotated.map(Direction::Up), Direction::Right);
+        assert_eq!(rotated.map(Direction::Left), Direction::Up);
+    }
+}
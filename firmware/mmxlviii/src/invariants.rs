@@ -0,0 +1,117 @@
+//! Checks of what every move must keep true, for fuzzers and property tests
+//! to run against any board or sequence of moves.
+//!
+//! A cargo-fuzz target can hand its input straight to `check_game`, and a
+//! proptest strategy can generate boards and directions for `check_move`.
+
+use rand::SeedableRng;
+use wyhash::WyRng;
+
+use crate::{board::Direction, game_board::GameBoard};
+
+/// An invariant a move broke.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// The tiles after the move weren't the tiles before, less one per merge.
+    TileCount {
+        before: usize,
+        after: usize,
+        merges: usize,
+    },
+    /// The score went down.
+    ScoreDecreased { before: u32, after: u32 },
+    /// The move changed the board when the board said it couldn't.
+    MovedWhenStuck(Direction),
+    /// The move left the board unchanged when the board said it could move.
+    StuckWhenMovable(Direction),
+}
+
+/// Count the tiles on a board, not counting blockers.
+fn tile_count(board: &GameBoard) -> usize {
+    board
+        .tiles()
+        .filter(|(_, tile)| !tile.is_empty() && !tile.is_blocker())
+        .count()
+}
+
+/// Make a move on a copy of the board and check the invariants of a move hold.
+/// No new tile is added.
+pub fn check_move(board: &GameBoard, direction: Direction) -> Result<(), Violation> {
+    let mut moved = board.clone();
+    let outcome = moved.make_move(direction);
+
+    if !board.can_move(direction) {
+        // Nothing may change, as often as the move is tried
+        if outcome.moved() || moved != *board {
+            return Err(Violation::MovedWhenStuck(direction));
+        }
+        return Ok(());
+    }
+    if !outcome.moved() {
+        return Err(Violation::StuckWhenMovable(direction));
+    }
+
+    let (before, after) = (tile_count(board), tile_count(&moved));
+    let merges = outcome.merges().count();
+    if after + merges != before {
+        return Err(Violation::TileCount {
+            before,
+            after,
+            merges,
+        });
+    }
+    if moved.get_score() < board.get_score() {
+        return Err(Violation::ScoreDecreased {
+            before: board.get_score(),
+            after: moved.get_score(),
+        });
+    }
+    Ok(())
+}
+
+/// Play a whole game from arbitrary bytes, checking every move in every
+/// direction along the way. The first 8 bytes seed the game, and each byte
+/// after picks the next move.
+pub fn check_game(bytes: &[u8]) -> Result<(), Violation> {
+    let mut seed = [0; 8];
+    let split = bytes.len().min(seed.len());
+    seed[..split].copy_from_slice(&bytes[..split]);
+    let mut board = GameBoard::new_game_with_rng(WyRng::seed_from_u64(u64::from_le_bytes(seed)));
+
+    for &byte in &bytes[split..] {
+        for &direction in Direction::ALL.iter() {
+            check_move(&board, direction)?;
+        }
+        let direction = Direction::ALL[usize::from(byte) % Direction::ALL.len()];
+        if board.make_move(direction).moved() {
+            board.spawn_tiles();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::RngCore;
+
+    use super::*;
+
+    #[test]
+    fn test_random_games() {
+        let mut rng = WyRng::seed_from_u64(2303);
+        let mut bytes = [0; 512];
+        for _ in 0..50 {
+            rng.fill_bytes(&mut bytes);
+            assert_eq!(check_game(&bytes), Ok(()));
+        }
+        assert_eq!(check_game(&[]), Ok(()));
+    }
+
+    #[test]
+    fn test_stuck_board() {
+        let board = GameBoard::with_tiles([1, 2, 1, 2, 2, 1, 2, 1, 1, 2, 1, 2, 2, 1, 2, 1]);
+        for &direction in Direction::ALL.iter() {
+            assert_eq!(check_move(&board, direction), Ok(()));
+        }
+    }
+}
@@ -1,8 +1,63 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod achievements;
+#[cfg(feature = "ai")]
+pub mod ai;
+pub mod animation;
+pub mod autoplay;
+pub mod bitboard;
 pub mod board;
+pub mod board_builder;
+pub mod budget;
+pub mod calibration;
+pub mod chain;
+pub mod checksum;
+pub mod clock;
+pub mod compact;
+pub mod console;
+pub mod crc;
+pub mod device;
+pub mod diagnostics;
+pub mod entropy;
+pub mod error;
+pub mod frame_recorder;
 pub mod game_board;
+pub mod game_stats;
+pub mod grading;
+pub mod high_score;
+pub mod input;
+#[cfg(feature = "fuzzing")]
+pub mod invariants;
+pub mod light_show;
+pub mod merge_rule;
+pub mod migration;
+pub mod mirror;
+pub mod monotonic;
+pub mod numerals;
+pub mod patterns;
+pub mod protocol;
+pub mod provisioning;
+pub mod puzzles;
+pub mod render;
+pub mod rng_state;
+pub mod running_stats;
+pub mod save;
 pub mod score_board;
+pub mod score_clock;
+pub mod script;
+pub mod settings;
+pub mod share;
+pub mod soak;
+pub mod speedrun;
+pub mod status_panel;
+pub mod telemetry;
+pub mod tempo;
+pub mod theme;
+pub mod thermal;
+pub mod tile;
+pub mod tutorial;
+pub mod ui;
+pub mod versus;
 
 pub fn add_one(n: i32) -> i32 {
     n + 1
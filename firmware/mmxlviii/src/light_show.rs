@@ -0,0 +1,115 @@
+use crate::{error::Error, patterns::ShowSlice};
+
+/// Frames between the leader announcing its frame, so units that drift or
+/// join late are brought back in step.
+pub const SYNC_FRAMES: u32 = 120;
+
+/// A light show played across several boards side by side, each rendering
+/// its own slice.
+///
+/// Unit 0 leads: its frame is the show's frame, which it announces every
+/// `SYNC_FRAMES` frames. Every other unit keeps an offset from its own frame
+/// counter to the leader's, updated whenever an announcement arrives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LightShow {
+    unit: u8,
+    units: u8,
+    offset: u32,
+}
+
+impl LightShow {
+    /// Join a show as the unit at some position, from the left, in a row of boards.
+    pub fn new(unit: u8, units: u8) -> Result<LightShow, Error> {
+        if unit >= units {
+            return Err(Error::InvalidPanel);
+        }
+        Ok(LightShow {
+            unit,
+            units,
+            offset: 0,
+        })
+    }
+
+    /// Get the unit's position in the row of boards.
+    pub fn unit(&self) -> u8 {
+        self.unit
+    }
+
+    /// Get the number of boards in the row.
+    pub fn units(&self) -> u8 {
+        self.units
+    }
+
+    /// Returns true if this unit keeps time for the others.
+    pub fn is_leader(&self) -> bool {
+        self.unit == 0
+    }
+
+    /// Get the show's frame, given this unit's frame.
+    pub fn show_frame(&self, frame: u32) -> u32 {
+        frame.wrapping_add(self.offset)
+    }
+
+    /// Returns the show's frame if the leader should announce it this frame.
+    pub fn announcement(&self, frame: u32) -> Option<u32> {
+        let show_frame = self.show_frame(frame);
+        if self.is_leader() && show_frame % SYNC_FRAMES == 0 {
+            Some(show_frame)
+        } else {
+            None
+        }
+    }
+
+    /// Bring this unit in step with the leader's announced frame.
+    /// The leader ignores announcements, as it is the one keeping time.
+    pub fn sync(&mut self, leader_frame: u32, frame: u32) {
+        if !self.is_leader() {
+            self.offset = leader_frame.wrapping_sub(frame);
+        }
+    }
+
+    /// Get this unit's slice of the show at some frame of its own.
+    pub fn slice(&self, frame: u32) -> ShowSlice {
+        ShowSlice {
+            unit: self.unit,
+            frame: self.show_frame(frame),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        assert!(LightShow::new(3, 4).is_ok());
+        assert_eq!(LightShow::new(4, 4), Err(Error::InvalidPanel));
+        assert_eq!(LightShow::new(0, 0), Err(Error::InvalidPanel));
+    }
+
+    #[test]
+    fn test_sync() {
+        let leader = LightShow::new(0, 2).unwrap();
+        let mut follower = LightShow::new(1, 2).unwrap();
+        // The follower booted later, so its frame counter is behind
+        let announced = (0..1000).find_map(|frame| leader.announcement(frame));
+        assert_eq!(announced, Some(0));
+        follower.sync(SYNC_FRAMES, 7);
+        assert_eq!(follower.show_frame(7), leader.show_frame(SYNC_FRAMES));
+        assert_eq!(follower.slice(8).frame, SYNC_FRAMES + 1);
+        assert_eq!(follower.announcement(7 + SYNC_FRAMES), None);
+
+        // Wrapping frame counters stay in step
+        follower.sync(3, u32::MAX);
+        assert_eq!(follower.show_frame(0), 4);
+    }
+
+    #[test]
+    fn test_leader_keeps_time() {
+        let mut leader = LightShow::new(0, 2).unwrap();
+        leader.sync(500, 10);
+        assert_eq!(leader.show_frame(10), 10);
+        assert_eq!(leader.announcement(SYNC_FRAMES), Some(SYNC_FRAMES));
+    }
+}
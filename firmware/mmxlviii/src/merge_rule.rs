@@ -0,0 +1,158 @@
+use crate::game_board::Variant;
+
+/// Decides which tiles merge when one slides into another, and what they make.
+///
+/// Tiles are small numbers that stand for larger values, e.g. an exponent in
+/// the classic game, with 0 as an empty space. Values are only used for
+/// scoring and display, so a rule is free to number its tiles however suits it,
+/// as long as larger tiles stand for larger values.
+///
+/// `Variant` is a rule too, following whichever rule its game is played by.
+pub trait MergeRule: Clone + Default {
+    /// Get the tile made when `moving` slides into `target`, or `None` if they
+    /// don't merge. Neither is ever empty.
+    fn merge(&self, moving: u8, target: u8) -> Option<u8>;
+
+    /// Get the value a tile stands for, which is also what making it scores.
+    fn value(&self, tile: u8) -> u32;
+}
+
+/// The classic rule: two equal tiles merge into one of double the value.
+/// Each tile is the exponent of its value, e.g. 11 for 2048.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Doubling;
+
+impl MergeRule for Doubling {
+    fn merge(&self, moving: u8, target: u8) -> Option<u8> {
+        if moving == target {
+            Some(moving.saturating_add(1))
+        } else {
+            None
+        }
+    }
+
+    fn value(&self, tile: u8) -> u32 {
+        match tile {
+            0 => 0,
+            exponent => 1u32.checked_shl(exponent.into()).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+/// Tiles are Fibonacci numbers, and neighbours in the sequence merge into the
+/// next, e.g. 3 and 5 make 8. Two 1s make a 2, the only equal tiles that merge.
+/// Tile n is the nth number of the sequence 1, 2, 3, 5, 8, ...
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Fibonacci;
+
+impl MergeRule for Fibonacci {
+    fn merge(&self, moving: u8, target: u8) -> Option<u8> {
+        let larger = moving.max(target);
+        if larger - moving.min(target) == 1 || (moving, target) == (1, 1) {
+            Some(larger.saturating_add(1))
+        } else {
+            None
+        }
+    }
+
+    fn value(&self, tile: u8) -> u32 {
+        if tile == 0 {
+            return 0;
+        }
+        let (mut value, mut next) = (1u32, 2u32);
+        for _ in 1..tile {
+            let after = value.saturating_add(next);
+            value = next;
+            next = after;
+        }
+        value
+    }
+}
+
+/// The rule of Threes: a 1 and a 2 combine into a 3, and equal tiles of 3 or
+/// more merge into one of double the value. Tiles 1 and 2 are the values 1
+/// and 2, and each tile after is double the one before, i.e. 3, 6, 12, ...
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Threes;
+
+impl MergeRule for Threes {
+    fn merge(&self, moving: u8, target: u8) -> Option<u8> {
+        match (moving, target) {
+            (1, 2) | (2, 1) => Some(3),
+            _ if moving == target && moving >= 3 => Some(moving.saturating_add(1)),
+            _ => None,
+        }
+    }
+
+    fn value(&self, tile: u8) -> u32 {
+        match tile {
+            0..=2 => tile.into(),
+            _ => 1u32
+                .checked_shl(u32::from(tile - 3))
+                .and_then(|power| power.checked_mul(3))
+                .unwrap_or(u32::MAX),
+        }
+    }
+}
+
+impl MergeRule for Variant {
+    fn merge(&self, moving: u8, target: u8) -> Option<u8> {
+        match self {
+            Variant::Classic => Doubling.merge(moving, target),
+            Variant::Threes => Threes.merge(moving, target),
+        }
+    }
+
+    fn value(&self, tile: u8) -> u32 {
+        match self {
+            Variant::Classic => Doubling.value(tile),
+            Variant::Threes => Threes.value(tile),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doubling() {
+        assert_eq!(Doubling.merge(3, 3), Some(4));
+        assert_eq!(Doubling.merge(3, 4), None);
+        assert_eq!(Doubling.value(0), 0);
+        assert_eq!(Doubling.value(11), 2048);
+        assert_eq!(Doubling.value(32), u32::MAX);
+    }
+
+    #[test]
+    fn test_fibonacci() {
+        let values: [u32; 8] = core::array::from_fn(|tile| Fibonacci.value(tile as u8));
+        assert_eq!(values, [0, 1, 2, 3, 5, 8, 13, 21]);
+        assert_eq!(Fibonacci.merge(1, 1), Some(2));
+        assert_eq!(Fibonacci.merge(4, 5), Some(6));
+        assert_eq!(Fibonacci.merge(5, 4), Some(6));
+        assert_eq!(Fibonacci.merge(2, 2), None);
+        assert_eq!(Fibonacci.merge(2, 4), None);
+        assert_eq!(Fibonacci.value(u8::MAX), u32::MAX);
+    }
+
+    #[test]
+    fn test_threes() {
+        let values: [u32; 7] = core::array::from_fn(|tile| Threes.value(tile as u8));
+        assert_eq!(values, [0, 1, 2, 3, 6, 12, 24]);
+        assert_eq!(Threes.merge(1, 2), Some(3));
+        assert_eq!(Threes.merge(2, 1), Some(3));
+        assert_eq!(Threes.merge(1, 1), None);
+        assert_eq!(Threes.merge(2, 2), None);
+        assert_eq!(Threes.merge(3, 3), Some(4));
+        assert_eq!(Threes.merge(3, 4), None);
+        assert_eq!(Threes.value(u8::MAX), u32::MAX);
+    }
+
+    #[test]
+    fn test_variant() {
+        assert_eq!(Variant::Classic.merge(1, 1), Some(2));
+        assert_eq!(Variant::Threes.merge(1, 1), None);
+        assert_eq!(Variant::Threes.value(4), 6);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::crc::crc16;
+
+/// Number of one-time data migrations the current firmware knows about.
+/// Increase this when adding a migration, and handle the new step in the firmware.
+pub const CURRENT_VERSION: u16 = 3;
+
+/// Size of the migration marker serialized in bytes, one EEPROM page.
+pub const BYTES_SIZE: usize = 16;
+
+const NO_STEP: u16 = 0xFFFF;
+
+/// Records which migrations have been applied to the data in storage, and which one was
+/// in progress if the power was lost during an update.
+///
+/// Each step is staged by writing the marker with the step pending, then the migration
+/// is run, then the marker is written again with the step complete. A step that was
+/// interrupted is run again on the next boot, so migrations must be safe to repeat.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MigrationMarker {
+    version: u16,
+    pending: Option<u16>,
+}
+
+impl MigrationMarker {
+    /// Get the version of the data in storage.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Get the step that was started but not completed, if any.
+    pub fn pending(&self) -> Option<u16> {
+        self.pending
+    }
+
+    /// Get the next migration step needed to reach `target`, if any.
+    pub fn next_step(&self, target: u16) -> Option<u16> {
+        match self.pending {
+            Some(step) => Some(step),
+            None if self.version < target => Some(self.version + 1),
+            None => None,
+        }
+    }
+
+    /// Mark a step as started. Store the marker before running the step.
+    pub fn begin(&mut self, step: u16) {
+        self.pending = Some(step);
+    }
+
+    /// Mark a step as complete. Store the marker once the step has run.
+    pub fn complete(&mut self, step: u16) {
+        self.version = step;
+        self.pending = None;
+    }
+
+    pub fn to_bytes(&self) -> [u8; BYTES_SIZE] {
+        let mut bytes = [0; BYTES_SIZE];
+        bytes[0..2].copy_from_slice(&self.version.to_le_bytes());
+        bytes[2..4].copy_from_slice(&self.pending.unwrap_or(NO_STEP).to_le_bytes());
+        let crc = crc16(&bytes[..4]);
+        bytes[4..6].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    /// Parse a marker. Blank or corrupt storage is treated as data that has never
+    /// been migrated, so every migration is run.
+    pub fn from_bytes(bytes: &[u8]) -> MigrationMarker {
+        if bytes.len() < 6 || crc16(&bytes[..4]) != u16::from_le_bytes([bytes[4], bytes[5]]) {
+            return MigrationMarker::default();
+        }
+        let pending = match u16::from_le_bytes([bytes[2], bytes[3]]) {
+            NO_STEP => None,
+            step => Some(step),
+        };
+        MigrationMarker {
+            version: u16::from_le_bytes([bytes[0], bytes[1]]),
+            pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps() {
+        let mut marker = MigrationMarker::default();
+        assert_eq!(marker.next_step(2), Some(1));
+        marker.begin(1);
+        assert_eq!(marker.next_step(2), Some(1));
+        marker.complete(1);
+        assert_eq!(marker.next_step(2), Some(2));
+        marker.begin(2);
+        marker.complete(2);
+        assert_eq!(marker.next_step(2), None);
+        assert_eq!(marker.version(), 2);
+    }
+
+    #[test]
+    fn test_interrupted_step_is_repeated() {
+        let mut marker = MigrationMarker::default();
+        marker.begin(1);
+        let parsed = MigrationMarker::from_bytes(&marker.to_bytes());
+        assert_eq!(parsed.pending(), Some(1));
+        assert_eq!(parsed.next_step(1), Some(1));
+    }
+
+    #[test]
+    fn test_blank_storage() {
+        let marker = MigrationMarker::from_bytes(&[0xFF; BYTES_SIZE]);
+        assert_eq!(marker, MigrationMarker::default());
+    }
+
+    #[test]
+    fn test_serialisation() {
+        let mut marker = MigrationMarker::default();
+        marker.complete(3);
+        marker.begin(4);
+        assert_eq!(MigrationMarker::from_bytes(&marker.to_bytes()), marker);
+    }
+}
@@ -0,0 +1,166 @@
+use core::fmt::Write;
+
+use heapless::String;
+use smart_leds::RGB8;
+
+use crate::{
+    board::{Board, SIZE},
+    game_board::GameBoard,
+};
+
+/// Length of a frame encoded as a line of hex, including the newline.
+pub const LINE_LENGTH: usize = 2 * 3 * SIZE * SIZE + 1;
+
+/// Length of a broadcast encoded as a line, including the newline.
+pub const BROADCAST_LENGTH: usize = 1 + 8 + 1 + 2 * SIZE * SIZE + 1 + 8 + 1 + LINE_LENGTH;
+
+/// What spectators see: the rendered frame, the board behind it and when it was shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Broadcast {
+    /// Milliseconds since boot.
+    pub millis: u32,
+    pub tiles: [u8; SIZE * SIZE],
+    pub score: u32,
+    pub frame: Board,
+}
+
+impl Broadcast {
+    pub fn from_board(millis: u32, board: &GameBoard, frame: Board) -> Broadcast {
+        Broadcast {
+            millis,
+            tiles: board.tile_array(),
+            score: board.get_score(),
+            frame,
+        }
+    }
+
+    /// Encode as a line starting with `@`, followed by the timestamp, tiles, score
+    /// and frame in hex, separated by spaces.
+    /// The `@` keeps broadcasts apart from plain mirrored frames on the same channel.
+    pub fn encode(&self) -> String<BROADCAST_LENGTH> {
+        let mut line = String::new();
+        write!(line, "@{:08x} ", self.millis).unwrap();
+        for tile in self.tiles.iter() {
+            write!(line, "{:02x}", tile).unwrap();
+        }
+        write!(line, " {:08x} ", self.score).unwrap();
+        line.push_str(&encode_frame(&self.frame)).unwrap();
+        line
+    }
+
+    /// Decode a broadcast from a line created by `encode`.
+    pub fn decode(line: &str) -> Option<Broadcast> {
+        let mut fields = line.trim().strip_prefix('@')?.split(' ');
+        let millis = fields.next().filter(|field| field.len() == 8)?;
+        let tiles = fields
+            .next()
+            .filter(|field| field.len() == 2 * SIZE * SIZE)?;
+        let score = fields.next().filter(|field| field.len() == 8)?;
+        let frame = decode_frame(fields.next()?)?;
+        if fields.next().is_some() || !tiles.is_ascii() {
+            return None;
+        }
+
+        let mut decoded = [0; SIZE * SIZE];
+        for (index, tile) in decoded.iter_mut().enumerate() {
+            *tile = u8::from_str_radix(&tiles[2 * index..2 * index + 2], 16).ok()?;
+        }
+        Some(Broadcast {
+            millis: u32::from_str_radix(millis, 16).ok()?,
+            tiles: decoded,
+            score: u32::from_str_radix(score, 16).ok()?,
+            frame,
+        })
+    }
+}
+
+/// Encode a frame as a line of hex, with each LED's RGB in PCB order.
+pub fn encode_frame(frame: &Board) -> String<LINE_LENGTH> {
+    let mut line = String::new();
+    for led in frame.into_iter() {
+        write!(line, "{:02x}{:02x}{:02x}", led.r, led.g, led.b).unwrap();
+    }
+    line.push('\n').unwrap();
+    line
+}
+
+/// Decode a frame from a line of hex created by `encode_frame`.
+pub fn decode_frame(line: &str) -> Option<Board> {
+    let line = line.trim();
+    if line.len() != LINE_LENGTH - 1 || !line.is_ascii() {
+        return None;
+    }
+    let byte = |index: usize| u8::from_str_radix(&line[2 * index..2 * index + 2], 16).ok();
+
+    let mut leds = [RGB8::default(); SIZE * SIZE];
+    for (index, led) in leds.iter_mut().enumerate() {
+        *led = RGB8 {
+            r: byte(3 * index)?,
+            g: byte(3 * index + 1)?,
+            b: byte(3 * index + 2)?,
+        };
+    }
+    Some(Board::from_leds(leds))
+}
+
+#[cfg(test)]
+mod tests {
+    use smart_leds::colors::{BLUE, RED};
+
+    use super::*;
+    use crate::board::Coord;
+
+    #[test]
+    fn test_round_trip() {
+        let mut frame = Board::new();
+        frame.set_led(Coord::new(0, 0).unwrap(), RED);
+        frame.set_led(Coord::new(2, 3).unwrap(), BLUE);
+        let line = encode_frame(&frame);
+        assert!(line.starts_with("ff0000"));
+        assert!(line.ends_with('\n'));
+        assert_eq!(decode_frame(&line), Some(frame));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert_eq!(decode_frame(""), None);
+        assert_eq!(decode_frame("zz"), None);
+        let mut line = encode_frame(&Board::new());
+        line.truncate(LINE_LENGTH - 2);
+        line.push('g').unwrap();
+        assert_eq!(decode_frame(&line), None);
+    }
+
+    #[test]
+    fn test_broadcast_round_trip() {
+        let mut frame = Board::new();
+        frame.set_led(Coord::new(1, 1).unwrap(), RED);
+        let mut tiles = [0; SIZE * SIZE];
+        tiles[0] = 1;
+        tiles[15] = 11;
+        let broadcast = Broadcast {
+            millis: 123_456,
+            tiles,
+            score: 20_480,
+            frame,
+        };
+        let line = broadcast.encode();
+        assert_eq!(line.len(), BROADCAST_LENGTH);
+        assert!(line.starts_with("@0001e240 01"));
+        assert_eq!(Broadcast::decode(&line), Some(broadcast));
+    }
+
+    #[test]
+    fn test_broadcast_decode_invalid() {
+        let line = Broadcast {
+            millis: 0,
+            tiles: [0; SIZE * SIZE],
+            score: 0,
+            frame: Board::new(),
+        }
+        .encode();
+        assert_eq!(Broadcast::decode(&line[1..]), None);
+        assert_eq!(Broadcast::decode(&encode_frame(&Board::new())), None);
+        assert_eq!(Broadcast::decode(&line[..BROADCAST_LENGTH - 10]), None);
+    }
+}
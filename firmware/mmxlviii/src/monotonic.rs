@@ -0,0 +1,87 @@
+/// A point in time, in milliseconds since boot.
+///
+/// The count wraps after about 49 days, so instants are only compared by the
+/// time between them, never by which is larger.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Instant {
+    millis: u32,
+}
+
+impl Instant {
+    pub const fn from_millis(millis: u32) -> Instant {
+        Instant { millis }
+    }
+
+    pub fn millis(&self) -> u32 {
+        self.millis
+    }
+
+    /// Get the milliseconds from an earlier instant to this one, correct across a wrap.
+    pub fn since(&self, earlier: Instant) -> u32 {
+        self.millis.wrapping_sub(earlier.millis)
+    }
+}
+
+/// Milliseconds since boot, counted from a free-running 32-bit cycle counter.
+///
+/// The counter is extended in software, so it must be read at least once
+/// every time it wraps, about every 89 seconds at 48 MHz. Cycles left over
+/// from a partial millisecond carry over to the next reading.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MonotonicClock {
+    cycles_per_milli: u32,
+    /// The counter value that `millis` was last advanced to.
+    last_cycles: u32,
+    millis: u32,
+}
+
+impl MonotonicClock {
+    /// Create a clock starting at zero when the counter reads zero.
+    pub const fn new(cycles_per_milli: u32) -> MonotonicClock {
+        MonotonicClock {
+            cycles_per_milli,
+            last_cycles: 0,
+            millis: 0,
+        }
+    }
+
+    /// Get the time from the cycle counter's current value.
+    pub fn now(&mut self, cycles: u32) -> Instant {
+        let millis = cycles.wrapping_sub(self.last_cycles) / self.cycles_per_milli;
+        self.last_cycles = self
+            .last_cycles
+            .wrapping_add(millis * self.cycles_per_milli);
+        self.millis = self.millis.wrapping_add(millis);
+        Instant::from_millis(self.millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_since() {
+        let earlier = Instant::from_millis(u32::MAX - 9);
+        let later = Instant::from_millis(10);
+        assert_eq!(later.since(earlier), 20);
+        assert_eq!(later.since(later), 0);
+    }
+
+    #[test]
+    fn test_partial_millis_carry_over() {
+        let mut clock = MonotonicClock::new(1000);
+        assert_eq!(clock.now(0).millis(), 0);
+        assert_eq!(clock.now(1500).millis(), 1);
+        assert_eq!(clock.now(1999).millis(), 1);
+        assert_eq!(clock.now(2000).millis(), 2);
+    }
+
+    #[test]
+    fn test_counter_wrap() {
+        let mut clock = MonotonicClock::new(48_000);
+        let before = clock.now(u32::MAX - 47_999);
+        let after = clock.now(48_000);
+        assert_eq!(after.since(before), 2);
+    }
+}
@@ -0,0 +1,200 @@
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+use smart_leds::RGB8;
+
+use crate::{
+    board::{Board, Coord, SIZE},
+    render::RenderContext,
+};
+
+/// Number of frames each digit is shown for when digits take turns.
+pub const DIGIT_FRAMES: u32 = 40;
+/// Number of blank frames after each digit, so repeated digits can be told apart.
+const GAP_FRAMES: u32 = 10;
+
+/// Most digits a number can have in base 10, enough for any `u32`.
+pub const MAX_DIGITS: usize = 10;
+
+/// Decimal numerals three LEDs wide, top row first, leftmost LED as the highest bit.
+const NUMERALS: [[u8; SIZE]; 10] = [
+    [0b111, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b111],
+    [0b110, 0b001, 0b010, 0b111],
+    [0b111, 0b011, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001],
+    [0b111, 0b110, 0b001, 0b110],
+    [0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010],
+    [0b111, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001],
+];
+const NUMERAL_WIDTH: usize = 3;
+
+/// How numbers such as the score and the time are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NumeralStyle {
+    /// Each digit as a row or column of bits, all shown at once.
+    #[default]
+    Binary,
+    /// As `Binary`, but counting in base 16 so every bit of a row is used.
+    Hex,
+    /// Decimal digits drawn as numerals, one after another.
+    Decimal,
+    /// Decimal digits drawn as that many dots, one after another.
+    Dots,
+}
+
+impl NumeralStyle {
+    /// Parse a style from its console name.
+    pub fn parse(name: &str) -> Option<NumeralStyle> {
+        match name {
+            "binary" => Some(NumeralStyle::Binary),
+            "hex" => Some(NumeralStyle::Hex),
+            "decimal" => Some(NumeralStyle::Decimal),
+            "dots" => Some(NumeralStyle::Dots),
+            _ => None,
+        }
+    }
+
+    /// Get the base numbers are written in.
+    pub fn base(&self) -> u32 {
+        match self {
+            NumeralStyle::Hex => 16,
+            _ => 10,
+        }
+    }
+
+    /// Returns true if digits take turns filling the board, rather than all
+    /// being shown at once.
+    pub fn is_in_turn(&self) -> bool {
+        matches!(self, NumeralStyle::Decimal | NumeralStyle::Dots)
+    }
+}
+
+/// Split a number into its decimal digits, most significant first.
+pub fn decimal_digits(n: u32) -> Vec<u8, MAX_DIGITS> {
+    let mut digits = Vec::new();
+    let mut remaining = n;
+    loop {
+        digits.push((remaining % 10) as u8).unwrap();
+        remaining /= 10;
+        if remaining == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    digits
+}
+
+/// Show decimal digits one after another, most significant first, then pause
+/// for a digit's time before starting again.
+pub fn render_in_turn(
+    digits: &[u8],
+    style: NumeralStyle,
+    colour: RGB8,
+    context: &RenderContext,
+) -> Board {
+    let slot = DIGIT_FRAMES + GAP_FRAMES;
+    let position = context.frame % (slot * (digits.len() as u32 + 1));
+    let mut board = Board::new();
+    if let Some(&digit) = digits.get((position / slot) as usize) {
+        if position % slot < DIGIT_FRAMES {
+            draw_digit(&mut board, style, digit, colour);
+        }
+    }
+    board
+}
+
+/// Draw a decimal digit across the whole board.
+fn draw_digit(board: &mut Board, style: NumeralStyle, digit: u8, colour: RGB8) {
+    match style {
+        // Zero is the whole board dimly lit, so it isn't mistaken for a gap
+        NumeralStyle::Dots if digit == 0 => {
+            let dim = RGB8::new(colour.r / 4, colour.g / 4, colour.b / 4);
+            for index in 0..SIZE * SIZE {
+                board.set_led(Coord::new(index % SIZE, index / SIZE).unwrap(), dim);
+            }
+        }
+        // Dots fill rows from the top left, so they can be counted four at a time
+        NumeralStyle::Dots => {
+            for index in 0..usize::from(digit) {
+                let coord = Coord::new(index % SIZE, SIZE - 1 - index / SIZE).unwrap();
+                board.set_led(coord, colour);
+            }
+        }
+        _ => {
+            for (row, bits) in NUMERALS[usize::from(digit)].iter().enumerate() {
+                for x in 0..NUMERAL_WIDTH {
+                    if bits & (1 << (NUMERAL_WIDTH - 1 - x)) != 0 {
+                        board.set_led(Coord::new(x, SIZE - 1 - row).unwrap(), colour);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smart_leds::colors::{BLACK, WHITE};
+
+    use super::*;
+
+    fn lit(board: &Board) -> usize {
+        board.into_iter().filter(|&&led| led != BLACK).count()
+    }
+
+    fn at_frame(frame: u32) -> RenderContext {
+        RenderContext {
+            frame,
+            ..RenderContext::default()
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(NumeralStyle::parse("dots"), Some(NumeralStyle::Dots));
+        assert_eq!(NumeralStyle::parse("hex"), Some(NumeralStyle::Hex));
+        assert_eq!(NumeralStyle::parse("roman"), None);
+    }
+
+    #[test]
+    fn test_decimal_digits() {
+        assert_eq!(decimal_digits(0), [0]);
+        assert_eq!(decimal_digits(2048), [2, 0, 4, 8]);
+        assert_eq!(decimal_digits(u32::MAX).len(), MAX_DIGITS);
+    }
+
+    #[test]
+    fn test_dots() {
+        let digits = [7, 0];
+        let style = NumeralStyle::Dots;
+        assert_eq!(lit(&render_in_turn(&digits, style, WHITE, &at_frame(0))), 7);
+        // Gap between digits
+        let gap = at_frame(DIGIT_FRAMES);
+        assert_eq!(lit(&render_in_turn(&digits, style, WHITE, &gap)), 0);
+        let zero = at_frame(DIGIT_FRAMES + GAP_FRAMES);
+        assert_eq!(
+            lit(&render_in_turn(&digits, style, WHITE, &zero)),
+            SIZE * SIZE
+        );
+        // Pause, then start again
+        let pause = at_frame(2 * (DIGIT_FRAMES + GAP_FRAMES));
+        assert_eq!(lit(&render_in_turn(&digits, style, WHITE, &pause)), 0);
+        let again = at_frame(3 * (DIGIT_FRAMES + GAP_FRAMES));
+        assert_eq!(lit(&render_in_turn(&digits, style, WHITE, &again)), 7);
+    }
+
+    #[test]
+    fn test_numerals_are_distinct() {
+        for (index, numeral) in NUMERALS.iter().enumerate() {
+            assert!(NUMERALS[..index].iter().all(|other| other != numeral));
+        }
+        let board = render_in_turn(&[1], NumeralStyle::Decimal, WHITE, &at_frame(0));
+        // The bottom of a 1 is a full bar
+        for x in 0..NUMERAL_WIDTH {
+            assert_eq!(board.get_led(Coord::new(x, 0).unwrap()), WHITE);
+        }
+        assert_eq!(board.get_led(Coord::new(SIZE - 1, 0).unwrap()), BLACK);
+    }
+}
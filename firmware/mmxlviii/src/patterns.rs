@@ -0,0 +1,217 @@
+use smart_leds::{
+    colors::{BLACK, BLUE, GOLD, GRAY, LIME, RED, WHITE, YELLOW},
+    hsv::{hsv2rgb, Hsv},
+    RGB8,
+};
+
+use crate::{
+    board::{Board, Coord, Direction, IntoBoard, SIZE},
+    render::RenderContext,
+};
+
+/// A 4x4 pattern, written as one string per row with the top row first.
+///
+/// `.` is off, `X` is the pattern's main colour, and `r`, `g`, `b` and `y`
+/// are red, green, blue and yellow respectively.
+type Pattern = [&'static str; SIZE];
+
+/// Change in hue from one LED of a light show to the next.
+const SHOW_HUE_STEP: u32 = 16;
+/// Frames for a light show's wave to move on by one LED.
+const SHOW_STEP_FRAMES: u32 = 4;
+
+/// Icons shown in the menus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Icon {
+    Gear,
+    Trophy,
+    Palette,
+    Speaker,
+    Battery,
+    Stopwatch,
+    /// Shows the board's checksum, for comparing boards in tournaments.
+    Checksum,
+}
+
+impl Icon {
+    pub const ALL: [Icon; 7] = [
+        Icon::Gear,
+        Icon::Trophy,
+        Icon::Palette,
+        Icon::Speaker,
+        Icon::Battery,
+        Icon::Stopwatch,
+        Icon::Checksum,
+    ];
+
+    /// Get the pattern and main colour for the icon.
+    fn pattern(&self) -> (Pattern, RGB8) {
+        match self {
+            Icon::Gear => ([".X.X", "XXX.", ".XXX", "X.X."], GRAY),
+            Icon::Trophy => (["XXXX", ".XX.", ".XX.", "XXXX"], GOLD),
+            Icon::Palette => (["rrgg", "rrgg", "bbyy", "bbyy"], BLACK),
+            Icon::Speaker => ([".X..", "XX.X", "XX.X", ".X.."], WHITE),
+            Icon::Battery => ([".XX.", "XggX", "XggX", "XggX"], WHITE),
+            Icon::Stopwatch => ([".XX.", "X.rX", "X..X", ".XX."], WHITE),
+            Icon::Checksum => (["....", ".rg.", ".by.", "...."], BLACK),
+        }
+    }
+}
+
+/// An arrow pointing the way of a suggested move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HintArrow(pub Direction);
+
+impl HintArrow {
+    fn pattern(&self) -> Pattern {
+        match self.0 {
+            Direction::Up => [".XX.", "XXXX", ".XX.", ".XX."],
+            Direction::Down => [".XX.", ".XX.", "XXXX", ".XX."],
+            Direction::Left => [".X..", "XXXX", "XXXX", ".X.."],
+            Direction::Right => ["..X.", "XXXX", "XXXX", "..X."],
+        }
+    }
+}
+
+impl IntoBoard for HintArrow {
+    fn into_board(&self, _context: &RenderContext) -> Board {
+        draw_pattern(&self.pattern(), LIME)
+    }
+}
+
+/// Get the colour of a pixel in a pattern.
+/// Returns `None` if the pixel isn't a valid pattern character.
+fn pixel_colour(pixel: u8, main_colour: RGB8) -> Option<RGB8> {
+    match pixel {
+        b'.' => Some(BLACK),
+        b'X' => Some(main_colour),
+        b'r' => Some(RED),
+        b'g' => Some(LIME),
+        b'b' => Some(BLUE),
+        b'y' => Some(YELLOW),
+        _ => None,
+    }
+}
+
+/// Draw a pattern onto a board.
+fn draw_pattern(pattern: &Pattern, main_colour: RGB8) -> Board {
+    let mut board = Board::new();
+    for (row, line) in pattern.iter().enumerate() {
+        for (coord, &pixel) in Coord::row(SIZE - row - 1).zip(line.as_bytes()) {
+            board.set_led(coord, pixel_colour(pixel, main_colour).unwrap_or(BLACK));
+        }
+    }
+    board
+}
+
+impl IntoBoard for Icon {
+    fn into_board(&self, _context: &RenderContext) -> Board {
+        let (pattern, colour) = self.pattern();
+        draw_pattern(&pattern, colour)
+    }
+}
+
+/// One board's slice of a light show played across several boards side by side.
+///
+/// A rainbow wave moves rightwards across the whole row of boards, so as long
+/// as every board agrees on the frame, the wave crosses from each board onto
+/// the next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShowSlice {
+    /// Position of the board in the row, from the left.
+    pub unit: u8,
+    /// Frame of the show, shared by every board.
+    pub frame: u32,
+}
+
+impl IntoBoard for ShowSlice {
+    fn into_board(&self, _context: &RenderContext) -> Board {
+        let mut board = Board::new();
+        for index in 0..SIZE * SIZE {
+            let (x, y) = (index % SIZE, index / SIZE);
+            let show_x = u32::from(self.unit) * SIZE as u32 + x as u32;
+            // Tilted by a row's height, so the wave isn't just vertical stripes
+            let position = (show_x + y as u32) * SHOW_HUE_STEP;
+            let hue =
+                position.wrapping_sub(self.frame.wrapping_mul(SHOW_HUE_STEP / SHOW_STEP_FRAMES));
+            board.set_led(
+                Coord::new(x, y).unwrap(),
+                hsv2rgb(Hsv {
+                    hue: hue as u8,
+                    sat: 255,
+                    val: 255,
+                }),
+            );
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icons_within_bounds() {
+        for icon in Icon::ALL.iter() {
+            let (pattern, colour) = icon.pattern();
+            for line in pattern.iter() {
+                assert_eq!(line.len(), SIZE, "{:?} has a row of the wrong width", icon);
+                for &pixel in line.as_bytes() {
+                    assert!(pixel_colour(pixel, colour).is_some());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_icons_are_distinct() {
+        let context = RenderContext::default();
+        for (index, icon) in Icon::ALL.iter().enumerate() {
+            let board = icon.into_board(&context);
+            assert_ne!(board, Board::new());
+            for other in Icon::ALL[index + 1..].iter() {
+                assert_ne!(board, other.into_board(&context));
+            }
+        }
+    }
+
+    #[test]
+    fn test_hint_arrows_are_distinct() {
+        let context = RenderContext::default();
+        for (index, &direction) in Direction::ALL.iter().enumerate() {
+            let board = HintArrow(direction).into_board(&context);
+            for &other in Direction::ALL[index + 1..].iter() {
+                assert_ne!(board, HintArrow(other).into_board(&context));
+            }
+        }
+    }
+
+    #[test]
+    fn test_show_crosses_boards() {
+        let context = RenderContext::default();
+        for frame in [0, 100, u32::MAX - 1].iter().copied() {
+            let left = ShowSlice { unit: 0, frame }.into_board(&context);
+            let right = ShowSlice {
+                unit: 1,
+                frame: frame.wrapping_add(SHOW_STEP_FRAMES),
+            }
+            .into_board(&context);
+            // The right board's first column shows what the left board's last one did
+            for y in 0..SIZE {
+                assert_eq!(
+                    right.get_led(Coord::new(0, y).unwrap()),
+                    left.get_led(Coord::new(SIZE - 1, y).unwrap())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_draw_pattern() {
+        let board = draw_pattern(&["X...", "....", "....", "...r"], WHITE);
+        assert_eq!(board.get_led(Coord::new(0, 3).unwrap()), WHITE);
+        assert_eq!(board.get_led(Coord::new(3, 0).unwrap()), RED);
+        assert_eq!(board.get_led(Coord::new(0, 0).unwrap()), BLACK);
+    }
+}
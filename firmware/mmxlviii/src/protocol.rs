@@ -0,0 +1,305 @@
+use core::fmt::{self, Write};
+
+use heapless::String;
+use postcard::{from_bytes, to_slice};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error, game_board::GameBoard, merge_rule::Doubling, settings::NAME_LENGTH, tile::Tile,
+};
+
+/// Size of a score submission serialized in bytes, rounded up to the next 16 bytes.
+pub const SUBMISSION_BYTES_SIZE: usize = 32;
+
+/// Size of a game event serialized in bytes, rounded up to the next 16 bytes.
+pub const EVENT_BYTES_SIZE: usize = 32;
+
+/// The smallest tile worth announcing, 256.
+pub const FIRST_MILESTONE: u8 = 8;
+
+/// A finished game, as sent to a leaderboard.
+///
+/// Devices send submissions postcard encoded. Leaderboards report them as
+/// JSON, written by `write_json`, so both ends share one definition.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreSubmission {
+    /// The submitting device's ID.
+    pub device: u16,
+    /// The device's friendly name.
+    pub name: String<NAME_LENGTH>,
+    pub score: u32,
+    pub max_tile: u8,
+    pub moves: u32,
+}
+
+impl ScoreSubmission {
+    pub fn to_bytes(&self) -> Result<[u8; SUBMISSION_BYTES_SIZE], Error> {
+        let mut bytes = [0; SUBMISSION_BYTES_SIZE];
+        to_slice(self, &mut bytes).map_err(|_| Error::StorageFull)?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        from_bytes::<ScoreSubmission>(bytes).map_err(|_| Error::SaveCorrupt)
+    }
+
+    /// Write the submission as a JSON object. Tiles are written as their values rather than exponents.
+    pub fn write_json<W: Write>(&self, out: &mut W) -> fmt::Result {
+        write!(out, "{{\"device\":{},\"name\":", self.device)?;
+        write_json_string(out, &self.name)?;
+        write!(
+            out,
+            ",\"score\":{},\"max_tile\":{},\"moves\":{}}}",
+            self.score,
+            Tile::from(self.max_tile).value_in(&Doubling),
+            self.moves
+        )
+    }
+}
+
+/// Something that happened in a game, for home automation to react to.
+///
+/// Every transport sends these postcard encoded, or as the JSON written by
+/// `write_json`, so integrations see the same payload wherever it came from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameEvent {
+    GameStarted {
+        device: u16,
+    },
+    /// A new best tile of at least `FIRST_MILESTONE` was made.
+    MilestoneReached {
+        device: u16,
+        tile: u8,
+        score: u32,
+        moves: u32,
+    },
+    GameOver {
+        device: u16,
+        score: u32,
+        max_tile: u8,
+        moves: u32,
+        /// The final board, in board index order, as yielded by `GameBoard::tiles`.
+        tiles: [u8; 16],
+    },
+}
+
+impl GameEvent {
+    /// Get the milestone event for a move, if it raised the best tile past a milestone.
+    pub fn milestone(device: u16, before: &GameBoard, after: &GameBoard) -> Option<GameEvent> {
+        let tile = after.max_tile();
+        if tile >= Tile::from(FIRST_MILESTONE) && tile > before.max_tile() {
+            Some(GameEvent::MilestoneReached {
+                device,
+                tile: tile.into(),
+                score: after.get_score(),
+                moves: after.get_move_count(),
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn game_over(device: u16, board: &GameBoard) -> GameEvent {
+        GameEvent::GameOver {
+            device,
+            score: board.get_score(),
+            max_tile: board.max_tile().into(),
+            moves: board.get_move_count(),
+            tiles: board.tile_array(),
+        }
+    }
+
+    /// Get the event's name, as used for the `event` field in JSON.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameEvent::GameStarted { .. } => "game_started",
+            GameEvent::MilestoneReached { .. } => "milestone_reached",
+            GameEvent::GameOver { .. } => "game_over",
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<[u8; EVENT_BYTES_SIZE], Error> {
+        let mut bytes = [0; EVENT_BYTES_SIZE];
+        to_slice(self, &mut bytes).map_err(|_| Error::StorageFull)?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        from_bytes::<GameEvent>(bytes).map_err(|_| Error::SaveCorrupt)
+    }
+
+    /// Write the event as a JSON object. Tiles are written as their values rather than exponents.
+    pub fn write_json<W: Write>(&self, out: &mut W) -> fmt::Result {
+        write!(out, "{{\"event\":\"{}\"", self.name())?;
+        match self {
+            GameEvent::GameStarted { device } => write!(out, ",\"device\":{}", device)?,
+            GameEvent::MilestoneReached {
+                device,
+                tile,
+                score,
+                moves,
+            } => write!(
+                out,
+                ",\"device\":{},\"tile\":{},\"score\":{},\"moves\":{}",
+                device,
+                Tile::from(*tile).value_in(&Doubling),
+                score,
+                moves
+            )?,
+            GameEvent::GameOver {
+                device,
+                score,
+                max_tile,
+                moves,
+                tiles,
+            } => {
+                write!(
+                    out,
+                    ",\"device\":{},\"score\":{},\"max_tile\":{},\"moves\":{},\"board\":[",
+                    device,
+                    score,
+                    Tile::from(*max_tile).value_in(&Doubling),
+                    moves
+                )?;
+                for (index, &tile) in tiles.iter().enumerate() {
+                    if index > 0 {
+                        out.write_char(',')?;
+                    }
+                    write!(out, "{}", Tile::from(tile).value_in(&Doubling))?;
+                }
+                out.write_char(']')?;
+            }
+        }
+        out.write_char('}')
+    }
+}
+
+/// Write a string as a JSON string, escaping anything that needs it.
+pub fn write_json_string<W: Write>(out: &mut W, value: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for character in value.chars() {
+        match character {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            character if character.is_control() => write!(out, "\\u{:04x}", character as u32)?,
+            character => out.write_char(character)?,
+        }
+    }
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Direction;
+
+    fn submission(name: &str) -> ScoreSubmission {
+        ScoreSubmission {
+            device: 0x2048,
+            name: String::from(name),
+            score: 20_480,
+            max_tile: 11,
+            moves: 987,
+        }
+    }
+
+    #[test]
+    fn test_serialisation() {
+        let submission = submission("kitchen");
+        let bytes = submission.to_bytes().unwrap();
+        assert_eq!(ScoreSubmission::from_bytes(&bytes), Ok(submission));
+    }
+
+    #[test]
+    fn test_worst_case_size() {
+        let submission = ScoreSubmission {
+            device: u16::MAX,
+            name: String::from("0123456789abcdef"),
+            score: u32::MAX,
+            max_tile: u8::MAX,
+            moves: u32::MAX,
+        };
+        assert!(submission.to_bytes().is_ok());
+    }
+
+    #[test]
+    fn test_json() {
+        let mut json = String::<128>::new();
+        submission("kitchen").write_json(&mut json).unwrap();
+        assert_eq!(
+            json,
+            "{\"device\":8264,\"name\":\"kitchen\",\"score\":20480,\"max_tile\":2048,\"moves\":987}"
+        );
+    }
+
+    #[test]
+    fn test_event_serialisation() {
+        let board = GameBoard::with_tiles([11, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        for event in [
+            GameEvent::GameStarted { device: 7 },
+            GameEvent::game_over(7, &board),
+        ] {
+            let bytes = event.to_bytes().unwrap();
+            assert_eq!(GameEvent::from_bytes(&bytes), Ok(event));
+        }
+    }
+
+    #[test]
+    fn test_event_worst_case_size() {
+        let event = GameEvent::GameOver {
+            device: u16::MAX,
+            score: u32::MAX,
+            max_tile: u8::MAX,
+            moves: u32::MAX,
+            tiles: [u8::MAX; 16],
+        };
+        assert!(event.to_bytes().is_ok());
+    }
+
+    #[test]
+    fn test_milestone() {
+        let before = GameBoard::with_tiles([7, 7, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let mut after = before.clone();
+        after.make_move(Direction::Left);
+        assert_eq!(
+            GameEvent::milestone(1, &before, &after),
+            Some(GameEvent::MilestoneReached {
+                device: 1,
+                tile: 8,
+                score: 256,
+                moves: 1,
+            })
+        );
+        // Neither matching the best tile nor small tiles are milestones
+        assert_eq!(GameEvent::milestone(1, &after, &after), None);
+        assert_eq!(GameEvent::milestone(1, &GameBoard::empty(), &before), None);
+    }
+
+    #[test]
+    fn test_event_json() {
+        let mut json = String::<64>::new();
+        GameEvent::GameStarted { device: 7 }
+            .write_json(&mut json)
+            .unwrap();
+        assert_eq!(json, "{\"event\":\"game_started\",\"device\":7}");
+
+        let board = GameBoard::with_tiles([11, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let mut json = String::<160>::new();
+        GameEvent::game_over(7, &board)
+            .write_json(&mut json)
+            .unwrap();
+        assert_eq!(
+            json,
+            "{\"event\":\"game_over\",\"device\":7,\"score\":0,\"max_tile\":2048,\"moves\":0,\
+             \"board\":[2048,2,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}"
+        );
+    }
+
+    #[test]
+    fn test_json_escaping() {
+        let mut json = String::<32>::new();
+        write_json_string(&mut json, "a \"b\"\\\u{1}").unwrap();
+        assert_eq!(json, "\"a \\\"b\\\"\\\\\\u0001\"");
+    }
+}
@@ -0,0 +1,159 @@
+use postcard::{from_bytes, to_slice};
+use serde::{Deserialize, Serialize};
+
+use crate::{crc::crc16, error::Error, input::ButtonMap, settings::Settings, theme::Palette};
+
+/// Size of a provisioning blob in bytes, rounded up to the next 16 bytes.
+pub const BYTES_SIZE: usize = 128;
+
+/// Version of the provisioning blob format.
+pub const VERSION: u8 = 5;
+
+const MAGIC: [u8; 2] = [0x20, 0x48];
+/// Magic (2 bytes), version (1 byte) and payload length (2 bytes).
+const HEADER_SIZE: usize = 5;
+const CRC_SIZE: usize = 2;
+
+/// Defaults for a batch of boards, provisioned over the console or pre-flashed to the EEPROM.
+///
+/// The blob is laid out as a header, the postcard encoded payload, then a
+/// CRC-16 of the header and payload.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provisioning {
+    pub settings: Settings,
+    pub palette: Palette,
+    pub button_map: ButtonMap,
+}
+
+impl Provisioning {
+    pub fn to_bytes(&self) -> Result<[u8; BYTES_SIZE], Error> {
+        let mut bytes = [0; BYTES_SIZE];
+        let length = to_slice(self, &mut bytes[HEADER_SIZE..BYTES_SIZE - CRC_SIZE])
+            .map_err(|_| Error::StorageFull)?
+            .len();
+        bytes[..2].copy_from_slice(&MAGIC);
+        bytes[2] = VERSION;
+        bytes[3..HEADER_SIZE].copy_from_slice(&(length as u16).to_le_bytes());
+
+        let end = HEADER_SIZE + length;
+        let crc = crc16(&bytes[..end]);
+        bytes[end..end + CRC_SIZE].copy_from_slice(&crc.to_le_bytes());
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < HEADER_SIZE || bytes[..2] != MAGIC {
+            return Err(Error::SaveCorrupt);
+        }
+        if bytes[2] != VERSION {
+            return Err(Error::UnsupportedVersion);
+        }
+        let length = usize::from(u16::from_le_bytes([bytes[3], bytes[4]]));
+        let end = HEADER_SIZE + length;
+        let crc = bytes.get(end..end + CRC_SIZE).ok_or(Error::SaveCorrupt)?;
+        if crc16(&bytes[..end]).to_le_bytes() != crc {
+            return Err(Error::SaveCorrupt);
+        }
+        from_bytes::<Provisioning>(&bytes[HEADER_SIZE..end]).map_err(|_| Error::SaveCorrupt)
+    }
+}
+
+/// Collects a provisioning blob as it is received one byte at a time.
+pub struct Receiver {
+    bytes: [u8; BYTES_SIZE],
+    count: usize,
+}
+
+impl Receiver {
+    pub fn new() -> Receiver {
+        Receiver {
+            bytes: [0; BYTES_SIZE],
+            count: 0,
+        }
+    }
+
+    /// Add a received byte. Returns the complete blob once all bytes have arrived.
+    pub fn push(&mut self, byte: u8) -> Option<[u8; BYTES_SIZE]> {
+        self.bytes[self.count] = byte;
+        self.count += 1;
+        if self.count == BYTES_SIZE {
+            self.count = 0;
+            Some(self.bytes)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Receiver {
+    fn default() -> Receiver {
+        Receiver::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smart_leds::colors::{BLACK, RED};
+
+    use super::*;
+    use crate::{board::Direction, theme::PALETTE_SIZE};
+
+    fn custom_provisioning() -> Provisioning {
+        let mut settings = Settings::new();
+        settings.set_name("gift").unwrap();
+        let mut colours = [RED; PALETTE_SIZE];
+        colours[0] = BLACK;
+        Provisioning {
+            settings,
+            palette: Palette::new(colours),
+            button_map: ButtonMap::new(
+                Direction::Down,
+                Direction::Up,
+                Direction::Right,
+                Direction::Left,
+            ),
+        }
+    }
+
+    #[test]
+    fn test_serialisation() {
+        let provisioning = custom_provisioning();
+        let parsed = Provisioning::from_bytes(&provisioning.to_bytes().unwrap()).unwrap();
+        assert_eq!(provisioning, parsed);
+    }
+
+    #[test]
+    fn test_corruption() {
+        let mut bytes = custom_provisioning().to_bytes().unwrap();
+        bytes[10] ^= 0x01;
+        assert_eq!(Provisioning::from_bytes(&bytes), Err(Error::SaveCorrupt));
+    }
+
+    #[test]
+    fn test_version() {
+        let mut bytes = custom_provisioning().to_bytes().unwrap();
+        bytes[2] = VERSION + 1;
+        assert_eq!(
+            Provisioning::from_bytes(&bytes),
+            Err(Error::UnsupportedVersion)
+        );
+    }
+
+    #[test]
+    fn test_blank_eeprom() {
+        assert_eq!(
+            Provisioning::from_bytes(&[0xFF; BYTES_SIZE]),
+            Err(Error::SaveCorrupt)
+        );
+    }
+
+    #[test]
+    fn test_receiver() {
+        let bytes = custom_provisioning().to_bytes().unwrap();
+        let mut receiver = Receiver::new();
+        for &byte in bytes[..BYTES_SIZE - 1].iter() {
+            assert!(receiver.push(byte).is_none());
+        }
+        assert_eq!(receiver.push(bytes[BYTES_SIZE - 1]), Some(bytes));
+    }
+}
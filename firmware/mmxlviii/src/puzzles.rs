@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::SIZE, board_builder::GameBoardBuilder, error::Error, game_board::GameBoard, tile::Tile,
+};
+
+/// A starting position and the goal to reach from it.
+pub struct Puzzle {
+    /// Starting tiles, in board index order.
+    pub tiles: [Tile; SIZE * SIZE],
+    /// Tile that must be made to solve the puzzle.
+    pub target: Tile,
+    /// Maximum number of moves allowed.
+    pub moves: u8,
+}
+
+impl Puzzle {
+    /// Get a board set up at the start of the puzzle.
+    pub fn board(&self) -> GameBoard {
+        self.board_with(GameBoardBuilder::new())
+    }
+
+    /// Get a board set up at the start of the puzzle, with the rules and
+    /// statistics of a builder, e.g. those carried on from the game before.
+    pub fn board_with(&self, rules: GameBoardBuilder) -> GameBoard {
+        // Every puzzle's tiles are in range, as `test_puzzles_are_solvable` checks
+        rules.tiles(self.tiles).score(0).build().unwrap()
+    }
+
+    /// Returns true if the board has reached the puzzle's goal.
+    pub fn is_solved(&self, board: &GameBoard) -> bool {
+        board.max_tile() >= self.target
+    }
+}
+
+/// Convert a puzzle's tiles, written as exponents to keep the pack readable.
+const fn tiles(exponents: [u8; SIZE * SIZE]) -> [Tile; SIZE * SIZE] {
+    let mut tiles = [Tile::EMPTY; SIZE * SIZE];
+    let mut i = 0;
+    while i < tiles.len() {
+        tiles[i] = Tile::from_exponent(exponents[i]);
+        i += 1;
+    }
+    tiles
+}
+
+/// The puzzle pack, stored in flash.
+/// New puzzles go at the end, as progress is saved by index.
+pub const PUZZLES: [Puzzle; 7] = [
+    // Reach 8 in 2 moves
+    Puzzle {
+        tiles: tiles([1, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+        target: Tile::from_exponent(3),
+        moves: 2,
+    },
+    // Reach 32 in 6 moves
+    Puzzle {
+        tiles: tiles([3, 0, 0, 3, 2, 0, 2, 0, 0, 4, 0, 0, 0, 0, 0, 0]),
+        target: Tile::from_exponent(5),
+        moves: 6,
+    },
+    // Reach 128 in 4 moves
+    Puzzle {
+        tiles: tiles([6, 4, 1, 0, 3, 2, 1, 0, 0, 3, 0, 0, 0, 0, 5, 0]),
+        target: Tile::from_exponent(7),
+        moves: 4,
+    },
+    // Reach 256 in 7 moves
+    Puzzle {
+        tiles: tiles([7, 1, 0, 2, 5, 4, 2, 0, 6, 3, 0, 1, 0, 0, 0, 0]),
+        target: Tile::from_exponent(8),
+        moves: 7,
+    },
+    // Reach 2048 in 7 moves
+    Puzzle {
+        tiles: tiles([10, 8, 6, 5, 9, 7, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0]),
+        target: Tile::from_exponent(11),
+        moves: 7,
+    },
+    // Reach 512 in 10 moves
+    Puzzle {
+        tiles: tiles([5, 2, 1, 8, 8, 5, 4, 6, 7, 2, 0, 7, 4, 1, 0, 0]),
+        target: Tile::from_exponent(9),
+        moves: 10,
+    },
+    // Reach 1024 in 8 moves
+    Puzzle {
+        tiles: tiles([7, 0, 0, 8, 8, 4, 4, 9, 2, 0, 7, 4, 0, 3, 0, 1]),
+        target: Tile::from_exponent(10),
+        moves: 8,
+    },
+];
+
+/// Progress through a puzzle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PuzzleStatus {
+    InProgress,
+    Solved,
+    Failed,
+}
+
+/// A puzzle that is being played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PuzzleRun {
+    index: u8,
+    moves: u8,
+}
+
+impl PuzzleRun {
+    /// Start a puzzle from the pack, returning the run and its starting board.
+    /// Returns `None` if there is no puzzle with that index.
+    pub fn start(index: usize) -> Option<(PuzzleRun, GameBoard)> {
+        PuzzleRun::start_with(index, GameBoardBuilder::new())
+    }
+
+    /// Start a puzzle as `start`, with its board set up by `Puzzle::board_with`.
+    pub fn start_with(index: usize, rules: GameBoardBuilder) -> Option<(PuzzleRun, GameBoard)> {
+        let puzzle = PUZZLES.get(index)?;
+        let run = PuzzleRun {
+            index: index as u8,
+            moves: 0,
+        };
+        Some((run, puzzle.board_with(rules)))
+    }
+
+    /// Get the index of the puzzle being played.
+    pub fn index(&self) -> usize {
+        usize::from(self.index)
+    }
+
+    /// Get the puzzle being played.
+    pub fn puzzle(&self) -> &'static Puzzle {
+        &PUZZLES[self.index()]
+    }
+
+    /// Get the number of moves remaining.
+    pub fn moves_remaining(&self) -> u8 {
+        self.puzzle().moves.saturating_sub(self.moves)
+    }
+
+    /// Record a successful move and check whether the puzzle is finished.
+    pub fn record_move(&mut self, board: &GameBoard) -> PuzzleStatus {
+        self.moves = self.moves.saturating_add(1);
+        if self.puzzle().is_solved(board) {
+            PuzzleStatus::Solved
+        } else if self.moves_remaining() == 0 || board.is_game_over() {
+            PuzzleStatus::Failed
+        } else {
+            PuzzleStatus::InProgress
+        }
+    }
+}
+
+/// Which puzzles have been solved, persisted across power cycles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PuzzleProgress {
+    solved: u32,
+}
+
+/// Size of the puzzle progress serialized in bytes, rounded up to the next 16 bytes.
+pub const PROGRESS_BYTES_SIZE: usize = 16;
+
+impl PuzzleProgress {
+    pub fn new() -> PuzzleProgress {
+        PuzzleProgress::default()
+    }
+
+    /// Returns true if the puzzle has been solved.
+    pub fn is_solved(&self, index: usize) -> bool {
+        index < PUZZLES.len() && self.solved & (1 << index) != 0
+    }
+
+    /// Mark a puzzle as solved.
+    pub fn mark_solved(&mut self, index: usize) {
+        if index < PUZZLES.len() {
+            self.solved |= 1 << index;
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<[u8; PROGRESS_BYTES_SIZE], Error> {
+        let mut bytes = [0; PROGRESS_BYTES_SIZE];
+        postcard::to_slice(self, &mut bytes).map_err(|_| Error::StorageFull)?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        postcard::from_bytes::<PuzzleProgress>(bytes).map_err(|_| Error::SaveCorrupt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::Direction, game_board::UndoPolicy, game_stats::GameStats};
+
+    /// Search for a sequence of moves, ignoring spawns, that solves the puzzle.
+    fn solvable(board: &GameBoard, target: Tile, moves: u8) -> bool {
+        if board.max_tile() >= target {
+            return true;
+        }
+        moves > 0
+            && Direction::ALL.iter().any(|&direction| {
+                let mut next = board.clone();
+                next.make_move(direction).moved() && solvable(&next, target, moves - 1)
+            })
+    }
+
+    #[test]
+    fn test_puzzles_are_solvable() {
+        for (index, puzzle) in PUZZLES.iter().enumerate() {
+            let board = puzzle.board();
+            assert!(!puzzle.is_solved(&board), "puzzle {} starts solved", index);
+            assert!(
+                solvable(&board, puzzle.target, puzzle.moves),
+                "puzzle {} can't be solved",
+                index
+            );
+            assert!(
+                !solvable(&board, puzzle.target, puzzle.moves - 1),
+                "puzzle {} can be solved in fewer moves",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_solved() {
+        let (mut run, mut board) = PuzzleRun::start(0).unwrap();
+        assert!(board.make_move(Direction::Left).moved());
+        assert_eq!(run.record_move(&board), PuzzleStatus::InProgress);
+        assert_eq!(run.moves_remaining(), 1);
+        assert!(board.make_move(Direction::Up).moved());
+        assert_eq!(run.record_move(&board), PuzzleStatus::Solved);
+        assert!(run.puzzle().is_solved(&board));
+    }
+
+    #[test]
+    fn test_run_failed() {
+        let (mut run, mut board) = PuzzleRun::start(0).unwrap();
+        assert!(board.make_move(Direction::Left).moved());
+        assert_eq!(run.record_move(&board), PuzzleStatus::InProgress);
+        assert!(board.make_move(Direction::Right).moved());
+        assert_eq!(run.record_move(&board), PuzzleStatus::Failed);
+    }
+
+    #[test]
+    fn test_start_invalid() {
+        assert!(PuzzleRun::start(PUZZLES.len()).is_none());
+    }
+
+    #[test]
+    fn test_start_with() {
+        let mut stats = GameStats::new();
+        stats.record_move();
+        let rules = GameBoardBuilder::new()
+            .stats(stats)
+            .undo_policy(UndoPolicy::Limited(1));
+        let (_, board) = PuzzleRun::start_with(0, rules).unwrap();
+        assert_eq!(board, PUZZLES[0].board());
+        assert_eq!(*board.stats(), stats);
+        assert_eq!(board.undo_policy(), UndoPolicy::Limited(1));
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut progress = PuzzleProgress::new();
+        assert!(!progress.is_solved(2));
+        progress.mark_solved(2);
+        assert!(progress.is_solved(2));
+        assert!(!progress.is_solved(1));
+        progress.mark_solved(100);
+        assert!(!progress.is_solved(100));
+
+        let parsed = PuzzleProgress::from_bytes(&progress.to_bytes().unwrap()).unwrap();
+        assert_eq!(progress, parsed);
+    }
+}
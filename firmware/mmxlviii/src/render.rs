@@ -0,0 +1,316 @@
+use serde::{Deserialize, Serialize};
+use smart_leds::RGB8;
+
+use crate::{
+    board::{Board, Coord, Direction, SIZE},
+    monotonic::Instant,
+    numerals::NumeralStyle,
+    theme::Palette,
+};
+
+/// Number of frames a blinking highlight spends on, then off.
+pub const BLINK_PERIOD: u32 = 20;
+
+/// Which way up the board is mounted, as clockwise quarter turns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Orientation {
+    #[default]
+    Normal,
+    Clockwise90,
+    Clockwise180,
+    Clockwise270,
+}
+
+impl Orientation {
+    /// Get the number of clockwise quarter turns.
+    pub fn quarter_turns(&self) -> usize {
+        match self {
+            Orientation::Normal => 0,
+            Orientation::Clockwise90 => 1,
+            Orientation::Clockwise180 => 2,
+            Orientation::Clockwise270 => 3,
+        }
+    }
+
+    /// Parse an orientation from its console name, the clockwise turn in degrees.
+    pub fn parse(name: &str) -> Option<Orientation> {
+        match name {
+            "0" => Some(Orientation::Normal),
+            "90" => Some(Orientation::Clockwise90),
+            "180" => Some(Orientation::Clockwise180),
+            "270" => Some(Orientation::Clockwise270),
+            _ => None,
+        }
+    }
+
+    /// Work out which way up the board is from an accelerometer reading,
+    /// taken with the accelerometer's x axis towards the board's right edge
+    /// and its y axis towards the top edge.
+    /// Returns `None` if the board is lying flat, or too near a corner to tell.
+    pub fn from_gravity(x: i16, y: i16, z: i16) -> Option<Orientation> {
+        let (x, y, z) = (i32::from(x), i32::from(y), i32::from(z));
+        if z.abs() >= x.abs().max(y.abs()) {
+            return None;
+        }
+        // The axis pointing up reads positive, so whichever edge reads
+        // negative is facing down
+        if y.abs() > 2 * x.abs() {
+            Some(if y > 0 {
+                Orientation::Normal
+            } else {
+                Orientation::Clockwise180
+            })
+        } else if x.abs() > 2 * y.abs() {
+            // Standing on its left edge, the board has turned anticlockwise
+            Some(if x > 0 {
+                Orientation::Clockwise90
+            } else {
+                Orientation::Clockwise270
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Get the game direction for a joystick direction, as the joystick turns
+    /// with the board while the picture is turned back upright.
+    pub fn unrotate(&self, direction: Direction) -> Direction {
+        (0..self.quarter_turns()).fold(direction, |direction, _| direction.rotate_ccw())
+    }
+}
+
+/// Everything a renderer may need to know, besides what it is rendering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderContext {
+    /// Colours to use for tiles.
+    pub palette: Palette,
+    /// Global brightness, out of 255.
+    pub brightness: u8,
+    /// Number of frames rendered since boot, for animations.
+    pub frame: u32,
+    /// When the frame is rendered, for animations timed in milliseconds.
+    pub now: Instant,
+    /// Which way up the board is mounted.
+    pub orientation: Orientation,
+    /// How numbers such as the score are drawn.
+    pub numerals: NumeralStyle,
+}
+
+impl RenderContext {
+    /// Apply orientation and brightness to a rendered board, ready to be displayed.
+    ///
+    /// This runs for every frame, so each LED is turned and scaled in a single
+    /// pass, and scaling is skipped entirely at full brightness.
+    pub fn finish(&self, board: Board) -> Board {
+        let turns = self.orientation.quarter_turns();
+        if turns == 0 && self.brightness == u8::MAX {
+            return board;
+        }
+        let mut finished = Board::new();
+        for coord in Coord::all() {
+            let turned = (0..turns).fold(coord, |coord, _| coord.rotate_cw());
+            finished.set_led(turned, self.scale(board.get_led(coord)));
+        }
+        finished
+    }
+
+    /// Returns true while blinking highlights should be shown.
+    pub fn blink_on(&self) -> bool {
+        (self.frame / BLINK_PERIOD) % 2 == 0
+    }
+
+    /// Scale a colour by the global brightness.
+    fn scale(&self, colour: RGB8) -> RGB8 {
+        let scale = |value: u8| (u16::from(value) * (u16::from(self.brightness) + 1) / 256) as u8;
+        RGB8 {
+            r: scale(colour.r),
+            g: scale(colour.g),
+            b: scale(colour.b),
+        }
+    }
+}
+
+/// Overlay a blinking highlight on some pixels of a rendered board.
+pub fn highlight<I>(board: &mut Board, coords: I, colour: RGB8, context: &RenderContext)
+where
+    I: IntoIterator<Item = Coord>,
+{
+    if context.blink_on() {
+        for coord in coords {
+            board.set_led(coord, colour);
+        }
+    }
+}
+
+/// Fade between two rendered boards, where an `amount` of 0 is entirely `from`
+/// and 255 is entirely `to`.
+pub fn blend(from: &Board, to: &Board, amount: u8) -> Board {
+    let mut leds = [RGB8::default(); SIZE * SIZE];
+    for (led, (a, b)) in leds.iter_mut().zip(from.into_iter().zip(to.into_iter())) {
+        *led = blend_colour(*a, *b, amount);
+    }
+    Board::from_leds(leds)
+}
+
+/// Fade between two colours, as `blend` does for every LED.
+pub fn blend_colour(from: RGB8, to: RGB8, amount: u8) -> RGB8 {
+    let mix = |a: u8, b: u8| {
+        let amount = u16::from(amount);
+        ((u16::from(a) * (255 - amount) + u16::from(b) * amount) / 255) as u8
+    };
+    RGB8 {
+        r: mix(from.r, to.r),
+        g: mix(from.g, to.g),
+        b: mix(from.b, to.b),
+    }
+}
+
+impl Default for RenderContext {
+    fn default() -> RenderContext {
+        RenderContext {
+            palette: Palette::default(),
+            brightness: 255,
+            frame: 0,
+            now: Instant::default(),
+            orientation: Orientation::default(),
+            numerals: NumeralStyle::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smart_leds::colors::WHITE;
+
+    use super::*;
+    use crate::board::Coord;
+
+    #[test]
+    fn test_finish_brightness() {
+        let mut board = Board::new();
+        let coord = Coord::new(0, 0).unwrap();
+        board.set_led(coord, WHITE);
+
+        let context = RenderContext::default();
+        assert_eq!(context.finish(board), board);
+
+        let context = RenderContext {
+            brightness: 127,
+            ..RenderContext::default()
+        };
+        assert_eq!(
+            context.finish(board).get_led(coord),
+            RGB8::new(127, 127, 127)
+        );
+    }
+
+    #[test]
+    fn test_highlight() {
+        let coord = Coord::new(1, 2).unwrap();
+        let mut board = Board::new();
+        highlight(&mut board, [coord], WHITE, &RenderContext::default());
+        assert_eq!(board.get_led(coord), WHITE);
+
+        let context = RenderContext {
+            frame: BLINK_PERIOD,
+            ..RenderContext::default()
+        };
+        let mut board = Board::new();
+        highlight(&mut board, [coord], WHITE, &context);
+        assert_eq!(board, Board::new());
+    }
+
+    #[test]
+    fn test_blend() {
+        let coord = Coord::new(2, 1).unwrap();
+        let mut to = Board::new();
+        to.set_led(coord, WHITE);
+        let from = Board::new();
+
+        assert_eq!(blend(&from, &to, 0), from);
+        assert_eq!(blend(&from, &to, 255), to);
+        assert_eq!(
+            blend(&from, &to, 128).get_led(coord),
+            RGB8::new(128, 128, 128)
+        );
+    }
+
+    #[test]
+    fn test_from_gravity() {
+        assert_eq!(
+            Orientation::from_gravity(0, 1000, 0),
+            Some(Orientation::Normal)
+        );
+        assert_eq!(
+            Orientation::from_gravity(50, -1000, 100),
+            Some(Orientation::Clockwise180)
+        );
+        assert_eq!(
+            Orientation::from_gravity(1000, 0, 0),
+            Some(Orientation::Clockwise90)
+        );
+        assert_eq!(
+            Orientation::from_gravity(i16::MIN, 0, 0),
+            Some(Orientation::Clockwise270)
+        );
+        // Lying flat, or balanced on a corner
+        assert_eq!(Orientation::from_gravity(100, 100, 1000), None);
+        assert_eq!(Orientation::from_gravity(700, 700, 0), None);
+    }
+
+    #[test]
+    fn test_unrotate_matches_finish() {
+        // A tile drawn on the game's top edge must be pushed towards by the joystick
+        let mut board = Board::new();
+        board.set_led(Coord::new(1, SIZE - 1).unwrap(), WHITE);
+        for &orientation in [
+            Orientation::Normal,
+            Orientation::Clockwise90,
+            Orientation::Clockwise180,
+            Orientation::Clockwise270,
+        ]
+        .iter()
+        {
+            let context = RenderContext {
+                orientation,
+                ..RenderContext::default()
+            };
+            let shown = context.finish(board);
+            let joystick = [
+                (Direction::Up, Coord::new(1, SIZE - 1).unwrap()),
+                (Direction::Right, Coord::new(SIZE - 1, SIZE - 2).unwrap()),
+                (Direction::Down, Coord::new(SIZE - 2, 0).unwrap()),
+                (Direction::Left, Coord::new(0, 1).unwrap()),
+            ]
+            .iter()
+            .find(|&&(_, coord)| shown.get_led(coord) == WHITE)
+            .map(|&(direction, _)| direction)
+            .unwrap();
+            assert_eq!(orientation.unrotate(joystick), Direction::Up);
+        }
+    }
+
+    #[test]
+    fn test_finish_orientation() {
+        let mut board = Board::new();
+        board.set_led(Coord::new(0, 3).unwrap(), WHITE);
+
+        let context = RenderContext {
+            orientation: Orientation::Clockwise90,
+            ..RenderContext::default()
+        };
+        assert_eq!(
+            context.finish(board).get_led(Coord::new(3, 3).unwrap()),
+            WHITE
+        );
+
+        let context = RenderContext {
+            orientation: Orientation::Clockwise180,
+            ..RenderContext::default()
+        };
+        assert_eq!(
+            context.finish(board).get_led(Coord::new(3, 0).unwrap()),
+            WHITE
+        );
+    }
+}
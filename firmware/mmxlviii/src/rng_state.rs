@@ -0,0 +1,92 @@
+use crate::{crc::crc16, error::Error};
+
+/// Size of the generator state serialized in bytes, one EEPROM page.
+pub const BYTES_SIZE: usize = 16;
+
+/// Most numbers a generator can have drawn and still be restored, about two
+/// a move for 30,000 moves. Restoring draws them all again, so this bounds
+/// how long restoring takes, e.g. at boot. A longer game is reseeded instead.
+pub const MAX_DRAWS: u32 = 1 << 16;
+
+/// What to store in place of a state when there is none, as erased storage
+/// reads, so a state left over from another game can't be restored.
+pub const ERASED: [u8; BYTES_SIZE] = [0xFF; BYTES_SIZE];
+
+/// Where a board's random number generator is in its sequence: the seed it
+/// started from, and how many numbers have been drawn since.
+///
+/// Saved on its own, apart from the save, as the save has no room left. A
+/// resumed game restored to this state adds the same tiles it would have
+/// without a power cycle, so it still matches a replay from its seed. As the
+/// two are written separately, the state is tagged with the CRC of the save
+/// it was written with, and only restored alongside that save.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RngState {
+    pub seed: u64,
+    pub draws: u32,
+}
+
+impl RngState {
+    /// Serialize the state, tagged as belonging to the serialized `save`.
+    pub fn to_bytes(&self, save: &[u8]) -> [u8; BYTES_SIZE] {
+        let mut bytes = [0; BYTES_SIZE];
+        bytes[0..8].copy_from_slice(&self.seed.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.draws.to_le_bytes());
+        bytes[12..14].copy_from_slice(&crc16(save).to_le_bytes());
+        let crc = crc16(&bytes[..14]);
+        bytes[14..16].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    /// Parse a state written alongside the serialized `save`. Fails with
+    /// `SaveCorrupt` if the bytes fail their checksum, as erased storage
+    /// does, or were written with a different save.
+    pub fn from_bytes(bytes: &[u8], save: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < BYTES_SIZE
+            || crc16(&bytes[..14]) != u16::from_le_bytes([bytes[14], bytes[15]])
+            || crc16(save) != u16::from_le_bytes([bytes[12], bytes[13]])
+        {
+            return Err(Error::SaveCorrupt);
+        }
+        let mut seed = [0; 8];
+        seed.copy_from_slice(&bytes[0..8]);
+        let mut draws = [0; 4];
+        draws.copy_from_slice(&bytes[8..12]);
+        Ok(RngState {
+            seed: u64::from_le_bytes(seed),
+            draws: u32::from_le_bytes(draws),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes() {
+        let state = RngState {
+            seed: u64::MAX,
+            draws: MAX_DRAWS,
+        };
+        let save = [1, 2, 3];
+        let bytes = state.to_bytes(&save);
+        assert_eq!(RngState::from_bytes(&bytes, &save), Ok(state));
+
+        // A state is only restored with the save it was written with
+        assert_eq!(
+            RngState::from_bytes(&bytes, &[1, 2, 4]),
+            Err(Error::SaveCorrupt)
+        );
+        let mut corrupt = bytes;
+        corrupt[8] ^= 1;
+        assert_eq!(
+            RngState::from_bytes(&corrupt, &save),
+            Err(Error::SaveCorrupt)
+        );
+        assert_eq!(
+            RngState::from_bytes(&ERASED, &save),
+            Err(Error::SaveCorrupt)
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use postcard::{from_bytes, to_slice};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// Size of the running statistics serialized in bytes, rounded up to the next 16 bytes.
+pub const BYTES_SIZE: usize = 32;
+
+/// Bits of an average kept below the point.
+pub const FRACTION_BITS: u32 = 8;
+
+/// The mean of every sample recorded so far, kept without floating point.
+///
+/// Only the count and the mean are kept, as a fixed point number with
+/// `FRACTION_BITS` below the point, so it never overflows however many
+/// samples are recorded. Means too large to hold saturate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunningAverage {
+    count: u32,
+    mean: u32,
+}
+
+impl RunningAverage {
+    pub fn new() -> RunningAverage {
+        RunningAverage::default()
+    }
+
+    pub fn record(&mut self, sample: u32) {
+        self.count = self.count.saturating_add(1);
+        let target = i64::from(sample) << FRACTION_BITS;
+        let mean = i64::from(self.mean);
+        let mean = mean + (target - mean) / i64::from(self.count);
+        self.mean = mean.clamp(0, i64::from(u32::MAX)) as u32;
+    }
+
+    /// Get how many samples have been recorded.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Get the mean, rounded to the nearest whole number, or 0 if there are no samples.
+    pub fn mean(&self) -> u32 {
+        ((u64::from(self.mean) + (1 << (FRACTION_BITS - 1))) >> FRACTION_BITS) as u32
+    }
+
+    /// Get the mean with `FRACTION_BITS` below the point.
+    pub fn mean_fixed(&self) -> u32 {
+        self.mean
+    }
+}
+
+/// Averages over every finished game, shown on the stats screen.
+///
+/// Saved on their own, like `GameStats`, so they last across power cycles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunningStats {
+    /// Final score of each game.
+    pub score: RunningAverage,
+    /// Moves each game took until none were left.
+    pub moves: RunningAverage,
+}
+
+impl RunningStats {
+    pub fn new() -> RunningStats {
+        RunningStats::default()
+    }
+
+    /// Record a finished game.
+    pub fn record_game(&mut self, score: u32, moves: u32) {
+        self.score.record(score);
+        self.moves.record(moves);
+    }
+
+    pub fn to_bytes(&self) -> Result<[u8; BYTES_SIZE], Error> {
+        let mut bytes = [0; BYTES_SIZE];
+        to_slice(self, &mut bytes).map_err(|_| Error::StorageFull)?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        from_bytes::<RunningStats>(bytes).map_err(|_| Error::SaveCorrupt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average() {
+        let mut average = RunningAverage::new();
+        assert_eq!(average.mean(), 0);
+        for &sample in [1000, 2000, 4000].iter() {
+            average.record(sample);
+        }
+        assert_eq!(average.count(), 3);
+        assert_eq!(average.mean(), 2333);
+        // 2333.33, less any rounding along the way
+        let fixed = average.mean_fixed();
+        assert!((2333 << FRACTION_BITS..2334 << FRACTION_BITS).contains(&fixed));
+
+        // Large samples saturate rather than overflow
+        let mut average = RunningAverage::new();
+        average.record(u32::MAX);
+        assert_eq!(average.mean_fixed(), u32::MAX);
+        average.record(0);
+        assert!(average.mean() > 0);
+    }
+
+    #[test]
+    fn test_bytes() {
+        let mut stats = RunningStats::new();
+        stats.record_game(20_000, 900);
+        stats.record_game(u32::MAX, u32::MAX);
+        assert_eq!(
+            RunningStats::from_bytes(&stats.to_bytes().unwrap()),
+            Ok(stats)
+        );
+        assert_eq!(
+            RunningStats::from_bytes(&[0; BYTES_SIZE]),
+            Ok(RunningStats::new())
+        );
+    }
+}
@@ -0,0 +1,410 @@
+use postcard::{from_bytes, to_slice};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board_builder::GameBoardBuilder,
+    error::Error,
+    game_board::{
+        EndlessPolicy, GameBoard, History, ScorePolicy, SpawnRate, UndoPolicy, DEFAULT_FOUR_ODDS,
+    },
+    grading::MoveGrade,
+    tile::Tile,
+    ui::UiState,
+};
+
+/// Size of a save serialized in bytes, rounded up to the next 16 bytes.
+pub const BYTES_SIZE: usize = 64;
+
+/// Format of saves written by this firmware, increased whenever older
+/// firmware couldn't make sense of them. Saves from before the format was
+/// recorded read as 0.
+pub const FORMAT: u8 = 1;
+
+/// Everything needed to resume exactly where the user left off.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Save {
+    pub board: GameBoard,
+    pub ui: UiState,
+    pub grade: MoveGrade,
+}
+
+/// Serialized form of `Save`.
+///
+/// The board's undo history, move count, spawn odds, endless policy, the
+/// format, the best merge, the undo policy and count, whether the game is a
+/// daily challenge, the spawn rate and the score policy come last, so older
+/// saves load with them empty from their zero padding.
+#[derive(Deserialize)]
+struct SaveData {
+    board: GameBoard,
+    ui: UiState,
+    grade: MoveGrade,
+    history: History,
+    moves: u32,
+    /// `None` in saves from before the odds were configurable.
+    four_odds: Option<u8>,
+    endless: EndlessPolicy,
+    format: u8,
+    best_merge: Tile,
+    undo_policy: UndoPolicy,
+    undos: u8,
+    daily: bool,
+    spawn_rate: SpawnRate,
+    scoring: ScorePolicy,
+}
+
+/// Borrowed form of `SaveData`, which serializes identically.
+#[derive(Serialize)]
+struct SaveRef<'a> {
+    board: &'a GameBoard,
+    ui: &'a UiState,
+    grade: &'a MoveGrade,
+    history: &'a History,
+    moves: u32,
+    four_odds: Option<u8>,
+    endless: EndlessPolicy,
+    format: u8,
+    best_merge: Tile,
+    undo_policy: UndoPolicy,
+    undos: u8,
+    daily: bool,
+    spawn_rate: SpawnRate,
+    scoring: ScorePolicy,
+}
+
+impl<'a> SaveRef<'a> {
+    fn new(board: &'a GameBoard, ui: &'a UiState, grade: &'a MoveGrade) -> SaveRef<'a> {
+        SaveRef {
+            board,
+            ui,
+            grade,
+            history: board.history(),
+            moves: board.get_move_count(),
+            four_odds: Some(board.four_odds()),
+            endless: board.endless(),
+            format: FORMAT,
+            best_merge: board.best_merge(),
+            undo_policy: board.undo_policy(),
+            undos: board.get_undo_count(),
+            daily: ui.daily,
+            spawn_rate: board.spawn_rate(),
+            scoring: board.score_policy(),
+        }
+    }
+}
+
+impl SaveData {
+    /// Put the separately serialized parts back together. Fails with
+    /// `UnsupportedVersion` if the save was written by newer firmware, or
+    /// `SaveImplausible` if a tile is out of range.
+    fn into_save(self) -> Result<Save, Error> {
+        if self.format > FORMAT {
+            return Err(Error::UnsupportedVersion);
+        }
+        let board = GameBoardBuilder::from_board(&self.board)
+            .history(self.history)
+            .move_count(self.moves)
+            .best_merge(self.best_merge)
+            .four_odds(self.four_odds.unwrap_or(DEFAULT_FOUR_ODDS))
+            .endless(self.endless)
+            .undo_policy(self.undo_policy)
+            .undos(self.undos)
+            .spawn_rate(self.spawn_rate)
+            .score_policy(self.scoring)
+            .variant(self.ui.variant)
+            .build()
+            .map_err(|_| Error::SaveImplausible)?;
+        let ui = UiState {
+            daily: self.daily,
+            ..self.ui
+        };
+        Ok(Save::new(board, ui, self.grade))
+    }
+}
+
+impl Save {
+    pub fn new(board: GameBoard, ui: UiState, grade: MoveGrade) -> Save {
+        Save { board, ui, grade }
+    }
+
+    /// Serialize the game state as a save, without having to move it into a `Save`.
+    pub fn encode(
+        board: &GameBoard,
+        ui: &UiState,
+        grade: &MoveGrade,
+    ) -> Result<[u8; BYTES_SIZE], Error> {
+        let mut bytes = [0; BYTES_SIZE];
+        let save = SaveRef::new(board, ui, grade);
+        to_slice(&save, &mut bytes).map_err(|_| Error::StorageFull)?;
+        Ok(bytes)
+    }
+
+    pub fn to_bytes(&self) -> Result<[u8; BYTES_SIZE], Error> {
+        Save::encode(&self.board, &self.ui, &self.grade)
+    }
+
+    /// Check the save could have come from a real game, as corrupt data can
+    /// still decode, e.g. with tiles too large or a score the tiles can't
+    /// account for.
+    pub fn validate(&self) -> Result<(), Error> {
+        // Puzzles start from tiles that weren't scored
+        self.board.check_plausible(self.ui.puzzle.is_some())
+    }
+
+    /// Decode a save. Fails with `SaveCorrupt` if it can't be decoded, or
+    /// `UnsupportedVersion` if it was written by newer firmware. Whether it
+    /// could have been played is checked separately, by `validate`, though
+    /// tiles out of range already fail here with `SaveImplausible`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        from_bytes::<SaveData>(bytes)
+            .map_err(|_| Error::SaveCorrupt)?
+            .into_save()
+    }
+
+    /// Serialize the save as JSON, with the same fields as the bytes, for
+    /// reading and editing on a computer.
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> Result<std::string::String, Error> {
+        serde_json::to_string_pretty(&SaveRef::new(&self.board, &self.ui, &self.grade))
+            .map_err(|_| Error::StorageFull)
+    }
+
+    /// Decode a save from JSON, failing as `from_bytes` does.
+    #[cfg(feature = "std")]
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str::<SaveData>(json)
+            .map_err(|_| Error::SaveCorrupt)?
+            .into_save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::Direction,
+        board_builder::GameBoardBuilder,
+        game_board::{Variant, MAX_TILE},
+        puzzles::PuzzleRun,
+        rng_state::RngState,
+        ui::Screen,
+    };
+
+    #[test]
+    fn test_serialisation() {
+        let mut board = GameBoard::new_game();
+        board.make_move(Direction::Left);
+        board.make_move(Direction::Down);
+        let ui = UiState {
+            screen: Screen::Menu,
+            menu_item: 3,
+            paused: true,
+            ..UiState::default()
+        };
+        let mut grade = MoveGrade::new();
+        grade.record(&board, Direction::Up);
+        let save = Save::new(board, ui, grade);
+        let parsed_save = Save::from_bytes(&save.to_bytes().unwrap()).unwrap();
+        assert_eq!(save, parsed_save);
+    }
+
+    #[test]
+    fn test_worst_case_size() {
+        let mut tiles = [15; 16];
+        tiles[0] = 0;
+        let mut board = GameBoard::with_tiles(tiles);
+        board.make_move(Direction::Left);
+        board.make_move(Direction::Right);
+        // Far more moves than a game can last
+        board.set_move_count(1_000_000);
+        board.set_undo_policy(UndoPolicy::Penalty(100));
+        board.set_undo_count(u8::MAX);
+        board.set_spawn_rate(SpawnRate::Escalating);
+        board.set_score_policy(ScorePolicy::HighestTile);
+        let ui = UiState {
+            screen: Screen::Tutorial,
+            menu_item: u8::MAX,
+            puzzle: PuzzleRun::start(0).map(|(run, _)| run),
+            daily: true,
+            ..UiState::default()
+        };
+        let save = Save::new(board, ui, MoveGrade::new());
+        assert!(save.to_bytes().is_ok());
+    }
+
+    #[test]
+    fn test_history() {
+        let mut board = GameBoard::new_game();
+        board.make_move(Direction::Left);
+        board.make_move(Direction::Down);
+        let bytes = Save::encode(&board, &UiState::default(), &MoveGrade::new()).unwrap();
+
+        let mut parsed = Save::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.board.history().len(), 1);
+        assert_eq!(parsed.board.get_move_count(), board.get_move_count());
+        assert_eq!(parsed.board.best_merge(), board.best_merge());
+        assert!(parsed.board.undo());
+        assert!(board.undo());
+        assert_eq!(parsed.board, board);
+    }
+
+    #[test]
+    fn test_rng_state() {
+        let mut board = GameBoard::new_game_with_seed(2305);
+        let play = |board: &mut GameBoard| {
+            for &direction in Direction::ALL.iter().cycle().take(12) {
+                if board.make_move(direction).moved() {
+                    board.set_random();
+                }
+            }
+        };
+        play(&mut board);
+        let bytes = Save::encode(&board, &UiState::default(), &MoveGrade::new()).unwrap();
+        let state = board.rng_state().unwrap().to_bytes(&bytes);
+
+        // A resumed game adds the same tiles as the game it was saved from
+        let mut resumed = Save::from_bytes(&bytes).unwrap().board;
+        assert_eq!(resumed.rng_state(), None);
+        let restored = RngState::from_bytes(&state, &bytes).unwrap();
+        assert_eq!(resumed.restore_rng(restored), Ok(()));
+        play(&mut board);
+        play(&mut resumed);
+        assert_eq!(resumed, board);
+    }
+
+    #[test]
+    fn test_save_without_history() {
+        // Saves from before undo existed end in padding where the history now goes
+        let board = GameBoard::new_game();
+        let bytes = Save::encode(&board, &UiState::default(), &MoveGrade::new()).unwrap();
+        let parsed = Save::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.board, board);
+        assert!(parsed.board.history().is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        let save = |board: GameBoard, ui: UiState| Save::new(board, ui, MoveGrade::new());
+
+        let mut board = GameBoard::new_game_with_seed(3);
+        for &direction in Direction::ALL.iter().cycle().take(40) {
+            if board.make_move(direction).moved() {
+                board.set_random();
+            }
+        }
+        assert_eq!(save(board, UiState::default()).validate(), Ok(()));
+
+        let mut tiles = [0; 16];
+        tiles[0] = MAX_TILE + 1;
+        let too_large = GameBoard::with_tiles(tiles);
+        assert_eq!(
+            save(too_large, UiState::default()).validate(),
+            Err(Error::SaveImplausible)
+        );
+
+        // A 2048 takes at least 9 * 2048 points to make
+        tiles[0] = 11;
+        let unscored = GameBoard::with_tiles(tiles);
+        assert_eq!(
+            save(unscored.clone(), UiState::default()).validate(),
+            Err(Error::SaveImplausible)
+        );
+        let puzzle = UiState {
+            puzzle: PuzzleRun::start(0).map(|(run, _)| run),
+            ..UiState::default()
+        };
+        assert_eq!(save(unscored, puzzle).validate(), Ok(()));
+
+        // A lone 2 is worth nothing
+        let lone_two = GameBoard::with_tiles([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let mut bytes = Save::encode(&lone_two, &UiState::default(), &MoveGrade::new()).unwrap();
+        // The save starts with the board: tiles, the generator's placeholder, then the score
+        assert_eq!(bytes[17], 0);
+        bytes[17] = 4;
+        let overscored = Save::from_bytes(&bytes).unwrap();
+        assert_eq!(overscored.board.get_score(), 4);
+        assert_eq!(overscored.validate(), Err(Error::SaveImplausible));
+    }
+
+    #[test]
+    fn test_format() {
+        let board = GameBoard::new_game();
+        let mut bytes = Save::encode(&board, &UiState::default(), &MoveGrade::new()).unwrap();
+        // The format is the last byte written
+        let end = bytes.iter().rposition(|&byte| byte != 0).unwrap();
+        assert_eq!(bytes[end], FORMAT);
+
+        // Saves from before the format was recorded still load
+        bytes[end] = 0;
+        assert!(Save::from_bytes(&bytes).is_ok());
+
+        bytes[end] = FORMAT + 1;
+        assert_eq!(Save::from_bytes(&bytes), Err(Error::UnsupportedVersion));
+    }
+
+    #[test]
+    fn test_game_rules() {
+        let mut board = GameBoardBuilder::new()
+            .four_odds(0)
+            .endless(EndlessPolicy::Saturate)
+            .undo_policy(UndoPolicy::Limited(3))
+            .spawn_rate(SpawnRate::Double)
+            .score_policy(ScorePolicy::Merges)
+            .build()
+            .unwrap();
+        board.set_undo_count(2);
+        let bytes = Save::encode(&board, &UiState::default(), &MoveGrade::new()).unwrap();
+        let parsed = Save::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.board.four_odds(), 0);
+        assert_eq!(parsed.board.endless(), EndlessPolicy::Saturate);
+        assert_eq!(parsed.board.undo_policy(), UndoPolicy::Limited(3));
+        assert_eq!(parsed.board.get_undo_count(), 2);
+        assert_eq!(parsed.board.spawn_rate(), SpawnRate::Double);
+        assert_eq!(parsed.board.score_policy(), ScorePolicy::Merges);
+
+        // The variant is saved as part of the UI state
+        let board = GameBoard::new_variant_game(Variant::Threes, 0);
+        let ui = UiState {
+            variant: Variant::Threes,
+            ..UiState::default()
+        };
+        let bytes = Save::encode(&board, &ui, &MoveGrade::new()).unwrap();
+        let parsed = Save::from_bytes(&bytes).unwrap();
+        assert_eq!(*parsed.board.rule(), Variant::Threes);
+    }
+
+    #[test]
+    fn test_daily() {
+        let board = GameBoard::new_game();
+        let ui = UiState {
+            daily: true,
+            ..UiState::default()
+        };
+        let bytes = Save::encode(&board, &ui, &MoveGrade::new()).unwrap();
+        assert!(Save::from_bytes(&bytes).unwrap().ui.daily);
+
+        let bytes = Save::encode(&board, &UiState::default(), &MoveGrade::new()).unwrap();
+        assert!(!Save::from_bytes(&bytes).unwrap().ui.daily);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_json() {
+        let mut board = GameBoard::new_game_with_seed(7);
+        board.make_move(Direction::Left);
+        board.make_move(Direction::Down);
+        let moves = board.get_move_count();
+        let save = Save::new(board, UiState::default(), MoveGrade::new());
+        let json = save.to_json().unwrap();
+        let parsed = Save::from_json(&json).unwrap();
+        assert_eq!(parsed, Save::from_bytes(&save.to_bytes().unwrap()).unwrap());
+        assert_eq!(parsed.board.get_move_count(), moves);
+
+        // Fields can be edited by hand
+        let edited = json.replace("\"Single\"", "\"Double\"");
+        let parsed = Save::from_json(&edited).unwrap();
+        assert_eq!(parsed.board.spawn_rate(), SpawnRate::Double);
+
+        assert_eq!(Save::from_json("{}"), Err(Error::SaveCorrupt));
+    }
+}
@@ -1,34 +1,36 @@
 use smart_leds::{colors::GRAY, RGB8};
 
-use crate::board::{Board, Coord, IntoBoard, SIZE};
-use core::fmt::Debug;
+use crate::{
+    board::{Board, Coord, IntoBoard, SIZE},
+    numerals,
+    render::RenderContext,
+};
 
-const BASE: u32 = 10;
 const SCORE_COLOUR: RGB8 = GRAY;
 
-/// Compute base 10 exponent of an integer.
-fn compute_exponent(n: u32) -> u32 {
+/// Compute the exponent of an integer in some base.
+fn compute_exponent(n: u32, base: u32) -> u32 {
     let mut exponent = 0;
     let mut remaining = n;
-    while remaining >= BASE {
+    while remaining >= base {
         exponent += 1;
-        remaining /= BASE;
+        remaining /= base;
     }
     exponent
 }
 
-/// Compute 2 digit base 10 mantissa of an integer.
+/// Compute the 2 digit mantissa of an integer in some base.
 /// The most significant digit is returned first.
-fn compute_mantissa(n: u32) -> (u32, u32) {
+fn compute_mantissa(n: u32, base: u32) -> (u32, u32) {
     let mut remaining = n;
-    while remaining >= BASE * BASE {
-        remaining /= BASE;
+    while remaining >= base * base {
+        remaining /= base;
     }
-    if remaining < BASE {
-        remaining *= BASE;
+    if remaining < base {
+        remaining *= base;
     }
-    let d0 = remaining / BASE;
-    let d1 = remaining - BASE * d0;
+    let d0 = remaining / base;
+    let d1 = remaining - base * d0;
     (d0, d1)
 }
 
@@ -44,19 +46,35 @@ fn int_to_bin4(n: u32) -> [bool; SIZE] {
     result
 }
 
+/// Shows a score in the context's numeral style.
+///
+/// The bit styles show an exponent along the top row and a two digit
+/// mantissa along the bottom two.
+#[derive(Debug)]
 pub struct ScoreBoard {
     score: u32,
-    board: Board,
 }
 
 impl ScoreBoard {
     /// Create a board with a score
     pub fn from_score(score: u32) -> ScoreBoard {
-        let mut board = Board::new();
+        ScoreBoard { score }
+    }
+}
 
-        let exp_bits = int_to_bin4(compute_exponent(score));
+impl IntoBoard for ScoreBoard {
+    fn into_board(&self, context: &RenderContext) -> Board {
+        let style = context.numerals;
+        if style.is_in_turn() {
+            let digits = numerals::decimal_digits(self.score);
+            return numerals::render_in_turn(&digits, style, SCORE_COLOUR, context);
+        }
 
-        let (d0, d1) = compute_mantissa(score);
+        let mut board = Board::new();
+        let base = style.base();
+        let exp_bits = int_to_bin4(compute_exponent(self.score, base));
+
+        let (d0, d1) = compute_mantissa(self.score, base);
         let d0_bits = int_to_bin4(d0);
         let d1_bits = int_to_bin4(d1);
 
@@ -72,21 +90,7 @@ impl ScoreBoard {
             }
         }
 
-        ScoreBoard { score, board }
-    }
-}
-
-impl Debug for ScoreBoard {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("ScoreBoard")
-            .field("score", &self.score)
-            .finish()
-    }
-}
-
-impl IntoBoard for ScoreBoard {
-    fn into_board(&self) -> Board {
-        self.board
+        board
     }
 }
 
@@ -95,27 +99,31 @@ mod tests {
     use smart_leds::colors::BLACK;
 
     use super::*;
+    use crate::numerals::NumeralStyle;
 
     #[test]
     fn test_compute_exponent() {
-        assert_eq!(compute_exponent(0), 0);
-        assert_eq!(compute_exponent(1), 0);
-        assert_eq!(compute_exponent(9), 0);
-        assert_eq!(compute_exponent(10), 1);
-        assert_eq!(compute_exponent(50_097), 4);
-        assert_eq!(compute_exponent(999_999_999), 8);
-        assert_eq!(compute_exponent(1_000_000_000), 9);
+        assert_eq!(compute_exponent(0, 10), 0);
+        assert_eq!(compute_exponent(1, 10), 0);
+        assert_eq!(compute_exponent(9, 10), 0);
+        assert_eq!(compute_exponent(10, 10), 1);
+        assert_eq!(compute_exponent(50_097, 10), 4);
+        assert_eq!(compute_exponent(999_999_999, 10), 8);
+        assert_eq!(compute_exponent(1_000_000_000, 10), 9);
+        assert_eq!(compute_exponent(0xFF, 16), 1);
+        assert_eq!(compute_exponent(u32::MAX, 16), 7);
     }
 
     #[test]
     fn test_compute_mantissa() {
-        assert_eq!(compute_mantissa(0), (0, 0));
-        assert_eq!(compute_mantissa(1), (1, 0));
-        assert_eq!(compute_mantissa(10), (1, 0));
-        assert_eq!(compute_mantissa(11), (1, 1));
-        assert_eq!(compute_mantissa(473), (4, 7));
-        assert_eq!(compute_mantissa(999_999_999), (9, 9));
-        assert_eq!(compute_mantissa(1_010_000_000), (1, 0));
+        assert_eq!(compute_mantissa(0, 10), (0, 0));
+        assert_eq!(compute_mantissa(1, 10), (1, 0));
+        assert_eq!(compute_mantissa(10, 10), (1, 0));
+        assert_eq!(compute_mantissa(11, 10), (1, 1));
+        assert_eq!(compute_mantissa(473, 10), (4, 7));
+        assert_eq!(compute_mantissa(999_999_999, 10), (9, 9));
+        assert_eq!(compute_mantissa(1_010_000_000, 10), (1, 0));
+        assert_eq!(compute_mantissa(0x1D3, 16), (1, 13));
     }
 
     #[test]
@@ -129,7 +137,28 @@ mod tests {
 
     #[test]
     fn test_from_score() {
-        let scoreboard = ScoreBoard::from_score(0);
-        assert!(scoreboard.board.into_iter().all(|&led| led == BLACK));
+        let board = ScoreBoard::from_score(0).into_board(&RenderContext::default());
+        assert!(board.into_iter().all(|&led| led == BLACK));
+    }
+
+    #[test]
+    fn test_numeral_styles() {
+        let context = RenderContext {
+            numerals: NumeralStyle::Hex,
+            ..RenderContext::default()
+        };
+        // 0x800 has exponent 2 and mantissa 8, 0
+        let board = ScoreBoard::from_score(2048).into_board(&context);
+        assert_ne!(board.get_led(Coord::new(2, 0).unwrap()), BLACK);
+        assert_ne!(board.get_led(Coord::new(0, SIZE - 1).unwrap()), BLACK);
+        assert!(Coord::row(SIZE - 2).all(|coord| board.get_led(coord) == BLACK));
+
+        let context = RenderContext {
+            numerals: NumeralStyle::Dots,
+            ..RenderContext::default()
+        };
+        // The first digit, 2, is shown first
+        let board = ScoreBoard::from_score(2048).into_board(&context);
+        assert_eq!(board.into_iter().filter(|&&led| led != BLACK).count(), 2);
     }
 }
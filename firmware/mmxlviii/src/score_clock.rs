@@ -0,0 +1,112 @@
+use crate::{
+    board::{Board, Coord, IntoBoard},
+    clock::TimeOfDay,
+    render::{blend, RenderContext},
+    score_board::ScoreBoard,
+    tile::Tile,
+};
+
+/// Number of frames each face is shown for.
+pub const FACE_FRAMES: u32 = 300;
+/// Number of frames spent fading from one face into the next, at the end of each face.
+pub const TRANSITION_FRAMES: u32 = 30;
+
+/// Something the idle display can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Face {
+    Clock,
+    Score,
+    BestTile,
+}
+
+/// An idle display that alternates between the time and the saved game's progress.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScoreClock {
+    /// The time, if it has been set.
+    pub time: Option<TimeOfDay>,
+    pub score: u32,
+    pub max_tile: Tile,
+}
+
+impl ScoreClock {
+    /// Get the faces to cycle through. The clock is skipped if the time is unknown.
+    fn faces(&self) -> &'static [Face] {
+        match self.time {
+            Some(_) => &[Face::Clock, Face::Score, Face::BestTile],
+            None => &[Face::Score, Face::BestTile],
+        }
+    }
+
+    fn render_face(&self, face: Face, context: &RenderContext) -> Board {
+        match face {
+            Face::Clock => self.time.unwrap_or_default().into_board(context),
+            Face::Score => ScoreBoard::from_score(self.score).into_board(context),
+            Face::BestTile => {
+                let mut board = Board::new();
+                for coord in Coord::all() {
+                    board.set_led(coord, context.palette.tile_colour(self.max_tile));
+                }
+                board
+            }
+        }
+    }
+}
+
+impl IntoBoard for ScoreClock {
+    fn into_board(&self, context: &RenderContext) -> Board {
+        let faces = self.faces();
+        let cycle = context.frame % (FACE_FRAMES * faces.len() as u32);
+        let index = (cycle / FACE_FRAMES) as usize;
+        let elapsed = cycle % FACE_FRAMES;
+
+        let current = self.render_face(faces[index], context);
+        let fade_start = FACE_FRAMES - TRANSITION_FRAMES;
+        if elapsed < fade_start {
+            return current;
+        }
+        let next = self.render_face(faces[(index + 1) % faces.len()], context);
+        let amount = (elapsed - fade_start) * 255 / TRANSITION_FRAMES;
+        blend(&current, &next, amount as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(clock: &ScoreClock, frame: u32) -> Board {
+        clock.into_board(&RenderContext {
+            frame,
+            ..RenderContext::default()
+        })
+    }
+
+    #[test]
+    fn test_alternates() {
+        let context = RenderContext::default();
+        let time = TimeOfDay::new(12, 34).unwrap();
+        let clock = ScoreClock {
+            time: Some(time),
+            score: 1234,
+            max_tile: Tile::from(7),
+        };
+        assert_eq!(render(&clock, 0), time.into_board(&context));
+        assert_eq!(
+            render(&clock, FACE_FRAMES),
+            ScoreBoard::from_score(1234).into_board(&context)
+        );
+        assert_eq!(render(&clock, FACE_FRAMES * 3), render(&clock, 0));
+    }
+
+    #[test]
+    fn test_transition() {
+        let clock = ScoreClock {
+            time: None,
+            score: 0,
+            max_tile: Tile::from(11),
+        };
+        let halfway = render(&clock, FACE_FRAMES - TRANSITION_FRAMES / 2);
+        assert_ne!(halfway, render(&clock, 0));
+        assert_ne!(halfway, render(&clock, FACE_FRAMES));
+    }
+}
@@ -0,0 +1,352 @@
+use heapless::Vec;
+
+use crate::{
+    board::{Board, Direction, IntoBoard},
+    error::Error,
+    game_board::GameBoard,
+    patterns::Icon,
+    render::RenderContext,
+    score_board::ScoreBoard,
+};
+
+/// Maximum length of a script in bytes.
+pub const SCRIPT_LENGTH: usize = 128;
+
+/// Stop running the script.
+pub const OP_END: u8 = 0x00;
+/// Start a new game, followed by its seed as 4 little endian bytes.
+pub const OP_NEW_GAME: u8 = 0x01;
+/// Make a move and show the game, followed by its index in `Direction::ALL`.
+pub const OP_MOVE: u8 = 0x02;
+/// Keep showing the current scene, followed by a number of frames as 2 little endian bytes.
+pub const OP_WAIT: u8 = 0x03;
+/// Show an icon, followed by its index in `Icon::ALL`.
+pub const OP_ICON: u8 = 0x04;
+/// Show a number, followed by it as 4 little endian bytes.
+pub const OP_NUMBER: u8 = 0x05;
+/// Show the game.
+pub const OP_BOARD: u8 = 0x06;
+/// Go back to the start of the script.
+pub const OP_LOOP: u8 = 0x07;
+
+/// A single step of a script.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    End,
+    NewGame(u32),
+    Move(Direction),
+    Wait(u16),
+    Icon(Icon),
+    Number(u32),
+    Board,
+    Loop,
+}
+
+impl Instruction {
+    /// Decode the instruction at the start of some bytes.
+    /// Returns the instruction and how many bytes it took up.
+    pub fn decode(bytes: &[u8]) -> Result<(Instruction, usize), Error> {
+        let (&op, operand) = bytes.split_first().ok_or(Error::InvalidScript)?;
+        let operand_length = match op {
+            OP_NEW_GAME | OP_NUMBER => 4,
+            OP_WAIT => 2,
+            OP_MOVE | OP_ICON => 1,
+            _ => 0,
+        };
+        let operand = operand.get(..operand_length).ok_or(Error::InvalidScript)?;
+        let word = || u32::from_le_bytes([operand[0], operand[1], operand[2], operand[3]]);
+        let instruction = match op {
+            OP_END => Instruction::End,
+            OP_NEW_GAME => Instruction::NewGame(word()),
+            OP_MOVE => Instruction::Move(
+                *Direction::ALL
+                    .get(usize::from(operand[0]))
+                    .ok_or(Error::InvalidScript)?,
+            ),
+            OP_WAIT => Instruction::Wait(u16::from_le_bytes([operand[0], operand[1]])),
+            OP_ICON => Instruction::Icon(
+                *Icon::ALL
+                    .get(usize::from(operand[0]))
+                    .ok_or(Error::InvalidScript)?,
+            ),
+            OP_NUMBER => Instruction::Number(word()),
+            OP_BOARD => Instruction::Board,
+            OP_LOOP => Instruction::Loop,
+            _ => return Err(Error::InvalidScript),
+        };
+        Ok((instruction, 1 + operand_length))
+    }
+
+    /// Encode the instruction onto the end of some bytes.
+    pub fn encode<const N: usize>(&self, bytes: &mut Vec<u8, N>) -> Result<(), Error> {
+        let mut encoded = [0; 5];
+        let (op, operand): (u8, &[u8]) = match *self {
+            Instruction::End => (OP_END, &[]),
+            Instruction::NewGame(seed) => (OP_NEW_GAME, &seed.to_le_bytes()),
+            Instruction::Move(direction) => {
+                let index = Direction::ALL.iter().position(|&d| d == direction);
+                (OP_MOVE, &[index.unwrap() as u8])
+            }
+            Instruction::Wait(frames) => (OP_WAIT, &frames.to_le_bytes()),
+            Instruction::Icon(icon) => {
+                let index = Icon::ALL.iter().position(|&i| i == icon);
+                (OP_ICON, &[index.unwrap() as u8])
+            }
+            Instruction::Number(number) => (OP_NUMBER, &number.to_le_bytes()),
+            Instruction::Board => (OP_BOARD, &[]),
+            Instruction::Loop => (OP_LOOP, &[]),
+        };
+        encoded[0] = op;
+        encoded[1..=operand.len()].copy_from_slice(operand);
+        bytes
+            .extend_from_slice(&encoded[..=operand.len()])
+            .map_err(|_| Error::StorageFull)
+    }
+}
+
+/// A sequence of instructions for an exhibition demo, checked to decode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Script {
+    bytes: Vec<u8, SCRIPT_LENGTH>,
+}
+
+impl Script {
+    pub fn new(bytes: &[u8]) -> Result<Script, Error> {
+        let mut position = 0;
+        while position < bytes.len() {
+            position += Instruction::decode(&bytes[position..])?.1;
+        }
+        Ok(Script {
+            bytes: Vec::from_slice(bytes).map_err(|_| Error::StorageFull)?,
+        })
+    }
+
+    /// Encode a script, such as one built into the firmware.
+    pub fn from_instructions(instructions: &[Instruction]) -> Result<Script, Error> {
+        let mut bytes = Vec::new();
+        for instruction in instructions {
+            instruction.encode(&mut bytes)?;
+        }
+        Ok(Script { bytes })
+    }
+}
+
+/// Accumulates a script of a known length received from the host.
+pub struct ScriptReceiver {
+    bytes: Vec<u8, SCRIPT_LENGTH>,
+    length: usize,
+}
+
+impl ScriptReceiver {
+    /// Returns `None` if the script would be too long to store.
+    pub fn new(length: usize) -> Option<ScriptReceiver> {
+        if length > SCRIPT_LENGTH {
+            return None;
+        }
+        Some(ScriptReceiver {
+            bytes: Vec::new(),
+            length,
+        })
+    }
+
+    /// Add a received byte. Returns the script once all bytes have arrived.
+    pub fn push(&mut self, byte: u8) -> Option<Result<Script, Error>> {
+        // Can't overflow, as the length was checked when the receiver was made
+        let _ = self.bytes.push(byte);
+        if self.bytes.len() == self.length {
+            Some(Script::new(&self.bytes))
+        } else {
+            None
+        }
+    }
+}
+
+/// What an exhibition is currently showing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scene {
+    Board,
+    Icon(Icon),
+    Number(u32),
+}
+
+/// Runs a script, playing its own game so the player's game is left alone.
+///
+/// Each instruction takes one frame, so a script that loops without waiting
+/// still lets the rest of the firmware run.
+#[derive(Clone)]
+pub struct Exhibition {
+    script: Script,
+    position: usize,
+    wait_frames: u16,
+    scene: Scene,
+    board: GameBoard,
+}
+
+impl Exhibition {
+    pub fn new(script: Script) -> Exhibition {
+        Exhibition {
+            script,
+            position: 0,
+            wait_frames: 0,
+            scene: Scene::Board,
+            board: GameBoard::new_game_with_seed(0),
+        }
+    }
+
+    /// Advance by a frame. Returns false once the script has ended.
+    pub fn tick(&mut self) -> bool {
+        if self.wait_frames > 0 {
+            self.wait_frames -= 1;
+            return true;
+        }
+        let instruction = match Instruction::decode(&self.script.bytes[self.position..]) {
+            Ok((instruction, length)) => {
+                self.position += length;
+                instruction
+            }
+            // Running off the end is the same as ending
+            Err(_) => Instruction::End,
+        };
+        match instruction {
+            Instruction::End => return false,
+            Instruction::NewGame(seed) => {
+                self.board = GameBoard::new_game_with_seed(u64::from(seed));
+                self.scene = Scene::Board;
+            }
+            Instruction::Move(direction) => {
+                if self.board.make_move(direction).moved() {
+                    self.board.set_random();
+                }
+                self.scene = Scene::Board;
+            }
+            // This frame is the first of the wait
+            Instruction::Wait(frames) => self.wait_frames = frames.saturating_sub(1),
+            Instruction::Icon(icon) => self.scene = Scene::Icon(icon),
+            Instruction::Number(number) => self.scene = Scene::Number(number),
+            Instruction::Board => self.scene = Scene::Board,
+            Instruction::Loop => self.position = 0,
+        }
+        true
+    }
+
+    /// Get the game being played by the script.
+    pub fn board(&self) -> &GameBoard {
+        &self.board
+    }
+}
+
+impl IntoBoard for Exhibition {
+    fn into_board(&self, context: &RenderContext) -> Board {
+        match self.scene {
+            Scene::Board => self.board.into_board(context),
+            Scene::Icon(icon) => icon.into_board(context),
+            Scene::Number(number) => ScoreBoard::from_score(number).into_board(context),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(
+            Instruction::decode(&[OP_WAIT, 0x2C, 0x01, OP_END]),
+            Ok((Instruction::Wait(300), 3))
+        );
+        assert_eq!(
+            Instruction::decode(&[OP_MOVE, 3]),
+            Ok((Instruction::Move(Direction::ALL[3]), 2))
+        );
+        assert_eq!(
+            Instruction::decode(&[OP_MOVE, 4]),
+            Err(Error::InvalidScript)
+        );
+        assert_eq!(
+            Instruction::decode(&[OP_NUMBER, 1, 2]),
+            Err(Error::InvalidScript)
+        );
+        assert_eq!(Instruction::decode(&[0xFF]), Err(Error::InvalidScript));
+    }
+
+    #[test]
+    fn test_encode() {
+        let instructions = [
+            Instruction::NewGame(u32::MAX),
+            Instruction::Move(Direction::Left),
+            Instruction::Wait(300),
+            Instruction::Icon(Icon::Checksum),
+            Instruction::Loop,
+        ];
+        let script = Script::from_instructions(&instructions).unwrap();
+        let mut position = 0;
+        for &instruction in instructions.iter() {
+            let (decoded, length) = Instruction::decode(&script.bytes[position..]).unwrap();
+            assert_eq!(decoded, instruction);
+            position += length;
+        }
+        assert_eq!(position, script.bytes.len());
+        assert_eq!(
+            Script::from_instructions(&[Instruction::Number(0); SCRIPT_LENGTH]),
+            Err(Error::StorageFull)
+        );
+    }
+
+    #[test]
+    fn test_script_validation() {
+        assert!(Script::new(&[OP_ICON, 1, OP_WAIT, 10, 0, OP_LOOP]).is_ok());
+        assert_eq!(
+            Script::new(&[OP_ICON, 1, OP_WAIT, 10]),
+            Err(Error::InvalidScript)
+        );
+        assert_eq!(
+            Script::new(&[OP_BOARD; SCRIPT_LENGTH + 1]),
+            Err(Error::StorageFull)
+        );
+    }
+
+    #[test]
+    fn test_receiver() {
+        assert!(ScriptReceiver::new(SCRIPT_LENGTH + 1).is_none());
+        let mut receiver = ScriptReceiver::new(3).unwrap();
+        assert_eq!(receiver.push(OP_MOVE), None);
+        assert_eq!(receiver.push(0), None);
+        assert_eq!(
+            receiver.push(OP_END),
+            Some(Script::new(&[OP_MOVE, 0, OP_END]))
+        );
+    }
+
+    #[test]
+    fn test_exhibition() {
+        let script = Script::from_instructions(&[
+            Instruction::NewGame(7),
+            Instruction::Wait(3),
+            Instruction::Move(Direction::Right),
+            Instruction::Number(2048),
+            Instruction::End,
+        ])
+        .unwrap();
+        let mut exhibition = Exhibition::new(script);
+        let mut frames = 0;
+        while exhibition.tick() {
+            frames += 1;
+        }
+        // New game, three frames waiting, move, number
+        assert_eq!(frames, 6);
+        let mut expected = GameBoard::new_game_with_seed(7);
+        expected.make_move(Direction::Right);
+        expected.set_random();
+        assert!(exhibition.board().tiles().eq(expected.tiles()));
+        assert_eq!(exhibition.scene, Scene::Number(2048));
+    }
+
+    #[test]
+    fn test_loop_never_ends() {
+        let script = Script::new(&[OP_ICON, 0, OP_LOOP]).unwrap();
+        let mut exhibition = Exhibition::new(script);
+        assert!((0..100).all(|_| exhibition.tick()));
+        assert_eq!(exhibition.scene, Scene::Icon(Icon::ALL[0]));
+    }
+}
@@ -0,0 +1,172 @@
+use heapless::String;
+use postcard::{from_bytes, to_slice};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diagnostics::StatusLedPolicy, error::Error, input::Shortcut, numerals::NumeralStyle,
+    render::Orientation, theme::Theme,
+};
+
+/// Maximum length of a device's friendly name in bytes.
+pub const NAME_LENGTH: usize = 16;
+
+/// Size of the settings serialized in bytes, rounded up to the next 16 bytes.
+pub const BYTES_SIZE: usize = 32;
+
+/// User configurable settings, persisted separately from the game.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Settings {
+    name: String<NAME_LENGTH>,
+    tutorial_done: bool,
+    ghost_preview: bool,
+    telemetry: bool,
+    status_led: StatusLedPolicy,
+    numerals: NumeralStyle,
+    shortcut: Shortcut,
+    theme: Theme,
+    orientation: Option<Orientation>,
+}
+
+impl Settings {
+    pub fn new() -> Settings {
+        Settings::default()
+    }
+
+    /// Get the device's friendly name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Set the device's friendly name.
+    /// If the name is too long, then no changes are made.
+    pub fn set_name(&mut self, name: &str) -> Result<(), Error> {
+        let mut new_name = String::new();
+        new_name.push_str(name).map_err(|_| Error::NameTooLong)?;
+        self.name = new_name;
+        Ok(())
+    }
+
+    /// Returns true once the first-run tutorial has been completed.
+    pub fn is_tutorial_done(&self) -> bool {
+        self.tutorial_done
+    }
+
+    pub fn set_tutorial_done(&mut self, done: bool) {
+        self.tutorial_done = done;
+    }
+
+    /// Returns true if holding a direction should preview the move before it is made.
+    pub fn is_ghost_preview(&self) -> bool {
+        self.ghost_preview
+    }
+
+    pub fn set_ghost_preview(&mut self, enabled: bool) {
+        self.ghost_preview = enabled;
+    }
+
+    /// Returns true if the user has opted in to keeping usage counters.
+    pub fn is_telemetry(&self) -> bool {
+        self.telemetry
+    }
+
+    pub fn set_telemetry(&mut self, enabled: bool) {
+        self.telemetry = enabled;
+    }
+
+    /// Get what the status LED indicates.
+    pub fn status_led(&self) -> StatusLedPolicy {
+        self.status_led
+    }
+
+    pub fn set_status_led(&mut self, policy: StatusLedPolicy) {
+        self.status_led = policy;
+    }
+
+    /// Get how numbers such as the score are drawn.
+    pub fn numerals(&self) -> NumeralStyle {
+        self.numerals
+    }
+
+    pub fn set_numerals(&mut self, style: NumeralStyle) {
+        self.numerals = style;
+    }
+
+    /// Get what a short press of B does.
+    pub fn shortcut(&self) -> Shortcut {
+        self.shortcut
+    }
+
+    pub fn set_shortcut(&mut self, shortcut: Shortcut) {
+        self.shortcut = shortcut;
+    }
+
+    /// Get which palette tiles are drawn with.
+    pub fn theme(&self) -> Theme {
+        self.theme
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Get which way up the board is, or `None` to detect it at boot.
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.orientation
+    }
+
+    pub fn set_orientation(&mut self, orientation: Option<Orientation>) {
+        self.orientation = orientation;
+    }
+
+    pub fn to_bytes(&self) -> Result<[u8; BYTES_SIZE], Error> {
+        let mut bytes = [0; BYTES_SIZE];
+        to_slice(self, &mut bytes).map_err(|_| Error::StorageFull)?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        from_bytes::<Settings>(bytes).map_err(|_| Error::SaveCorrupt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_name() {
+        let mut settings = Settings::new();
+        assert_eq!(settings.name(), "");
+        assert_eq!(settings.set_name("kitchen"), Ok(()));
+        assert_eq!(settings.name(), "kitchen");
+        assert_eq!(
+            settings.set_name("a name that is far too long"),
+            Err(Error::NameTooLong)
+        );
+        assert_eq!(settings.name(), "kitchen");
+    }
+
+    #[test]
+    fn test_serialisation() {
+        let mut settings = Settings::new();
+        settings.set_name("0123456789abcdef").unwrap();
+        settings.set_tutorial_done(true);
+        settings.set_ghost_preview(true);
+        settings.set_telemetry(true);
+        settings.set_status_led(StatusLedPolicy::Off);
+        settings.set_numerals(NumeralStyle::Dots);
+        settings.set_shortcut(Shortcut::Score);
+        settings.set_theme(Theme::Greyscale);
+        settings.set_orientation(Some(Orientation::Clockwise270));
+        let parsed_settings = Settings::from_bytes(&settings.to_bytes().unwrap()).unwrap();
+        assert_eq!(settings, parsed_settings);
+    }
+
+    #[test]
+    fn test_blank_eeprom() {
+        assert_eq!(
+            Settings::from_bytes(&[0xFF; BYTES_SIZE]),
+            Err(Error::SaveCorrupt)
+        );
+    }
+}
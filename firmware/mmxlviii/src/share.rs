@@ -0,0 +1,149 @@
+use heapless::String;
+
+use crate::{
+    board::{Board, IntoBoard, SIZE},
+    compact::{self, PACKED_SIZE},
+    error::Error,
+    game_board::GameBoard,
+    render::RenderContext,
+};
+
+/// Length of a share code, five bits to a character.
+pub const CODE_LENGTH: usize = (PACKED_SIZE * 8).div_ceil(5);
+
+/// RFC 4648 base32, which survives being read aloud or retyped from a chat message.
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// A finished board, as shared between players.
+///
+/// Shared as a short base32 code, so a position can be pasted from the
+/// console into chat and drawn again by anything that can decode it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SharedBoard {
+    /// Tiles in board index order, as yielded by `GameBoard::tiles`.
+    pub tiles: [u8; SIZE * SIZE],
+    pub score: u32,
+}
+
+impl SharedBoard {
+    pub fn from_board(board: &GameBoard) -> SharedBoard {
+        SharedBoard {
+            tiles: board.tile_array(),
+            score: board.get_score(),
+        }
+    }
+
+    /// Get the board's share code.
+    /// Fails with `InvalidTile` if a tile is larger than 32768 or a blocker.
+    pub fn encode(&self) -> Result<String<CODE_LENGTH>, Error> {
+        let packed = compact::pack(&self.tiles, self.score)?;
+
+        let mut code = String::new();
+        for index in 0..CODE_LENGTH {
+            let symbol = (0..5).fold(0, |symbol, bit| {
+                symbol << 1 | get_bit(&packed, index * 5 + bit)
+            });
+            code.push(char::from(ALPHABET[usize::from(symbol)]))
+                .unwrap();
+        }
+        Ok(code)
+    }
+
+    /// Read a board back from its share code, ignoring case and surrounding whitespace.
+    pub fn decode(code: &str) -> Result<SharedBoard, Error> {
+        let code = code.trim();
+        if code.len() != CODE_LENGTH {
+            return Err(Error::SaveCorrupt);
+        }
+        let mut packed = [0; PACKED_SIZE];
+        for (index, character) in code.bytes().enumerate() {
+            let symbol = ALPHABET
+                .iter()
+                .position(|&letter| letter == character.to_ascii_uppercase())
+                .ok_or(Error::SaveCorrupt)?;
+            for bit in 0..5 {
+                let position = index * 5 + bit;
+                if position < PACKED_SIZE * 8 && symbol & (0x10 >> bit) != 0 {
+                    packed[position / 8] |= 0x80 >> (position % 8);
+                }
+            }
+        }
+        let (tiles, score) = compact::unpack(&packed)?;
+        Ok(SharedBoard { tiles, score })
+    }
+}
+
+/// Get a bit of some bytes, most significant first. Bits past the end are zero.
+fn get_bit(bytes: &[u8], position: usize) -> u8 {
+    bytes
+        .get(position / 8)
+        .map_or(0, |byte| (byte >> (7 - position % 8)) & 1)
+}
+
+impl IntoBoard for SharedBoard {
+    fn into_board(&self, context: &RenderContext) -> Board {
+        GameBoard::with_tiles(self.tiles).into_board(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shared() -> SharedBoard {
+        SharedBoard {
+            tiles: [11, 10, 9, 8, 1, 2, 3, 4, 0, 0, 0, 0, 15, 0, 0, 1],
+            score: 20_480,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let code = shared().encode().unwrap();
+        assert_eq!(code.len(), CODE_LENGTH);
+        assert!(code.bytes().all(|character| ALPHABET.contains(&character)));
+        assert_eq!(SharedBoard::decode(&code), Ok(shared()));
+
+        let mut lower = String::<CODE_LENGTH>::new();
+        for character in code.chars() {
+            lower.push(character.to_ascii_lowercase()).unwrap();
+        }
+        assert_eq!(SharedBoard::decode(&lower), Ok(shared()));
+    }
+
+    #[test]
+    fn test_from_board() {
+        let board = GameBoard::with_tiles(shared().tiles);
+        let code = SharedBoard::from_board(&board).encode().unwrap();
+        let decoded = SharedBoard::decode(&code).unwrap();
+        assert_eq!(decoded.tiles, board.tile_array());
+        assert_eq!(decoded.score, 0);
+    }
+
+    #[test]
+    fn test_tile_too_large() {
+        let mut shared = shared();
+        shared.tiles[0] = 16;
+        assert_eq!(shared.encode(), Err(Error::InvalidTile));
+    }
+
+    #[test]
+    fn test_corrupt_code() {
+        let code = shared().encode().unwrap();
+        let mut typo = String::<CODE_LENGTH>::new();
+        for (index, character) in code.chars().enumerate() {
+            let character = match (index, character) {
+                (0, 'A') => 'B',
+                (0, _) => 'A',
+                (_, character) => character,
+            };
+            typo.push(character).unwrap();
+        }
+        assert_eq!(SharedBoard::decode(&typo), Err(Error::SaveCorrupt));
+        assert_eq!(SharedBoard::decode(&code[1..]), Err(Error::SaveCorrupt));
+        assert_eq!(
+            SharedBoard::decode("!!!!!!!!!!!!!!!!!!!!!!!"),
+            Err(Error::SaveCorrupt)
+        );
+    }
+}
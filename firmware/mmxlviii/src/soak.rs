@@ -0,0 +1,123 @@
+use core::fmt::{self, Display, Formatter};
+
+use rand::{RngCore, SeedableRng};
+use wyhash::WyRng;
+
+use crate::{board::Direction, monotonic::Instant};
+
+/// Number of frames between soak test reports, a minute at 60 frames per second.
+pub const REPORT_FRAMES: u32 = 60 * 60;
+
+/// Plays pseudo-random moves as fast as the limiter allows, to check the
+/// firmware stays stable over hours of play before a release.
+///
+/// Problems seen along the way are counted, and reported with `report`.
+#[derive(Clone)]
+pub struct SoakTest {
+    rng: WyRng,
+    started: Instant,
+    moves: u32,
+    frame_misses: u32,
+}
+
+impl SoakTest {
+    pub fn new(seed: u64, now: Instant) -> SoakTest {
+        SoakTest {
+            rng: WyRng::seed_from_u64(seed),
+            started: now,
+            moves: 0,
+            frame_misses: 0,
+        }
+    }
+
+    /// Choose the next move to play.
+    pub fn next_move(&mut self) -> Direction {
+        self.moves = self.moves.saturating_add(1);
+        Direction::ALL[self.rng.next_u32() as usize % Direction::ALL.len()]
+    }
+
+    /// Note that a frame was drawn late enough to miss its slot.
+    pub fn record_frame_miss(&mut self) {
+        self.frame_misses = self.frame_misses.saturating_add(1);
+    }
+
+    /// Summarise the test so far, along with the storage errors seen since boot.
+    pub fn report(&self, now: Instant, storage_errors: u32) -> SoakReport {
+        SoakReport {
+            seconds: now.since(self.started) / 1000,
+            moves: self.moves,
+            frame_misses: self.frame_misses,
+            storage_errors,
+        }
+    }
+}
+
+/// A summary of a soak test, written as one line for the console.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoakReport {
+    pub seconds: u32,
+    pub moves: u32,
+    pub frame_misses: u32,
+    pub storage_errors: u32,
+}
+
+impl Display for SoakReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "soak {}s: {} moves, {} frame misses, {} storage errors",
+            self.seconds, self.moves, self.frame_misses, self.storage_errors
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::String;
+
+    use super::*;
+    use core::fmt::Write;
+
+    #[test]
+    fn test_moves_are_repeatable() {
+        let mut first = SoakTest::new(7, Instant::default());
+        let mut second = SoakTest::new(7, Instant::default());
+        for _ in 0..32 {
+            assert_eq!(first.next_move(), second.next_move());
+        }
+    }
+
+    #[test]
+    fn test_every_direction_is_played() {
+        let mut soak = SoakTest::new(1, Instant::default());
+        let mut seen = [false; 4];
+        for _ in 0..64 {
+            let direction = soak.next_move();
+            let index = Direction::ALL.iter().position(|&d| d == direction).unwrap();
+            seen[index] = true;
+        }
+        assert_eq!(seen, [true; 4]);
+    }
+
+    #[test]
+    fn test_report() {
+        let mut soak = SoakTest::new(1, Instant::from_millis(1000));
+        soak.next_move();
+        soak.next_move();
+        soak.record_frame_miss();
+        let report = soak.report(Instant::from_millis(62_500), 3);
+        assert_eq!(
+            report,
+            SoakReport {
+                seconds: 61,
+                moves: 2,
+                frame_misses: 1,
+                storage_errors: 3,
+            }
+        );
+
+        let mut line = String::<64>::new();
+        write!(line, "{}", report).unwrap();
+        assert_eq!(line, "soak 61s: 2 moves, 1 frame misses, 3 storage errors");
+    }
+}
@@ -0,0 +1,167 @@
+use postcard::{from_bytes, to_slice};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Error, monotonic::Instant, tile::Tile};
+
+/// Number of splits recorded during a run.
+pub const SPLIT_COUNT: usize = 4;
+
+/// Tiles at which splits are recorded: 256, 512, 1024 and 2048.
+/// Reaching the last one finishes the run.
+pub const SPLIT_TILES: [Tile; SPLIT_COUNT] = [
+    Tile::from_exponent(8),
+    Tile::from_exponent(9),
+    Tile::from_exponent(10),
+    Tile::from_exponent(11),
+];
+
+/// Number of timer ticks in one second.
+pub const TICKS_PER_SECOND: u32 = 10;
+/// Number of milliseconds in one timer tick.
+pub const MILLIS_PER_TICK: u32 = 1000 / TICKS_PER_SECOND;
+
+/// Size of the personal bests serialized in bytes, rounded up to the next 16 bytes.
+pub const BYTES_SIZE: usize = 32;
+
+/// Times of a speedrun, measured in ticks from the first move.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Speedrun {
+    start: Option<Instant>,
+    splits: [Option<u32>; SPLIT_COUNT],
+}
+
+impl Speedrun {
+    pub fn new() -> Speedrun {
+        Speedrun::default()
+    }
+
+    /// Record a move made at `now`, starting the timer on the first move.
+    /// Returns the index of the split reached by this move, if any.
+    pub fn record_move(&mut self, max_tile: Tile, now: Instant) -> Option<usize> {
+        let start = *self.start.get_or_insert(now);
+        let mut reached = None;
+        for (index, (split, &tile)) in self.splits.iter_mut().zip(SPLIT_TILES.iter()).enumerate() {
+            if split.is_none() && max_tile >= tile {
+                *split = Some(now.since(start) / MILLIS_PER_TICK);
+                reached = Some(index);
+            }
+        }
+        reached
+    }
+
+    /// Get the time at which a split was reached.
+    pub fn split(&self, index: usize) -> Option<u32> {
+        self.splits.get(index).copied().flatten()
+    }
+
+    /// Get the time taken for the whole run, once it is finished.
+    pub fn final_time(&self) -> Option<u32> {
+        self.split(SPLIT_COUNT - 1)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.final_time().is_some()
+    }
+}
+
+/// The fastest time for each split, persisted across power cycles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersonalBests {
+    splits: [Option<u32>; SPLIT_COUNT],
+}
+
+impl PersonalBests {
+    pub fn new() -> PersonalBests {
+        PersonalBests::default()
+    }
+
+    /// Get the best time for a split.
+    pub fn split(&self, index: usize) -> Option<u32> {
+        self.splits.get(index).copied().flatten()
+    }
+
+    /// Update the bests with the splits of a run.
+    /// Returns true if any split was improved.
+    pub fn update(&mut self, run: &Speedrun) -> bool {
+        let mut improved = false;
+        for (index, best) in self.splits.iter_mut().enumerate() {
+            if let Some(time) = run.split(index) {
+                if best.is_none_or(|best| time < best) {
+                    *best = Some(time);
+                    improved = true;
+                }
+            }
+        }
+        improved
+    }
+
+    pub fn to_bytes(&self) -> Result<[u8; BYTES_SIZE], Error> {
+        let mut bytes = [0; BYTES_SIZE];
+        to_slice(self, &mut bytes).map_err(|_| Error::StorageFull)?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        from_bytes::<PersonalBests>(bytes).map_err(|_| Error::SaveCorrupt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(tick: u32) -> Instant {
+        Instant::from_millis(tick * MILLIS_PER_TICK)
+    }
+
+    #[test]
+    fn test_timer_starts_on_first_move() {
+        let mut run = Speedrun::new();
+        assert_eq!(run.record_move(Tile::from(2), at(100)), None);
+        assert_eq!(run.record_move(Tile::from(8), at(150)), Some(0));
+        assert_eq!(run.split(0), Some(50));
+        assert_eq!(run.record_move(Tile::from(8), at(160)), None);
+        assert_eq!(run.split(0), Some(50));
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut run = Speedrun::new();
+        for (tick, &tile) in SPLIT_TILES.iter().enumerate() {
+            assert!(!run.is_finished());
+            assert_eq!(run.record_move(tile, at(tick as u32)), Some(tick));
+        }
+        assert!(run.is_finished());
+        assert_eq!(run.final_time(), Some(3));
+    }
+
+    #[test]
+    fn test_personal_bests() {
+        let mut bests = PersonalBests::new();
+        let mut run = Speedrun::new();
+        run.record_move(Tile::EMPTY, at(0));
+        run.record_move(Tile::from(9), at(20));
+        assert!(bests.update(&run));
+        assert_eq!(bests.split(1), Some(20));
+        assert_eq!(bests.split(2), None);
+
+        let mut slower = Speedrun::new();
+        slower.record_move(Tile::EMPTY, at(0));
+        slower.record_move(Tile::from(9), at(30));
+        assert!(!bests.update(&slower));
+        assert_eq!(bests.split(1), Some(20));
+    }
+
+    #[test]
+    fn test_serialisation() {
+        let mut bests = PersonalBests::new();
+        let mut run = Speedrun::new();
+        run.record_move(Tile::EMPTY, at(0));
+        for &tile in SPLIT_TILES.iter() {
+            run.record_move(tile, Instant::from_millis(u32::MAX));
+        }
+        bests.update(&run);
+        let parsed = PersonalBests::from_bytes(&bests.to_bytes().unwrap()).unwrap();
+        assert_eq!(bests, parsed);
+    }
+}
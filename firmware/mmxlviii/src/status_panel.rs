@@ -0,0 +1,103 @@
+use smart_leds::{colors::CYAN, RGB8};
+
+use crate::{
+    board::{Board, Coord, IntoBoard},
+    render::RenderContext,
+    score_board::ScoreBoard,
+    tile::Tile,
+};
+
+/// Number of frames the score is shown for, then the move count.
+pub const FACE_FRAMES: u32 = 180;
+/// Colour of the move count, so it can't be mistaken for the score.
+const MOVES_COLOUR: RGB8 = CYAN;
+/// Row left free by `ScoreBoard`, used for the best tile.
+const BEST_TILE_ROW: usize = 1;
+
+/// The game's progress, shown on a panel of its own beside the game.
+///
+/// The score and move count take turns in the same digits as `ScoreBoard`,
+/// with the best tile's colour along the row between them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatusPanel {
+    pub score: u32,
+    pub moves: u32,
+    pub max_tile: Tile,
+}
+
+/// Show a move count in the same digits as `ScoreBoard`, in a colour of its own.
+pub fn render_move_count(moves: u32, context: &RenderContext) -> Board {
+    let digits = ScoreBoard::from_score(moves).into_board(context);
+    let mut board = Board::new();
+    for (coord, _) in Board::new().diff(&digits) {
+        board.set_led(coord, MOVES_COLOUR);
+    }
+    board
+}
+
+impl IntoBoard for StatusPanel {
+    fn into_board(&self, context: &RenderContext) -> Board {
+        let showing_moves = (context.frame / FACE_FRAMES) % 2 == 1;
+        let mut board = if showing_moves {
+            render_move_count(self.moves, context)
+        } else {
+            ScoreBoard::from_score(self.score).into_board(context)
+        };
+
+        for coord in Coord::row(BEST_TILE_ROW) {
+            board.set_led(coord, context.palette.tile_colour(self.max_tile));
+        }
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smart_leds::colors::BLACK;
+
+    use super::*;
+
+    fn render(panel: &StatusPanel, frame: u32) -> Board {
+        panel.into_board(&RenderContext {
+            frame,
+            ..RenderContext::default()
+        })
+    }
+
+    #[test]
+    fn test_best_tile_row() {
+        let context = RenderContext::default();
+        let panel = StatusPanel {
+            score: 0,
+            moves: 0,
+            max_tile: Tile::from(5),
+        };
+        let board = render(&panel, 0);
+        for coord in Coord::row(BEST_TILE_ROW) {
+            assert_eq!(
+                board.get_led(coord),
+                context.palette.tile_colour(Tile::from(5))
+            );
+        }
+    }
+
+    #[test]
+    fn test_alternates() {
+        let panel = StatusPanel {
+            score: 2048,
+            moves: 2048,
+            max_tile: Tile::EMPTY,
+        };
+        let score = render(&panel, 0);
+        let moves = render(&panel, FACE_FRAMES);
+        assert_ne!(score, moves);
+        assert_eq!(render(&panel, 2 * FACE_FRAMES), score);
+
+        // Both show the same digits, in different colours
+        for coord in Coord::all().filter(|coord| coord.y() != BEST_TILE_ROW) {
+            assert_eq!(score.get_led(coord) == BLACK, moves.get_led(coord) == BLACK);
+            assert!(moves.get_led(coord) == BLACK || moves.get_led(coord) == MOVES_COLOUR);
+        }
+        assert!(Coord::all().any(|coord| moves.get_led(coord) == MOVES_COLOUR));
+    }
+}
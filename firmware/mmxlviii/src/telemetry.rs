@@ -0,0 +1,148 @@
+use postcard::{from_bytes, to_slice};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// Size of the counters serialized in bytes, rounded up to the next 16 bytes.
+pub const BYTES_SIZE: usize = 32;
+
+/// Aggregate usage counters, kept only if the user opts in.
+/// Nothing here identifies a game or when it was played.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counters {
+    pub frames: u32,
+    pub moves: u32,
+    pub undos: u32,
+    pub crashes: u32,
+}
+
+impl Counters {
+    pub fn to_bytes(&self) -> Result<[u8; BYTES_SIZE], Error> {
+        let mut bytes = [0; BYTES_SIZE];
+        to_slice(self, &mut bytes).map_err(|_| Error::StorageFull)?;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        from_bytes::<Counters>(bytes).map_err(|_| Error::SaveCorrupt)
+    }
+}
+
+/// Tracks the counters and whether they have changed enough to be worth saving.
+/// Nothing is counted unless enabled.
+#[derive(Debug, Default)]
+pub struct Telemetry {
+    counters: Counters,
+    enabled: bool,
+    unsaved: bool,
+}
+
+impl Telemetry {
+    pub fn new(counters: Counters, enabled: bool) -> Telemetry {
+        Telemetry {
+            counters,
+            enabled,
+            unsaved: false,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    /// Count a rendered frame.
+    /// Frames alone don't need saving, so an idle device doesn't wear out its storage.
+    pub fn record_frame(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        self.counters.frames = self.counters.frames.saturating_add(1);
+    }
+
+    pub fn record_move(&mut self) {
+        if self.enabled {
+            self.counters.moves = self.counters.moves.saturating_add(1);
+            self.unsaved = true;
+        }
+    }
+
+    pub fn record_undo(&mut self) {
+        if self.enabled {
+            self.counters.undos = self.counters.undos.saturating_add(1);
+            self.unsaved = true;
+        }
+    }
+
+    pub fn record_crash(&mut self) {
+        if self.enabled {
+            self.counters.crashes = self.counters.crashes.saturating_add(1);
+            self.unsaved = true;
+        }
+    }
+
+    /// Returns true if the counters have changed since they were last saved.
+    pub fn needs_saving(&self) -> bool {
+        self.unsaved
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.unsaved = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needs_saving() {
+        let mut telemetry = Telemetry::new(Counters::default(), true);
+        telemetry.record_frame();
+        assert!(!telemetry.needs_saving());
+        telemetry.record_move();
+        assert!(telemetry.needs_saving());
+        telemetry.mark_saved();
+        assert!(!telemetry.needs_saving());
+        telemetry.record_crash();
+        assert!(telemetry.needs_saving());
+
+        assert_eq!(
+            *telemetry.counters(),
+            Counters {
+                frames: 1,
+                moves: 1,
+                undos: 0,
+                crashes: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut telemetry = Telemetry::new(Counters::default(), false);
+        telemetry.record_frame();
+        telemetry.record_move();
+        assert!(!telemetry.needs_saving());
+        assert_eq!(*telemetry.counters(), Counters::default());
+    }
+
+    #[test]
+    fn test_serialisation() {
+        let counters = Counters {
+            frames: u32::MAX,
+            moves: u32::MAX,
+            undos: u32::MAX,
+            crashes: u32::MAX,
+        };
+        let parsed = Counters::from_bytes(&counters.to_bytes().unwrap()).unwrap();
+        assert_eq!(counters, parsed);
+    }
+}
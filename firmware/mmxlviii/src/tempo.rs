@@ -0,0 +1,88 @@
+use crate::{animation::SlideAnimation, monotonic::Instant};
+
+/// Fewest milliseconds between moves, so one bouncy press can't make several moves.
+pub const DEBOUNCE_MILLIS: u32 = 100;
+
+/// Paces moves by the same clock as animations, so input and animation keep the same time.
+///
+/// A move is allowed once the debounce floor has passed and the previous slide
+/// has finished, either by playing out or by being skipped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MoveLimiter {
+    /// When the last move was made.
+    last_move: Option<Instant>,
+}
+
+impl MoveLimiter {
+    pub const fn new() -> MoveLimiter {
+        MoveLimiter { last_move: None }
+    }
+
+    /// Note that a move was made.
+    pub fn record_move(&mut self, now: Instant) {
+        self.last_move = Some(now);
+    }
+
+    /// Returns true if a move may be made now.
+    pub fn is_move_allowed(&self, now: Instant, animation: Option<&SlideAnimation>) -> bool {
+        let debounced = self
+            .last_move
+            .is_none_or(|last_move| now.since(last_move) >= DEBOUNCE_MILLIS);
+        debounced && animation.is_none_or(|animation| animation.is_finished(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        animation::SLIDE_MILLIS,
+        board::Direction,
+        game_board::{GameBoard, MoveOutcome},
+    };
+
+    fn at(millis: u32) -> Instant {
+        Instant::from_millis(millis)
+    }
+
+    #[test]
+    fn test_debounce() {
+        let mut limiter = MoveLimiter::new();
+        assert!(limiter.is_move_allowed(at(0), None));
+        limiter.record_move(at(10));
+        assert!(!limiter.is_move_allowed(at(10), None));
+        assert!(!limiter.is_move_allowed(at(10 + DEBOUNCE_MILLIS - 1), None));
+        assert!(limiter.is_move_allowed(at(10 + DEBOUNCE_MILLIS), None));
+    }
+
+    #[test]
+    fn test_waits_for_animation() {
+        let mut board = GameBoard::with_tiles([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let outcome = board.make_move(Direction::Right);
+        let animation = SlideAnimation::new(outcome, board, at(10));
+
+        let mut limiter = MoveLimiter::new();
+        limiter.record_move(at(10));
+        let longest = SLIDE_MILLIS.max(DEBOUNCE_MILLIS);
+        assert!(!limiter.is_move_allowed(at(10 + longest - 1), Some(&animation)));
+        assert!(limiter.is_move_allowed(at(10 + longest), Some(&animation)));
+    }
+
+    #[test]
+    fn test_skipped_animation() {
+        let animation = SlideAnimation::new(MoveOutcome::default(), GameBoard::empty(), at(10));
+        let mut limiter = MoveLimiter::new();
+        limiter.record_move(at(10));
+        // Once a slide is skipped, only the debounce floor applies
+        assert!(!limiter.is_move_allowed(at(10 + DEBOUNCE_MILLIS), Some(&animation)));
+        assert!(limiter.is_move_allowed(at(10 + DEBOUNCE_MILLIS), None));
+    }
+
+    #[test]
+    fn test_clock_wrap() {
+        let mut limiter = MoveLimiter::new();
+        limiter.record_move(at(u32::MAX));
+        assert!(!limiter.is_move_allowed(at(DEBOUNCE_MILLIS - 2), None));
+        assert!(limiter.is_move_allowed(at(DEBOUNCE_MILLIS - 1), None));
+    }
+}
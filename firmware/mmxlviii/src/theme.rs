@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use smart_leds::{
+    colors::{BLACK, DIM_GRAY, WHITE},
+    hsv::{hsv2rgb, Hsv},
+    RGB8,
+};
+
+use crate::tile::Tile;
+
+/// Number of distinct tile colours. Larger tiles share the last colour.
+pub const PALETTE_SIZE: usize = 14;
+
+/// Blockers are drawn a dim grey whatever the theme, so they look unlike any tile.
+const BLOCKER_COLOUR: RGB8 = RGB8 {
+    r: 0x10,
+    g: 0x10,
+    b: 0x10,
+};
+
+fn colour_with_hue(hue: u8) -> RGB8 {
+    hsv2rgb(Hsv {
+        hue,
+        sat: 255,
+        val: 255,
+    })
+}
+
+/// Which palette tiles are drawn with, chosen on the device.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Theme {
+    /// The palette the device was provisioned with.
+    #[default]
+    Provisioned,
+    /// The default rainbow palette, whatever was provisioned.
+    Rainbow,
+    /// Shades of white, brighter for larger tiles, so tiles can be told apart without colour.
+    Greyscale,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Provisioned, Theme::Rainbow, Theme::Greyscale];
+
+    /// Parse a theme from its name.
+    pub fn parse(name: &str) -> Option<Theme> {
+        match name {
+            "provisioned" => Some(Theme::Provisioned),
+            "rainbow" => Some(Theme::Rainbow),
+            "greyscale" => Some(Theme::Greyscale),
+            _ => None,
+        }
+    }
+
+    /// Get the next theme, wrapping back round to the first.
+    pub fn next(&self) -> Theme {
+        let index = Theme::ALL
+            .iter()
+            .position(|theme| theme == self)
+            .unwrap_or(0);
+        Theme::ALL[(index + 1) % Theme::ALL.len()]
+    }
+
+    /// Get the palette to draw tiles with, given the provisioned one.
+    pub fn palette(&self, provisioned: Palette) -> Palette {
+        match self {
+            Theme::Provisioned => provisioned,
+            Theme::Rainbow => Palette::default(),
+            Theme::Greyscale => Palette::greyscale(),
+        }
+    }
+}
+
+/// The colours used to display each tile value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Palette {
+    colours: [[u8; 3]; PALETTE_SIZE],
+}
+
+impl Palette {
+    /// Create a palette from a colour for each tile value, starting with the empty tile.
+    pub fn new(colours: [RGB8; PALETTE_SIZE]) -> Palette {
+        let mut palette = Palette {
+            colours: [[0; 3]; PALETTE_SIZE],
+        };
+        for (entry, colour) in palette.colours.iter_mut().zip(colours.iter()) {
+            *entry = [colour.r, colour.g, colour.b];
+        }
+        palette
+    }
+
+    /// Create a palette of shades of white, brighter for larger tiles.
+    pub fn greyscale() -> Palette {
+        let mut colours = [BLACK; PALETTE_SIZE];
+        for (value, colour) in colours.iter_mut().enumerate().skip(1) {
+            // Start dim but visible, up to full brightness for the last colour
+            let level = (0x20 + (0xFF - 0x20) * (value - 1) / (PALETTE_SIZE - 2)) as u8;
+            *colour = RGB8::new(level, level, level);
+        }
+        Palette::new(colours)
+    }
+
+    /// Get the colour for a tile, or for a blocker.
+    pub fn tile_colour(&self, tile: Tile) -> RGB8 {
+        if tile.is_blocker() {
+            return BLOCKER_COLOUR;
+        }
+        let [r, g, b] = self.colours[usize::from(tile.exponent()).min(PALETTE_SIZE - 1)];
+        RGB8 { r, g, b }
+    }
+}
+
+/// Keeps the palette last built for a theme, so themes with computed colours
+/// aren't rebuilt every frame.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PaletteCache {
+    cached: Option<(Theme, Palette)>,
+}
+
+impl PaletteCache {
+    pub const fn new() -> PaletteCache {
+        PaletteCache { cached: None }
+    }
+
+    /// Get the palette for a theme, as `Theme::palette`.
+    pub fn palette(&mut self, theme: Theme, provisioned: Palette) -> Palette {
+        // The provisioned palette can change, but costs nothing to use as is
+        if theme == Theme::Provisioned {
+            return provisioned;
+        }
+        match self.cached {
+            Some((cached_theme, palette)) if cached_theme == theme => palette,
+            _ => {
+                let palette = theme.palette(provisioned);
+                self.cached = Some((theme, palette));
+                palette
+            }
+        }
+    }
+}
+
+impl Default for Palette {
+    /// Map blank tiles to be off
+    /// Map 2 to 1024 tiles to rainbow colours
+    /// Map 2048 to 8192 tiles to decreasing shades of white
+    /// Map tiles greater than 8192 to the same gray as 8192
+    fn default() -> Palette {
+        Palette::new([
+            BLACK,              // Empty tile
+            colour_with_hue(0), // 2
+            colour_with_hue(15),
+            colour_with_hue(45),
+            colour_with_hue(75),
+            colour_with_hue(95),
+            colour_with_hue(130),
+            colour_with_hue(175),
+            colour_with_hue(195),
+            colour_with_hue(230),
+            colour_with_hue(250),
+            WHITE, // 2048
+            DIM_GRAY,
+            RGB8 {
+                r: 0x20,
+                g: 0x20,
+                b: 0x20,
+            },
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_board::MAX_TILE;
+
+    #[test]
+    fn test_theme_cycle() {
+        let mut theme = Theme::default();
+        for _ in 0..Theme::ALL.len() {
+            theme = theme.next();
+        }
+        assert_eq!(theme, Theme::default());
+
+        let provisioned = Palette::new([WHITE; PALETTE_SIZE]);
+        assert_eq!(Theme::Provisioned.palette(provisioned), provisioned);
+        assert_eq!(Theme::Rainbow.palette(provisioned), Palette::default());
+    }
+
+    #[test]
+    fn test_palette_cache() {
+        let mut cache = PaletteCache::new();
+        let provisioned = Palette::greyscale();
+        for &theme in Theme::ALL.iter().chain(Theme::ALL.iter()) {
+            assert_eq!(
+                cache.palette(theme, provisioned),
+                theme.palette(provisioned)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Theme::parse("greyscale"), Some(Theme::Greyscale));
+        assert_eq!(Theme::parse("sepia"), None);
+    }
+
+    #[test]
+    fn test_greyscale() {
+        let palette = Palette::greyscale();
+        assert_eq!(palette.tile_colour(Tile::EMPTY), BLACK);
+        assert_eq!(
+            palette.tile_colour(Tile::from(PALETTE_SIZE as u8 - 1)),
+            WHITE
+        );
+        for value in 1..PALETTE_SIZE as u8 - 1 {
+            let (tile, next) = (Tile::from(value), Tile::from(value + 1));
+            assert!(palette.tile_colour(tile).r < palette.tile_colour(next).r);
+        }
+    }
+
+    #[test]
+    fn test_tile_colour() {
+        let palette = Palette::default();
+        let last = palette.tile_colour(Tile::from(13));
+        assert_eq!(palette.tile_colour(Tile::EMPTY), BLACK);
+        assert_eq!(palette.tile_colour(Tile::from(11)), WHITE);
+        assert_eq!(palette.tile_colour(Tile::from(15)), last);
+        assert_eq!(palette.tile_colour(Tile::from(MAX_TILE)), last);
+        assert_eq!(palette.tile_colour(Tile::BLOCKER), BLOCKER_COLOUR);
+    }
+}
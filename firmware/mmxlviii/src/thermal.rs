@@ -0,0 +1,104 @@
+/// Temperature (in °C) above which the LEDs are dimmed.
+pub const THROTTLE_TEMPERATURE: i32 = 55;
+/// Temperature (in °C) below which full brightness is restored.
+pub const RESTORE_TEMPERATURE: i32 = 45;
+
+/// Internal temperature sensor voltage at 25 °C, in mV (STM32F303 datasheet).
+const V25: i32 = 1430;
+/// Internal temperature sensor average slope, in µV/°C (STM32F303 datasheet).
+const AVG_SLOPE: i32 = 4300;
+/// Analog supply voltage, in mV.
+const VDDA: i32 = 3300;
+/// Full scale reading of the 12-bit ADC.
+const ADC_MAX: i32 = 4095;
+
+/// Convert a raw 12-bit ADC reading of the internal temperature sensor to °C.
+pub fn temperature_from_adc(raw: u16) -> i32 {
+    let v_sense = i32::from(raw) * VDDA / ADC_MAX;
+    (V25 - v_sense) * 1000 / AVG_SLOPE + 25
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThermalEvent {
+    /// The temperature exceeded `THROTTLE_TEMPERATURE` and the LEDs have been dimmed.
+    Throttled(i32),
+    /// The temperature fell below `RESTORE_TEMPERATURE` and brightness has been restored.
+    Restored(i32),
+}
+
+/// Tracks whether the LEDs should be dimmed to keep the board cool.
+/// Hysteresis between the throttle and restore temperatures prevents flicker
+/// when hovering around the threshold.
+#[derive(Debug, Default)]
+pub struct ThermalThrottle {
+    throttled: bool,
+}
+
+impl ThermalThrottle {
+    pub fn new() -> ThermalThrottle {
+        ThermalThrottle { throttled: false }
+    }
+
+    /// Returns true if the LEDs are currently being dimmed.
+    pub fn is_throttled(&self) -> bool {
+        self.throttled
+    }
+
+    /// Update the throttle with a new temperature measurement.
+    /// Returns an event only when the throttling state changes.
+    pub fn update(&mut self, temperature: i32) -> Option<ThermalEvent> {
+        if !self.throttled && temperature > THROTTLE_TEMPERATURE {
+            self.throttled = true;
+            Some(ThermalEvent::Throttled(temperature))
+        } else if self.throttled && temperature < RESTORE_TEMPERATURE {
+            self.throttled = false;
+            Some(ThermalEvent::Restored(temperature))
+        } else {
+            None
+        }
+    }
+
+    /// Scale a global brightness according to the throttling state.
+    pub fn scale_brightness(&self, brightness: u8) -> u8 {
+        if self.throttled {
+            brightness / 2
+        } else {
+            brightness
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temperature_from_adc() {
+        // 1430 mV corresponds to 25 °C
+        assert_eq!(temperature_from_adc(1775), 25);
+        // Lower voltages mean hotter
+        assert!(temperature_from_adc(1600) > 25);
+        assert!(temperature_from_adc(1900) < 25);
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let mut throttle = ThermalThrottle::new();
+        assert_eq!(throttle.update(40), None);
+        assert_eq!(throttle.update(56), Some(ThermalEvent::Throttled(56)));
+        assert!(throttle.is_throttled());
+        assert_eq!(throttle.update(60), None);
+        assert_eq!(throttle.update(50), None);
+        assert!(throttle.is_throttled());
+        assert_eq!(throttle.update(44), Some(ThermalEvent::Restored(44)));
+        assert!(!throttle.is_throttled());
+    }
+
+    #[test]
+    fn test_scale_brightness() {
+        let mut throttle = ThermalThrottle::new();
+        assert_eq!(throttle.scale_brightness(32), 32);
+        throttle.update(THROTTLE_TEMPERATURE + 1);
+        assert_eq!(throttle.scale_brightness(32), 16);
+    }
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{game_board::BLOCKER, merge_rule::MergeRule};
+
+/// A tile as a board keeps it, e.g. the exponent 11 for 2048 in the classic
+/// game, so it can't be mistaken for the value itself. What value a tile
+/// stands for depends on the rule it is played by.
+///
+/// Bare exponents, as saved or sent, convert to and from this with `From`.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Tile(u8);
+
+impl Tile {
+    /// An empty space.
+    pub const EMPTY: Tile = Tile(0);
+    /// A space no tile can move into or merge with.
+    pub const BLOCKER: Tile = Tile(BLOCKER);
+
+    pub const fn from_exponent(exponent: u8) -> Tile {
+        Tile(exponent)
+    }
+
+    pub const fn exponent(&self) -> u8 {
+        self.0
+    }
+
+    /// Get the number shown on the tile when played by `rule`, e.g. 2048, or
+    /// 0 for an empty space or a blocker.
+    pub fn value_in<M: MergeRule>(&self, rule: &M) -> u32 {
+        match self.0 {
+            0 | BLOCKER => 0,
+            tile => rule.value(tile),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_blocker(&self) -> bool {
+        self.0 == BLOCKER
+    }
+}
+
+impl From<u8> for Tile {
+    fn from(exponent: u8) -> Tile {
+        Tile(exponent)
+    }
+}
+
+impl From<Tile> for u8 {
+    fn from(tile: Tile) -> u8 {
+        tile.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge_rule::{Doubling, Threes};
+
+    #[test]
+    fn test_value() {
+        assert_eq!(Tile::from_exponent(11).value_in(&Doubling), 2048);
+        assert_eq!(Tile::from_exponent(1).value_in(&Doubling), 2);
+        assert_eq!(Tile::from_exponent(6).value_in(&Threes), 24);
+        assert_eq!(Tile::from_exponent(1).value_in(&Threes), 1);
+        assert_eq!(Tile::EMPTY.value_in(&Doubling), 0);
+        assert!(Tile::EMPTY.is_empty());
+        assert_eq!(Tile::BLOCKER.value_in(&Doubling), 0);
+        assert_eq!(Tile::BLOCKER.value_in(&Threes), 0);
+        assert!(Tile::BLOCKER.is_blocker());
+        assert_eq!(Tile::from_exponent(40).value_in(&Doubling), u32::MAX);
+        assert_eq!(u8::from(Tile::from_exponent(11)), 11);
+    }
+}
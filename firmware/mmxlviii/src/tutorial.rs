@@ -0,0 +1,132 @@
+use smart_leds::colors::WHITE;
+
+use crate::{
+    board::{Board, Coord, Direction, IntoBoard, SIZE},
+    game_board::GameBoard,
+    render::{highlight, RenderContext},
+};
+
+/// One step of the tutorial: a scripted board and the move the player should make.
+pub struct Step {
+    /// Tiles shown at the start of the step, in board index order.
+    pub tiles: [u8; SIZE * SIZE],
+    /// The move that completes the step.
+    pub direction: Direction,
+    /// Indices of the tiles to draw attention to.
+    pub targets: &'static [usize],
+}
+
+/// The tutorial, from simple slides through to merges.
+pub const STEPS: [Step; 3] = [
+    // Slide a single tile across the board
+    Step {
+        tiles: [0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        direction: Direction::Left,
+        targets: &[3],
+    },
+    // Merge two equal tiles in a row
+    Step {
+        tiles: [1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        direction: Direction::Left,
+        targets: &[0, 3],
+    },
+    // Merge two equal tiles in a column
+    Step {
+        tiles: [0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0],
+        direction: Direction::Down,
+        targets: &[5, 13],
+    },
+];
+
+/// Progress through the tutorial.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tutorial {
+    step: u8,
+}
+
+impl Tutorial {
+    pub fn new() -> Tutorial {
+        Tutorial::default()
+    }
+
+    /// Start at a given step, e.g. when resuming from the saved UI state.
+    pub fn from_step(step: u8) -> Tutorial {
+        Tutorial { step }
+    }
+
+    /// Get the index of the current step.
+    pub fn step(&self) -> u8 {
+        self.step
+    }
+
+    /// Returns true once every step has been completed.
+    pub fn is_finished(&self) -> bool {
+        usize::from(self.step) >= STEPS.len()
+    }
+
+    /// Handle a move from the player, advancing if it was the requested one.
+    /// Returns true if the tutorial advanced.
+    pub fn make_move(&mut self, direction: Direction) -> bool {
+        match STEPS.get(usize::from(self.step)) {
+            Some(step) if step.direction == direction => {
+                self.step += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl IntoBoard for Tutorial {
+    fn into_board(&self, context: &RenderContext) -> Board {
+        let step = match STEPS.get(usize::from(self.step)) {
+            Some(step) => step,
+            None => return Board::new(),
+        };
+        let mut board = GameBoard::with_tiles(step.tiles).into_board(context);
+        let targets = step
+            .targets
+            .iter()
+            .filter_map(|&index| Coord::from_index(index).ok());
+        highlight(&mut board, targets, WHITE, context);
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_are_possible() {
+        for (index, step) in STEPS.iter().enumerate() {
+            let mut board = GameBoard::with_tiles(step.tiles);
+            assert!(
+                board.make_move(step.direction).moved(),
+                "step {} can't move",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn test_advance() {
+        let mut tutorial = Tutorial::new();
+        assert!(!tutorial.make_move(Direction::Right));
+        assert_eq!(tutorial.step(), 0);
+
+        for step in STEPS.iter() {
+            assert!(!tutorial.is_finished());
+            assert!(tutorial.make_move(step.direction));
+        }
+        assert!(tutorial.is_finished());
+        assert!(!tutorial.make_move(Direction::Left));
+    }
+
+    #[test]
+    fn test_render_highlights_targets() {
+        let tutorial = Tutorial::new();
+        let board = tutorial.into_board(&RenderContext::default());
+        assert_eq!(board.get_led(Coord::from_index(3).unwrap()), WHITE);
+    }
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{board::Direction, game_board::Variant, puzzles::PuzzleRun};
+
+/// The screen being shown on the board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Screen {
+    #[default]
+    Game,
+    Score,
+    Menu,
+    /// Statistics about the game that just ended.
+    Stats,
+    /// Choosing a puzzle from the pack.
+    PuzzleSelect,
+    /// The first-run tutorial, with the step stored in `menu_item`.
+    Tutorial,
+    /// The final time of a finished speedrun.
+    Speedrun,
+    /// The board's checksum, for comparing boards in tournaments.
+    Checksum,
+}
+
+/// Where the user is in the interface, saved so it can be restored after a power cycle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UiState {
+    pub screen: Screen,
+    /// Index of the selected menu item.
+    pub menu_item: u8,
+    pub variant: Variant,
+    pub paused: bool,
+    /// The puzzle being played, if the board holds a puzzle rather than a normal game.
+    pub puzzle: Option<PuzzleRun>,
+    /// Whether the game is a daily challenge rather than free play.
+    /// Saved at the end of the save, see `Save`.
+    #[serde(skip)]
+    pub daily: bool,
+}
+
+impl UiState {
+    /// Move the selection left or right through `count` items, wrapping at either end.
+    /// Returns false if the direction doesn't change the selection.
+    pub fn cycle_selection(&mut self, direction: Direction, count: u8) -> bool {
+        let current = self.menu_item % count;
+        self.menu_item = match direction {
+            Direction::Left => (current + count - 1) % count,
+            Direction::Right => (current + 1) % count,
+            Direction::Up | Direction::Down => return false,
+        };
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle_selection() {
+        let mut ui = UiState::default();
+        assert!(ui.cycle_selection(Direction::Left, 3));
+        assert_eq!(ui.menu_item, 2);
+        assert!(ui.cycle_selection(Direction::Right, 3));
+        assert_eq!(ui.menu_item, 0);
+        assert!(!ui.cycle_selection(Direction::Up, 3));
+        assert_eq!(ui.menu_item, 0);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{
+    board::Direction,
+    game_board::{GameBoard, MoveOutcome},
+    tile::Tile,
+};
+
+/// Smallest tile whose merge sends junk to the opponent, 64.
+pub const GARBAGE_TILE: Tile = Tile::from_exponent(6);
+
+/// Tile dropped onto a board as junk, a 2.
+pub const JUNK_TILE: Tile = Tile::from_exponent(1);
+
+/// One of the two players of a versus game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Player {
+    One,
+    Two,
+}
+
+impl Player {
+    pub fn opponent(&self) -> Player {
+        match self {
+            Player::One => Player::Two,
+            Player::Two => Player::One,
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Player::One => 0,
+            Player::Two => 1,
+        }
+    }
+}
+
+/// Two games played against each other, each player on their own board.
+///
+/// Every merge making `GARBAGE_TILE` or more attacks the opponent with one
+/// junk tile. Junk waits until the opponent's next move, and is dropped onto
+/// their board after that move's own new tiles. Attacking while junk is
+/// waiting cancels it first, so only what's left over is sent on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersusSession {
+    boards: [GameBoard; 2],
+    incoming: [u8; 2],
+}
+
+impl VersusSession {
+    /// Start both players on the same new game, so neither starts ahead.
+    pub fn new(seed: u64) -> VersusSession {
+        let board = GameBoard::new_game_with_seed(seed);
+        VersusSession::with_boards(board.clone(), board)
+    }
+
+    /// Start from boards already set up, e.g. for a handicap.
+    pub fn with_boards(one: GameBoard, two: GameBoard) -> VersusSession {
+        VersusSession {
+            boards: [one, two],
+            incoming: [0; 2],
+        }
+    }
+
+    pub fn board(&self, player: Player) -> &GameBoard {
+        &self.boards[player.index()]
+    }
+
+    /// Get how many junk tiles are waiting to drop onto a player's board.
+    pub fn incoming(&self, player: Player) -> u8 {
+        self.incoming[player.index()]
+    }
+
+    /// Make a move on a player's board, then add its new tiles and any junk
+    /// waiting for it. Nothing happens if the move doesn't change the board.
+    pub fn make_move(&mut self, player: Player, direction: Direction) -> MoveOutcome {
+        let index = player.index();
+        let board = &mut self.boards[index];
+        let outcome = board.make_move(direction);
+        if !outcome.moved() {
+            return outcome;
+        }
+
+        let attack = garbage(&outcome);
+        let cancelled = attack.min(self.incoming[index]);
+        self.incoming[index] -= cancelled;
+        let sent = &mut self.incoming[player.opponent().index()];
+        *sent = sent.saturating_add(attack - cancelled);
+
+        board.spawn_tiles();
+        for _ in 0..self.incoming[index] {
+            if !board.place_random_tile(JUNK_TILE) {
+                break;
+            }
+        }
+        self.incoming[index] = 0;
+        outcome
+    }
+
+    /// Get the player still able to move once the other can't, or `None` if
+    /// both or neither can.
+    pub fn winner(&self) -> Option<Player> {
+        match (
+            self.board(Player::One).is_game_over(),
+            self.board(Player::Two).is_game_over(),
+        ) {
+            (true, false) => Some(Player::Two),
+            (false, true) => Some(Player::One),
+            _ => None,
+        }
+    }
+}
+
+/// Get how many junk tiles a move sends.
+fn garbage(outcome: &MoveOutcome) -> u8 {
+    outcome
+        .merges()
+        .filter(|slide| slide.result() >= GARBAGE_TILE)
+        .count() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::Coord, board_builder::GameBoardBuilder};
+
+    fn tile_count(board: &GameBoard) -> usize {
+        board.tiles().filter(|(_, tile)| !tile.is_empty()).count()
+    }
+
+    /// Get a board with a pair of 32s, ready to merge into a 64.
+    fn attacking() -> GameBoard {
+        GameBoardBuilder::new()
+            .tile(Coord::new(0, 0).unwrap(), Tile::from(5))
+            .tile(Coord::new(1, 0).unwrap(), Tile::from(5))
+            .seed(1)
+            .build()
+            .unwrap()
+    }
+
+    /// Get a board with a single tile, which can move but not merge.
+    fn defending() -> GameBoard {
+        GameBoardBuilder::new()
+            .tile(Coord::new(3, 0).unwrap(), Tile::from(3))
+            .seed(2)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_garbage() {
+        let mut session = VersusSession::with_boards(attacking(), defending());
+        assert!(session.make_move(Player::One, Direction::Left).moved());
+        assert_eq!(session.incoming(Player::Two), 1);
+        assert_eq!(session.incoming(Player::One), 0);
+
+        // The junk drops after the defender's own new tile
+        assert!(session.make_move(Player::Two, Direction::Left).moved());
+        assert_eq!(session.incoming(Player::Two), 0);
+        assert_eq!(tile_count(session.board(Player::Two)), 3);
+
+        // A move that doesn't change the board lets the junk wait
+        let mut session = VersusSession::with_boards(attacking(), defending());
+        session.make_move(Player::One, Direction::Left);
+        assert!(!session.make_move(Player::Two, Direction::Right).moved());
+        assert_eq!(session.incoming(Player::Two), 1);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut session = VersusSession::with_boards(attacking(), attacking());
+        session.make_move(Player::One, Direction::Left);
+        assert_eq!(session.incoming(Player::Two), 1);
+        session.make_move(Player::Two, Direction::Left);
+        assert_eq!(session.incoming(Player::One), 0);
+        assert_eq!(session.incoming(Player::Two), 0);
+        // Only player two's own new tile was added
+        assert_eq!(tile_count(session.board(Player::Two)), 2);
+    }
+
+    #[test]
+    fn test_winner() {
+        let session = VersusSession::new(3);
+        assert_eq!(session.board(Player::One), session.board(Player::Two));
+        assert_eq!(session.winner(), None);
+
+        let stuck = GameBoard::with_tiles([1, 2, 1, 2, 2, 1, 2, 1, 1, 2, 1, 2, 2, 1, 2, 1]);
+        let session = VersusSession::with_boards(stuck, defending());
+        assert_eq!(session.winner(), Some(Player::Two));
+    }
+}